@@ -23,6 +23,28 @@ pub trait NimbersStorer<GamePosition>: NimbersProvider<GamePosition> {
     fn store_nimber(&mut self, position: GamePosition, nimber: u8);
 }
 
+/// Store nimbers and can be shared by many threads.
+pub trait ConcurrentNimbersStorer<GamePosition>: NimbersProvider<GamePosition> + Sync {
+    /// Saves `nimber` of the given `position`. Can be called concurrently by many threads.
+    fn store_nimber_concurrently(&self, position: GamePosition, nimber: u8);
+}
+
+// Thanks to this, a reference to a shared database can be used by a solver (for example by each of many threads).
+impl<GamePosition, DB: NimbersProvider<GamePosition> + ?Sized> NimbersProvider<GamePosition> for &DB {
+    #[inline(always)]
+    fn get_nimber(&self, position: &GamePosition) -> Option<u8> {
+        (**self).get_nimber(position)
+    }
+}
+
+// Thanks to this, a reference to a concurrent storer can be used as a transposition table of a solver.
+impl<GamePosition, DB: ConcurrentNimbersStorer<GamePosition> + ?Sized> NimbersStorer<GamePosition> for &DB {
+    #[inline(always)]
+    fn store_nimber(&mut self, position: GamePosition, nimber: u8) {
+        self.store_nimber_concurrently(position, nimber)
+    }
+}
+
 impl<GamePosition: Eq + Hash> NimbersProvider<GamePosition> for HashMap<GamePosition, u8> {
     #[inline(always)]
     fn get_nimber(&self, position: &GamePosition) -> Option<u8> {
@@ -150,6 +172,13 @@ impl<GamePosition> NimbersStorer<GamePosition> for () {
     }
 }
 
+impl<GamePosition> ConcurrentNimbersStorer<GamePosition> for () {
+    #[inline(always)]
+    fn store_nimber_concurrently(&self, _position: GamePosition, _nimber: u8) {
+        // Do nothing
+    }
+}
+
 pub trait HasLen {
     fn len(&self) -> usize;
 }
@@ -162,6 +191,10 @@ impl<K, V> HasLen for BTreeMap<K, V> {
     #[inline(always)] fn len(&self) -> usize { BTreeMap::<K, V>::len(self) }
 }
 
+impl<T: HasLen + ?Sized> HasLen for &T {
+    #[inline(always)] fn len(&self) -> usize { (**self).len() }
+}
+
 impl HasLen for () {
     #[inline(always)] fn len(&self) -> usize { 0 }
 }
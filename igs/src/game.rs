@@ -105,6 +105,23 @@ pub trait DecomposableGame: Game {
     }
 }
 
+/// Trait implemented by games with decomposable positions that can be played under the misère convention
+/// (i.e. the player who makes the last move loses).
+///
+/// Under misère play, the components of a position cannot be replaced by their nimbers,
+/// so reductions that are valid only under normal play
+/// (like skipping pairs of identical components) must not be applied by `decompose_misere`.
+pub trait MisereDecomposableGame: DecomposableGame {
+
+    /// Iterator over components of a position that can be decomposable, valid under misère play.
+    type MisereComponents<'s>: Iterator<Item=Self::Position> where Self: 's;
+
+    /// Returns the iterator over the components of the given `position` that can be decomposable.
+    /// The components returned form a position that is equivalent to `position` under misère play.
+    /// Components without moves can be (but do not have to be) skipped.
+    fn decompose_misere(&self, position: &Self::DecomposablePosition) -> Self::MisereComponents<'_>;
+}
+
 
 pub struct TheoreticalSolutions<'a, G: Game + ?Sized> {
     pub game: &'a G
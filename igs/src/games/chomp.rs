@@ -382,6 +382,7 @@ impl Iterator for SliceIterator<'_> {
 
 //impl FusedIterator for SliceIterator<'_> {}
 
+#[derive(Copy, Clone)]
 pub struct FewerBarsFirst;
 
 impl DifficultEvaluator for FewerBarsFirst {
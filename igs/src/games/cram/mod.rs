@@ -8,7 +8,7 @@ pub use moves_iterator::{Cram2ColumnsMovesIterator, CramCenterFirstMovesIterator
 pub use moves_sorter::{CramDifficultEvaluator, LessMovesFirst, SmallerComponentsFirst};
 
 use crate::bit::{lowest_bit_of, repeat_bit_sequence};
-pub use crate::game::{DecomposableGame, Game};
+pub use crate::game::{DecomposableGame, MisereDecomposableGame, Game};
 use crate::games::cram::moves_iterator::CramSimpleMovesIterator;
 use crate::solver::{Solver, SolverForDecomposableGame, StatsCollector};
 use crate::solver::dedicated::BRAspSetSolver;
@@ -95,6 +95,15 @@ impl DecomposableGame for Cram {
     }
 }
 
+impl MisereDecomposableGame for Cram {
+    type MisereComponents<'c> = std::vec::IntoIter<u64>;
+
+    #[inline(always)]
+    fn decompose_misere(&self, position: &Self::DecomposablePosition) -> Self::MisereComponents<'_> {
+        self.split_misere(*position).into_iter()
+    }
+}
+
 impl Cram {
 
     /// Construct Cram game played on board with given size (number of columns and rows).
@@ -484,6 +493,29 @@ impl Cram {
         result
     }
 
+    /// Finds all connected components of empty fields in `p`.
+    /// Skips single field components and normalize the rest.
+    /// Unlike `split`, does not reduce stars and keeps duplicated components,
+    /// so the result is equivalent to `p` also under misère play.
+    fn split_misere(&self, p: u64) -> Vec<u64> {
+        let mut result = Vec::new();
+        let mut rest = p;
+        while rest != 0 {
+            let mut component = lowest_bit_of(rest);
+            loop {
+                let prev = component;
+                component |= (self.shifted_left(component) | self.shifted_right(component) |
+                    self.shifted_down(component) | self.shifted_up(component)) & rest;
+                if prev == component { break; }
+            }
+            rest ^= component;
+            if component & (component - 1) != 0 {  // skip single, isolated empty cells
+                result.push(self.normalized(component));
+            }
+        }
+        result
+    }
+
     /// Checks if star-reduced position `p` consists of exactly one component.
     fn is_connected(&self, p: u64) -> bool {
         let can_look_for_r = !self.forbidden_horizontal_moves(p);
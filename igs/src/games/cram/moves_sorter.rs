@@ -36,6 +36,7 @@ impl<SliceType> From<&EndDb<&Cram, SliceType>> for CramDifficultEvaluator {
 }
 
 /// Move sorter and difficult evaluator which evaluate positions with less components and more empty fields as harder to solve.
+#[derive(Copy, Clone)]
 pub struct SmallerComponentsFirst;
 
 impl DifficultEvaluator for SmallerComponentsFirst {
@@ -49,6 +50,7 @@ impl DifficultEvaluator for SmallerComponentsFirst {
 }
 
 /// Move sorter and difficult evaluator which evaluate positions with less components and more moves as harder to solve.
+#[derive(Copy, Clone)]
 pub struct LessMovesFirst;
 
 impl DifficultEvaluator for LessMovesFirst {
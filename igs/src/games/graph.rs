@@ -0,0 +1,377 @@
+use crate::game::{Game, SimpleGame};
+use crate::nimber_set::NimberSet;
+use crate::moves::DifficultEvaluator;
+use crate::solver::dedicated::DefSolver;
+use crate::solver::{SolverForSimpleGame, Solver, StatsCollector};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::iter::Copied;
+use std::path::Path;
+use std::slice;
+
+/// Magic bytes at the beginning of the binary representation of a graph.
+const BINARY_MAGIC: [u8; 8] = *b"IGSGRAPH";
+
+/// Game played on a finite directed acyclic graph, given explicitly.
+///
+/// Positions are nodes of the graph (numbered from `0`) and moves are its arcs.
+/// The player who cannot move (as the token is at a node without successors) loses.
+///
+/// The graph can be read from a file in text or binary format.
+/// The text format consists of lines `node: successor successor ...`,
+/// and optionally `root: node` line which points the initial position (node `0` by default).
+/// Nodes that are not listed have no successors and empty lines and lines beginning with `#` are ignored.
+/// The nodes can be numbered by any 32-bit integers, not necessarily consecutive;
+/// they are renumbered densely (in increasing order) and their numbers in the text are kept as labels (see [`GraphGame::label`]).
+/// The binary format begins with `IGSGRAPH` followed by little-endian 32-bit integers:
+/// the number of nodes, the root, and then, for each node, the number of its successors followed by the successors.
+///
+/// The (recursive) solvers need the stack proportional to the depth of the searched position
+/// (see [`GraphGame::depths`]), so positions deeper than [`GraphGame::MAX_SEARCH_DEPTH`] should be solved by
+/// [`GraphGame::nimbers`] (or [`GraphGame::misere_nimbers`]) instead, which work in reverse topological order without recursion.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct GraphGame {
+    /// Successors of the node `i` are `successors[first_successor[i]..first_successor[i+1]]`.
+    first_successor: Box<[u32]>,
+    successors: Box<[u32]>,
+    /// The initial position.
+    root: u32,
+    /// The numbers of the nodes in the text the graph has been read from, if they differ from the node indices.
+    labels: Option<Box<[u32]>>
+}
+
+/// Returns `InvalidData` error with the given message.
+fn invalid_data<M: Into<String>>(msg: M) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.into())
+}
+
+impl GraphGame {
+    /// The depth up to which the positions can be safely searched by the recursive solvers,
+    /// also in the threads (with the default 2 MiB stack) of the parallel search and without optimizations.
+    pub const MAX_SEARCH_DEPTH: u32 = 1000;
+
+    /// Constructs the game on the graph whose node `i` has successors `successors[i]`, with the given `root`.
+    /// Returns error if the graph is not acyclic, some successor or the root is not a node,
+    /// or some node has more than 255 successors (as nimbers must fit in `u8`).
+    pub fn new(successors: &[Vec<u32>], root: u32) -> io::Result<Self> {
+        let nodes = successors.len();
+        if nodes == 0 { return Err(invalid_data("the graph has no nodes")); }
+        if nodes > u32::MAX as usize { return Err(invalid_data("the graph has too many nodes")); }
+        if root as usize >= nodes { return Err(invalid_data(format!("the root {} is not a node", root))); }
+        let mut first_successor = Vec::with_capacity(nodes + 1);
+        let mut all_successors = Vec::with_capacity(successors.iter().map(|s| s.len()).sum());
+        first_successor.push(0);
+        for (node, s) in successors.iter().enumerate() {
+            if s.len() > 255 { return Err(invalid_data(format!("node {} has more than 255 successors", node))); }
+            if let Some(v) = s.iter().find(|v| **v as usize >= nodes) {
+                return Err(invalid_data(format!("successor {} of node {} is not a node", v, node)));
+            }
+            all_successors.extend_from_slice(s);
+            first_successor.push(all_successors.len() as u32);
+        }
+        let result = Self { first_successor: first_successor.into_boxed_slice(), successors: all_successors.into_boxed_slice(), root, labels: None };
+        if let Err(node) = result.reverse_topological_order() {
+            return Err(invalid_data(format!("the graph is not acyclic, node {} is on a cycle", node)));
+        }
+        Ok(result)
+    }
+
+    /// Returns the number of nodes.
+    #[inline] pub fn nodes(&self) -> u32 { (self.first_successor.len() - 1) as u32 }
+
+    /// Returns the root (initial position).
+    #[inline] pub fn root(&self) -> u32 { self.root }
+
+    /// Returns the number of the given `node` in the text the graph has been read from (equal to `node` by default).
+    #[inline] pub fn label(&self, node: u32) -> u32 {
+        self.labels.as_ref().map_or(node, |labels| labels[node as usize])
+    }
+
+    /// Returns the labels of all nodes (see [`GraphGame::label`]) or `None` if they are equal to the node indices.
+    #[inline] pub fn labels(&self) -> Option<&[u32]> { self.labels.as_deref() }
+
+    /// Returns the successors of the given `node`.
+    #[inline] pub fn successors_of(&self, node: u32) -> &[u32] {
+        &self.successors[self.first_successor[node as usize] as usize .. self.first_successor[node as usize + 1] as usize]
+    }
+
+    /// Returns all nodes in reverse topological order (each node after all its successors),
+    /// or some node which is on a cycle if the graph is not acyclic.
+    fn reverse_topological_order(&self) -> Result<Vec<u32>, u32> {
+        const UNVISITED: u8 = 0; const ON_STACK: u8 = 1; const DONE: u8 = 2;
+        let mut state = vec![UNVISITED; self.nodes() as usize];
+        let mut order = Vec::with_capacity(self.nodes() as usize);
+        let mut stack = Vec::new();  // (node, index of the next successor to visit)
+        for start in 0..self.nodes() {
+            if state[start as usize] != UNVISITED { continue; }
+            state[start as usize] = ON_STACK;
+            stack.push((start, 0));
+            while let Some((node, next)) = stack.last_mut() {
+                if let Some(s) = self.successors_of(*node).get(*next).copied() {
+                    *next += 1;
+                    match state[s as usize] {
+                        UNVISITED => { state[s as usize] = ON_STACK; stack.push((s, 0)); }
+                        ON_STACK => return Err(s),
+                        _ => {}
+                    }
+                } else {
+                    state[*node as usize] = DONE;
+                    order.push(*node);
+                    stack.pop();
+                }
+            }
+        }
+        Ok(order)
+    }
+
+    /// Returns all nodes in reverse topological order (each node after all its successors).
+    #[inline] fn successors_first(&self) -> Vec<u32> {
+        self.reverse_topological_order().expect("the graph is acyclic")
+    }
+
+    /// Returns the depths of all nodes, i.e. the lengths of the longest paths starting at them.
+    pub fn depths(&self) -> Vec<u32> {
+        let mut depths = vec![0; self.nodes() as usize];
+        for node in self.successors_first() {
+            depths[node as usize] = self.successors_of(node).iter().map(|s| depths[*s as usize] + 1).max().unwrap_or(0);
+        }
+        depths
+    }
+
+    /// Returns the values of all nodes, calculated (without recursion) in reverse topological order
+    /// as the mex of the values of successors, or `terminal` for nodes without successors.
+    fn mex_values(&self, terminal: u8) -> Vec<u8> {
+        let mut values = vec![0; self.nodes() as usize];
+        for node in self.successors_first() {
+            if self.successors_of(node).is_empty() { values[node as usize] = terminal; continue; }
+            let mut options = <Self as Game>::NimberSet::empty();
+            for s in self.successors_of(node) { options.append(values[*s as usize]); }
+            values[node as usize] = options.mex();
+        }
+        values
+    }
+
+    /// Returns the nimbers of all nodes, calculated (without recursion) in reverse topological order.
+    #[inline] pub fn nimbers(&self) -> Vec<u8> { self.mex_values(0) }
+
+    /// Returns the misère Grundy values of all nodes, calculated (without recursion) in reverse topological order.
+    #[inline] pub fn misere_nimbers(&self) -> Vec<u8> { self.mex_values(1) }
+
+    /// Reads the graph in text format (see `GraphGame` documentation).
+    pub fn read_text(text: &str) -> io::Result<Self> {
+        let parse = |v: &str, line_nr: usize| v.parse::<u32>()
+            .map_err(|_| invalid_data(format!("line {}: {} is not a valid node", line_nr, v)));
+        // the lists of successors, as given in the text, are renumbered once all the nodes are known
+        let mut lists = Vec::<(u32, Vec<u32>)>::new();
+        let mut root = 0;
+        for (line_nr, line) in text.lines().enumerate().map(|(i, l)| (i+1, l.trim())) {
+            if line.is_empty() || line.starts_with('#') { continue; }
+            let (head, tail) = line.split_once(':').ok_or_else(|| invalid_data(format!("line {}: ':' expected", line_nr)))?;
+            let head = head.trim();
+            if head == "root" {
+                root = parse(tail.trim(), line_nr)?;
+                continue;
+            }
+            let node = parse(head, line_nr)?;
+            let successors = tail.split_ascii_whitespace().map(|v| parse(v, line_nr)).collect::<io::Result<Vec<_>>>()?;
+            lists.push((node, successors));
+        }
+        let mut labels: Vec<u32> = lists.iter().flat_map(|(node, successors)| successors.iter().copied().chain(Some(*node)))
+            .chain(Some(root)).collect();
+        labels.sort_unstable();
+        labels.dedup();
+        let index: HashMap<u32, u32> = labels.iter().enumerate().map(|(i, label)| (*label, i as u32)).collect();
+        let mut successors = vec![Vec::new(); labels.len()];
+        for (node, list) in lists {
+            successors[index[&node] as usize].extend(list.into_iter().map(|s| index[&s]));
+        }
+        let mut result = Self::new(&successors, index[&root])?;
+        if labels.iter().enumerate().any(|(i, label)| i as u32 != *label) {
+            result.labels = Some(labels.into_boxed_slice());
+        }
+        Ok(result)
+    }
+
+    /// Writes the graph in text format (see `GraphGame` documentation), with the nodes numbered by their labels.
+    pub fn write_text(&self, output: &mut dyn Write) -> io::Result<()> {
+        writeln!(output, "root: {}", self.label(self.root))?;
+        for node in 0..self.nodes() {
+            write!(output, "{}:", self.label(node))?;
+            for s in self.successors_of(node) { write!(output, " {}", self.label(*s))?; }
+            writeln!(output)?;
+        }
+        Ok(())
+    }
+
+    /// Reads the graph in binary format (see `GraphGame` documentation).
+    pub fn read_binary(input: &mut dyn Read) -> io::Result<Self> {
+        let mut magic = [0u8; BINARY_MAGIC.len()];
+        input.read_exact(&mut magic)?;
+        if magic != BINARY_MAGIC { return Err(invalid_data("the file is not a graph in binary format")); }
+        let mut read_u32 = || -> io::Result<u32> {
+            let mut bytes = [0u8; 4];
+            input.read_exact(&mut bytes)?;
+            Ok(u32::from_le_bytes(bytes))
+        };
+        let nodes = read_u32()?;
+        let root = read_u32()?;
+        // the number of nodes is not trusted, the vector grows as the nodes are read
+        let mut successors = Vec::with_capacity((nodes as usize).min(1 << 16));
+        for _ in 0..nodes {
+            let len = read_u32()?;
+            if len > 255 { return Err(invalid_data(format!("node {} has more than 255 successors", successors.len()))); }
+            successors.push((0..len).map(|_| read_u32()).collect::<io::Result<Vec<_>>>()?);
+        }
+        Self::new(&successors, root)
+    }
+
+    /// Writes the graph in binary format (see `GraphGame` documentation), with the nodes numbered by their indices (without labels).
+    pub fn write_binary(&self, output: &mut dyn Write) -> io::Result<()> {
+        output.write_all(&BINARY_MAGIC)?;
+        output.write_all(&self.nodes().to_le_bytes())?;
+        output.write_all(&self.root.to_le_bytes())?;
+        for node in 0..self.nodes() {
+            let successors = self.successors_of(node);
+            output.write_all(&(successors.len() as u32).to_le_bytes())?;
+            for s in successors { output.write_all(&s.to_le_bytes())?; }
+        }
+        Ok(())
+    }
+
+    /// Reads the graph from the file with the given `path`, in binary (recognized by the magic bytes) or text format.
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let content = fs::read(path)?;
+        if content.starts_with(&BINARY_MAGIC) {
+            Self::read_binary(&mut content.as_slice())
+        } else {
+            Self::read_text(std::str::from_utf8(&content).map_err(|_| invalid_data("the file is neither binary nor text graph"))?)
+        }
+    }
+}
+
+impl Game for GraphGame {
+    type Position = u32;
+    type NimberSet = [u64; 4];
+
+    #[inline(always)] fn moves_count(&self, position: &u32) -> u16 {
+        (self.first_successor[*position as usize + 1] - self.first_successor[*position as usize]) as u16
+    }
+
+    #[inline(always)] fn initial_position(&self) -> u32 { self.root }
+}
+
+impl SimpleGame for GraphGame {
+    type Successors<'s> = Copied<slice::Iter<'s, u32>>;
+    type HeuristicallyOrderedSuccessors<'s> = Copied<slice::Iter<'s, u32>>;
+
+    #[inline(always)]
+    fn successors(&self, position: &Self::Position) -> Self::Successors<'_> {
+        self.successors_of(*position).iter().copied()
+    }
+
+    #[inline(always)]
+    fn successors_in_heuristic_ordered(&self, position: &Self::Position) -> Self::HeuristicallyOrderedSuccessors<'_> {
+        self.successors_of(*position).iter().copied()
+    }
+
+    fn solver_with_stats<'s, STATS: 's+StatsCollector>(&'s self, stats: STATS) -> Box<dyn SolverForSimpleGame<Game=Self, StatsCollector=STATS> + 's> {
+        Box::new(DefSolver{
+            solver: Solver::new(self, HashMap::new(), (), FewerMovesFirst, stats)
+        })
+    }
+}
+
+/// Sorts moves of the graph game by the number of moves available after them.
+#[derive(Copy, Clone)]
+pub struct FewerMovesFirst;
+
+impl DifficultEvaluator for FewerMovesFirst {
+    type Game = GraphGame;
+    type PositionDifficult = u16;
+
+    #[inline] fn difficult_of(&self, game: &Self::Game, to_evaluate: &u32) -> Self::PositionDifficult {
+        game.moves_count(to_evaluate)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::def::DefSimpleGameSolver;
+    use crate::solver::br::BRSimpleGameSolver;
+
+    /// Nim heap of size 4 (node `i` is the heap of size `i`), with node 5 that can move to 1 and 3.
+    const TEXT: &str = "# nim\nroot: 4\n1: 0\n2: 0 1\n3: 0 1 2\n4: 0 1 2 3\n\n5: 1 3\n";
+
+    #[test]
+    fn text() {
+        let game = GraphGame::read_text(TEXT).unwrap();
+        assert_eq!(game.nodes(), 6);
+        assert_eq!(game.root(), 4);
+        assert_eq!(game.successors_of(3), &[0, 1, 2]);
+        assert_eq!(game.moves_count(&0), 0);
+        let mut solver = Solver::new(&game, HashMap::new(), (), FewerMovesFirst, ());
+        assert_eq!(solver.nimber_of_initial_def(), 4);
+        assert_eq!((0..6).map(|n| solver.nimber_def(n)).collect::<Vec<_>>(), [0, 1, 2, 3, 4, 0]);
+        assert_eq!(solver.nimber_br_aspset(5), 0);
+        let mut text = Vec::new();
+        game.write_text(&mut text).unwrap();
+        assert_eq!(GraphGame::read_text(std::str::from_utf8(&text).unwrap()).unwrap(), game);
+    }
+
+    #[test]
+    fn binary() {
+        let game = GraphGame::new(&[vec![], vec![0], vec![0, 1]], 2).unwrap();
+        let mut bin = Vec::new();
+        game.write_binary(&mut bin).unwrap();
+        assert_eq!(GraphGame::read_binary(&mut bin.as_slice()).unwrap(), game);
+        assert_eq!(Solver::new(&game, HashMap::new(), (), (), ()).nimber_of_initial_def(), 2);
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(GraphGame::read_text("0: 1\n1: 0").is_err());  // cycle
+        assert!(GraphGame::read_text("0: 0").is_err());  // loop
+        assert!(GraphGame::read_text("0 1").is_err());
+        assert!(GraphGame::new(&[vec![1]], 0).is_err());
+        assert!(GraphGame::new(&[vec![]], 1).is_err());
+        assert!(GraphGame::read_text("0: 5000000000").is_err());
+        let mut huge = BINARY_MAGIC.to_vec();
+        huge.extend_from_slice(&u32::MAX.to_le_bytes());
+        huge.extend_from_slice(&0u32.to_le_bytes());
+        assert!(GraphGame::read_binary(&mut huge.as_slice()).is_err());
+    }
+
+    #[test]
+    fn sparse_labels() {
+        let game = GraphGame::read_text("root: 4000000000\n4000000000: 7 1000\n1000: 7\n").unwrap();
+        assert_eq!(game.nodes(), 3);
+        assert_eq!(game.labels(), Some(&[7, 1000, 4000000000][..]));
+        assert_eq!((game.root(), game.label(game.root())), (2, 4000000000));
+        assert_eq!(game.successors_of(2), &[0, 1]);
+        assert_eq!(game.nimbers(), [0, 1, 2]);
+        let mut text = Vec::new();
+        game.write_text(&mut text).unwrap();
+        assert_eq!(GraphGame::read_text(std::str::from_utf8(&text).unwrap()).unwrap(), game);
+        assert_eq!(GraphGame::read_text(TEXT).unwrap().labels(), None);
+    }
+
+    #[test]
+    fn deep_chain() {
+        let nodes = 200_000u32;
+        let successors: Vec<_> = (0..nodes).map(|i| if i == 0 { vec![] } else { vec![i-1] }).collect();
+        let game = GraphGame::new(&successors, nodes-1).unwrap();
+        assert_eq!(game.depths()[(nodes-1) as usize], nodes-1);
+        let nimbers = game.nimbers();
+        assert!(nimbers.iter().enumerate().all(|(i, n)| *n as usize == i % 2));
+    }
+
+    #[test]
+    fn nimbers_and_depths() {
+        let game = GraphGame::read_text(TEXT).unwrap();
+        assert_eq!(game.nimbers(), [0, 1, 2, 3, 4, 0]);
+        assert_eq!(game.misere_nimbers(), [1, 0, 2, 3, 4, 1]);
+        assert_eq!(game.depths(), [0, 1, 2, 3, 4, 4]);
+    }
+}
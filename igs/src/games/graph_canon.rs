@@ -0,0 +1,112 @@
+use nauty_Traces_sys::{sparsegraph, size_t, optionblk, statsblk, sparsenauty, sortlists_sg, TRUE, SG_FREE, FALSE};
+use std::mem::MaybeUninit;
+
+/// Sparse graph with allocated memory
+#[derive(Debug, Default, Clone)]
+pub struct SparseGraph {
+    pub v: Vec<size_t>,
+    pub d: Vec<::std::os::raw::c_int>,
+    pub e: Vec<::std::os::raw::c_int>,
+}
+
+impl SparseGraph {
+    /// Create a sparse graph with the given number of vertices and edges.
+    pub fn new(vertices: usize, edges: usize) -> Self {
+        SparseGraph {
+            v: vec![0; vertices],
+            d: vec![0; vertices],
+            e: vec![0; edges],
+        }
+    }
+
+    /// Create a sparse graph with the given maximum numbers of neighbours (out degrees) of vertices.
+    pub fn with_max_neighbours_per_vertex<I: IntoIterator<Item=usize>>(max_neighbours: I) -> Self {
+        let mut edges = 0usize;
+        let v: Vec<_> = max_neighbours.into_iter().map(|n| {
+            let result = edges;
+            edges += n;
+            result as _
+        }).collect();
+        let d = vec![0; v.len()];
+        Self { v, d, e: vec![0; edges] }
+    }
+
+    /// Create a sparse graph with the given numbers of vertices and their neighbours (out degrees).
+    pub fn with_max_neighbours(number_of_vertices: usize, max_neighbours_per_vertex: usize) -> Self {
+        Self::with_max_neighbours_per_vertex((0..number_of_vertices).map(|_| max_neighbours_per_vertex))
+    }
+
+    /// Add directed edge.
+    fn add_directed_edge(&mut self, from_vertex: usize, to_vertex: usize) {
+        self.e[self.v[from_vertex] as usize + self.d[from_vertex] as usize] = to_vertex as _;
+        self.d[from_vertex] += 1;
+    }
+
+    /// Add undirected edge.
+    pub fn add_undirected_edge(&mut self, vertex_a: usize, vertex_b: usize) {
+        self.add_directed_edge(vertex_a, vertex_b);
+        self.add_directed_edge(vertex_b, vertex_a);
+    }
+}
+
+impl<'a> std::convert::From<&'a mut SparseGraph> for sparsegraph {
+    fn from(g: &'a mut SparseGraph) -> Self {
+        sparsegraph {
+            nv: g.v.len() as ::std::os::raw::c_int,
+            nde: g.d.iter().map(|v| *v as size_t).sum(),
+            v: g.v.as_mut_ptr(),
+            d: g.d.as_mut_ptr(),
+            e: g.e.as_mut_ptr(),
+            w: std::ptr::null_mut(),
+            vlen: g.v.len() as size_t,
+            dlen: g.d.len() as size_t,
+            elen: g.e.len() as size_t,
+            wlen: 0,
+        }
+    }
+}
+
+
+/// Returns the code of the canonical form (labelling) of the given undirected `graph` with at most 64 vertices,
+/// calculated by nauty. Isomorphic graphs have equal codes.
+pub fn canonical_code(graph: &mut SparseGraph) -> Box<[u8]> {
+    let mut options = optionblk::default_sparse();
+    let mut stats = statsblk::default();
+    options.getcanon = TRUE;
+    options.digraph = FALSE;
+    options.tc_level = 0;
+    //options.schreier = TRUE;
+    let mut lab_to_ignore: [MaybeUninit<::std::os::raw::c_int>; 64] = unsafe { MaybeUninit::uninit().assume_init() };
+    let mut ptn_to_ignore: [MaybeUninit<::std::os::raw::c_int>; 64] = unsafe { MaybeUninit::uninit().assume_init() };
+    let mut orbits_to_ignore: [MaybeUninit<::std::os::raw::c_int>; 64] = unsafe { MaybeUninit::uninit().assume_init() };
+    let mut canonical_graph = sparsegraph::default();
+    unsafe {
+        sparsenauty(
+            &mut graph.into(),
+            lab_to_ignore.as_mut_ptr() as *mut ::std::os::raw::c_int,
+            ptn_to_ignore.as_mut_ptr() as *mut ::std::os::raw::c_int,
+            orbits_to_ignore.as_mut_ptr() as *mut ::std::os::raw::c_int,
+            &mut options,
+            &mut stats,
+            &mut canonical_graph,
+        );
+        sortlists_sg(&mut canonical_graph);
+    }
+
+    // the code consists of the number of vertices followed by the lists of their neighbours separated by u8::MAX
+    let nv = canonical_graph.nv as usize;
+    let mut result = Vec::with_capacity(canonical_graph.nde as usize + nv);
+    result.push(nv as u8);
+    let v = unsafe { std::slice::from_raw_parts_mut(canonical_graph.v, canonical_graph.vlen as usize) };
+    let e = unsafe { std::slice::from_raw_parts_mut(canonical_graph.e, canonical_graph.elen as usize) };
+    let d = unsafe { std::slice::from_raw_parts_mut(canonical_graph.d, canonical_graph.dlen as usize) };
+    for vertex in 0..nv {
+        let beg = v[vertex] as usize;
+        result.extend(e[beg..(beg+d[vertex] as usize)].iter().map(|v|*v as u8));
+        if vertex+1 != nv {
+            result.push(u8::MAX);
+        }
+    }
+    SG_FREE(&mut canonical_graph);
+    result.into_boxed_slice()
+}
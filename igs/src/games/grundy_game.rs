@@ -1,6 +1,6 @@
 use std::{iter::FusedIterator, collections::HashMap};
 
-use crate::{game::{Game, DecomposableGame}, solver::{dedicated::DefSolver, Solver}};
+use crate::{game::{Game, DecomposableGame, MisereDecomposableGame}, solver::{dedicated::DefSolver, Solver}};
 
 /// Grundy's game with associated initial position.
 /// 
@@ -52,6 +52,14 @@ impl DecomposableGame for GrundyGame {
     
 }
 
+impl MisereDecomposableGame for GrundyGame {
+    type MisereComponents<'s> = GrundyGameComponentsIterator where Self: 's;
+
+    #[inline] fn decompose_misere(&self, position: &Self::DecomposablePosition) -> Self::MisereComponents<'_> {
+        GrundyGameComponentsIterator(*position)
+    }
+}
+
 pub struct GrundyGameMovesIterator([u16; 2]);
 
 impl GrundyGameMovesIterator {
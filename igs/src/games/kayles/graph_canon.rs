@@ -0,0 +1,78 @@
+use crate::games::graph_canon::{SparseGraph, canonical_code};
+use crate::dbs::{NimbersProvider, NimbersStorer, HasLen};
+use std::collections::HashMap;
+use super::Kayles;
+
+fn position_to_graph(kayles: &Kayles, position: u64) -> SparseGraph {
+    // calculate position_to_graph_index mapping and number of vertices (graph_size):
+    let mut position_to_graph_index = [0u8; 64];
+    let mut graph_size = 0u8;
+    let mut rest = position;
+    while rest != 0 {
+        let vertex = rest.trailing_zeros();
+        rest &= rest - 1;
+        position_to_graph_index[vertex as usize] = graph_size;
+        graph_size += 1;
+    }
+    let mut result = SparseGraph::with_max_neighbours_per_vertex(
+        super::ones(position).map(|v| (kayles.neighbours(v) & position).count_ones() as usize));
+    // add edges (each once, from the vertex with lower index) to our graph:
+    for v in super::ones(position) {
+        for u in super::ones(kayles.neighbours(v) & position & !(2u64 << v).wrapping_sub(1)) {
+            result.add_undirected_edge(position_to_graph_index[v] as usize, position_to_graph_index[u] as usize);
+        }
+    }
+    result
+}
+
+/// Transposition table for `Kayles` which stores isomorphic components (found by nauty) as single entries.
+pub struct KaylesCanonTT<'k> {
+    kayles: &'k Kayles,
+    db: HashMap<Box<[u8]>, u8>
+}
+
+impl<'k> KaylesCanonTT<'k> {
+    pub fn new(kayles: &'k Kayles) -> Self {
+        Self { kayles, db: HashMap::new() }
+    }
+
+    fn key(&self, position: u64) -> Box<[u8]> {
+        canonical_code(&mut position_to_graph(self.kayles, position))
+    }
+}
+
+impl<'k> NimbersProvider<u64> for KaylesCanonTT<'k> {
+    #[inline(always)] fn get_nimber(&self, position: &u64) -> Option<u8> {
+        self.db.get_nimber(&self.key(*position))
+    }
+}
+
+impl<'k> NimbersStorer<u64> for KaylesCanonTT<'k> {
+    #[inline(always)] fn store_nimber(&mut self, position: u64, nimber: u8) {
+        self.db.store_nimber(self.key(position), nimber)
+    }
+}
+
+impl<'k> HasLen for KaylesCanonTT<'k> {
+    #[inline(always)] fn len(&self) -> usize { self.db.len() }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::games::kayles::KaylesRule;
+
+    #[test]
+    fn isomorphic_components_share_entry() {
+        let kayles = Kayles::grid(KaylesRule::Node, 4, 4).unwrap();
+        let mut tt = KaylesCanonTT::new(&kayles);
+        tt.store_nimber(0b0111, 1);  // path along the first row
+        assert_eq!(tt.get_nimber(&0b1110), Some(1));    // shifted path
+        assert_eq!(tt.get_nimber(&0b1_0001_0001), Some(1)); // path along the first column
+        assert_eq!(tt.get_nimber(&0b1011), None);  // the same number of vertices, but an edge and an isolated vertex
+        assert_eq!(tt.len(), 1);
+        tt.store_nimber(0b0011_0011, 0);  // 2x2 square
+        assert_eq!(tt.get_nimber(&0b1100_1100_0000_0000), Some(0));
+        assert_eq!(tt.len(), 2);
+    }
+}
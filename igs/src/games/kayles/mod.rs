@@ -0,0 +1,405 @@
+use std::collections::HashMap;
+use std::{fmt, fs, io};
+use std::path::Path;
+
+use crate::bit::lowest_bit_of;
+pub use crate::game::{DecomposableGame, MisereDecomposableGame, Game};
+use crate::moves::DifficultEvaluator;
+use crate::solver::{Solver, SolverForDecomposableGame, StatsCollector};
+use crate::solver::dedicated::BRAspSetSolver;
+
+#[cfg(feature = "nauty-Traces-sys")] mod graph_canon;
+#[cfg(feature = "nauty-Traces-sys")] pub use graph_canon::KaylesCanonTT;
+
+/// Rule of a game from Kayles family, played on a graph.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum KaylesRule {
+    /// Node-Kayles: a move removes a vertex together with all its neighbours.
+    /// On a path, it is Dawson's chess (octal game 0.137).
+    Node,
+    /// Dawson's Kayles (also called Arc-Kayles): a move removes two adjacent vertices.
+    /// On a path, it is Dawson's Kayles (octal game 0.07), and on a grid, it is Cram.
+    Dawson
+}
+
+impl fmt::Display for KaylesRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self { KaylesRule::Node => "Node-Kayles", KaylesRule::Dawson => "Dawson's Kayles" })
+    }
+}
+
+/// Game from Kayles family (see `KaylesRule`) played on an undirected graph with at most 64 vertices.
+///
+/// Positions are represented by sets (bitsets) of vertices that are still in the graph.
+/// Each position is decomposed into connected components.
+#[derive(Clone)]
+pub struct Kayles {
+    /// Neighbours of each vertex.
+    neighbours: Box<[u64]>,
+    rule: KaylesRule
+}
+
+impl fmt::Display for Kayles {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} on {} vertices", self.rule, self.neighbours.len())
+    }
+}
+
+/// Returns iterator over indices of ones in `bits`.
+#[inline(always)] fn ones(mut bits: u64) -> impl Iterator<Item=usize> {
+    std::iter::from_fn(move || (bits != 0).then(|| {
+        let index = bits.trailing_zeros() as usize;
+        bits &= bits - 1;
+        index
+    }))
+}
+
+impl Game for Kayles {
+    type Position = u64;
+    type NimberSet = u128;
+
+    #[inline]
+    fn moves_count(&self, position: &u64) -> u16 {
+        match self.rule {
+            KaylesRule::Node => position.count_ones() as u16,
+            KaylesRule::Dawson => (ones(*position).map(|v| (self.neighbours[v] & position).count_ones()).sum::<u32>() / 2) as u16
+        }
+    }
+
+    fn initial_position(&self) -> Self::Position {
+        bitm::n_lowest_bits_0_64(self.neighbours.len() as u8)
+    }
+}
+
+impl_serializable_game_for!(Kayles);
+
+type DecomposedKaylesPosition = arrayvec::ArrayVec::<u64, 64>;
+
+impl DecomposableGame for Kayles {
+    type DecomposablePosition = u64;
+    type Successors<'c> = KaylesMovesIterator<'c>;
+    type HeuristicallyOrderedSuccessors<'c> = KaylesMovesIterator<'c>;
+    type Components<'c> = <DecomposedKaylesPosition as IntoIterator>::IntoIter;
+
+    #[inline(always)]
+    fn successors(&self, position: &Self::Position) -> Self::Successors<'_> {
+        KaylesMovesIterator::new(self, *position)
+    }
+
+    #[inline(always)]
+    fn successors_in_heuristic_ordered(&self, position: &Self::Position) -> Self::HeuristicallyOrderedSuccessors<'_> {
+        KaylesMovesIterator::new(self, *position)
+    }
+
+    #[inline(always)]
+    fn decompose(&self, position: &Self::DecomposablePosition) -> Self::Components<'_> {
+        let mut result = DecomposedKaylesPosition::new();
+        for component in self.components(*position) {
+            // two same components has same values and their xor is 0, we can skip both:
+            if let Some(in_vec_index) = result.iter().position(|x| *x == component) {
+                result.swap_remove(in_vec_index);
+            } else {
+                result.push(component);
+            }
+        }
+        result.into_iter()
+    }
+
+    fn solver_with_stats<'s, STATS: 's+StatsCollector>(&'s self, stats: STATS) -> Box<dyn SolverForDecomposableGame<Game=Self, StatsCollector=STATS> + 's> {
+        Box::new(BRAspSetSolver{
+            solver: Solver::new(self, HashMap::new(), (), SmallerComponentsFirst, stats)
+        })
+    }
+}
+
+impl MisereDecomposableGame for Kayles {
+    type MisereComponents<'c> = std::vec::IntoIter<u64>;
+
+    #[inline(always)]
+    fn decompose_misere(&self, position: &Self::DecomposablePosition) -> Self::MisereComponents<'_> {
+        self.components(*position).collect::<Vec<_>>().into_iter()
+    }
+}
+
+/// Error returned by the constructors of `Kayles` for graphs that are too large.
+const TOO_MANY_VERTICES: &str = "Kayles supports only graphs with no more than 64 vertices";
+
+impl Kayles {
+    /// Constructs the game with the given `rule`, played on the graph with the given number of `vertices` and `edges`.
+    /// Returns error if the graph has more than 64 vertices, an edge is a loop or has an end which is not a vertex,
+    /// or the game has more than 255 moves (which is not supported by the solvers).
+    pub fn new(rule: KaylesRule, vertices: u8, edges: &[(u8, u8)]) -> Result<Self, &'static str> {
+        if vertices > 64 { return Err(TOO_MANY_VERTICES); }
+        let mut neighbours = vec![0u64; vertices as usize].into_boxed_slice();
+        for &(a, b) in edges {
+            if a >= vertices || b >= vertices { return Err("an edge has an end which is not a vertex"); }
+            if a == b { return Err("loops are not supported"); }
+            neighbours[a as usize] |= 1 << b;
+            neighbours[b as usize] |= 1 << a;
+        }
+        let result = Self { neighbours, rule };
+        if result.moves_count(&result.initial_position()) > 255 {
+            return Err("the graph has more than 255 edges, which is not supported by the solvers of Dawson's Kayles");
+        }
+        Ok(result)
+    }
+
+    /// Reads the game with the given `rule`, played on the graph given in text format.
+    ///
+    /// Each line is either `a b`, an edge between vertices `a` and `b`, or `v`, a (possibly isolated) vertex.
+    /// Vertices are numbered from `0` and the graph has as many vertices as the largest number given plus one.
+    /// Empty lines and lines beginning with `#` are ignored.
+    /// Returns `InvalidData` error if the graph has more than 64 vertices or a line is not valid.
+    pub fn read_text(rule: KaylesRule, text: &str) -> io::Result<Self> {
+        let invalid_data = |line_nr: usize, msg: &str| io::Error::new(io::ErrorKind::InvalidData, format!("line {}: {}", line_nr, msg));
+        let mut vertices = 0u8;
+        let mut edges = Vec::new();
+        for (line_nr, line) in text.lines().enumerate().map(|(i, l)| (i+1, l.trim())) {
+            if line.is_empty() || line.starts_with('#') { continue; }
+            let ends = line.split_ascii_whitespace()
+                .map(|v| v.parse::<u8>().ok().filter(|v| *v < 64))
+                .collect::<Option<Vec<_>>>()
+                .ok_or_else(|| invalid_data(line_nr, "vertices must be numbers from 0 to 63"))?;
+            match ends[..] {
+                [v] => vertices = vertices.max(v + 1),
+                [a, b] if a != b => { vertices = vertices.max(a.max(b) + 1); edges.push((a, b)); },
+                [_, _] => return Err(invalid_data(line_nr, "loops are not supported")),
+                _ => return Err(invalid_data(line_nr, "one vertex or two ends of an edge expected"))
+            }
+        }
+        Self::new(rule, vertices, &edges).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    /// Reads the game with the given `rule`, played on the graph read from the file with the given `path`
+    /// (see [`Kayles::read_text`] for the format).
+    pub fn load<P: AsRef<Path>>(rule: KaylesRule, path: P) -> io::Result<Self> {
+        let content = fs::read_to_string(path)?;
+        Self::read_text(rule, &content)
+    }
+
+    /// Constructs the game with the given `rule`, played on the path with `n` vertices.
+    /// Returns error if `n > 64`.
+    pub fn path(rule: KaylesRule, n: u8) -> Result<Self, &'static str> {
+        Self::new(rule, n, &(1..n).map(|v| (v-1, v)).collect::<Vec<_>>())
+    }
+
+    /// Constructs the game with the given `rule`, played on the cycle with `n` vertices.
+    /// Returns error if `n > 64`.
+    pub fn cycle(rule: KaylesRule, n: u8) -> Result<Self, &'static str> {
+        let mut edges: Vec<_> = (1..n).map(|v| (v-1, v)).collect();
+        if n > 2 { edges.push((n-1, 0)); }
+        Self::new(rule, n, &edges)
+    }
+
+    /// Constructs the game with the given `rule`, played on the complete graph with `n` vertices.
+    /// Returns error if `n > 64` or, under Dawson's rule, `n > 23` (as the graph has more than 255 edges).
+    pub fn complete(rule: KaylesRule, n: u8) -> Result<Self, &'static str> {
+        if n > 64 { return Err(TOO_MANY_VERTICES); }
+        Self::new(rule, n, &(0..n).flat_map(|a| (a+1..n).map(move |b| (a, b))).collect::<Vec<_>>())
+    }
+
+    /// Constructs the game with the given `rule`, played on the grid graph with the given number of columns and rows.
+    /// Vertex at column `c` and row `r` has index `r*cols+c`.
+    /// Returns error if the grid has more than 64 vertices.
+    pub fn grid(rule: KaylesRule, cols: u8, rows: u8) -> Result<Self, &'static str> {
+        if cols as u16 * rows as u16 > 64 { return Err(TOO_MANY_VERTICES); }
+        let mut edges = Vec::new();
+        for r in 0..rows {
+            for c in 0..cols {
+                let v = r*cols + c;
+                if c+1 < cols { edges.push((v, v+1)); }
+                if r+1 < rows { edges.push((v, v+cols)); }
+            }
+        }
+        Self::new(rule, cols*rows, &edges)
+    }
+
+    /// Constructs the game with the given `rule`, played on the generalized Petersen graph `G(n, k)`
+    /// (the Petersen graph is `G(5, 2)`).
+    /// Vertices `0..n` form the outer cycle and `n..2n` the inner star polygon.
+    /// Returns error if `n > 32` (as the graph has more than 64 vertices) or `k` is not in range `1..n/2`.
+    pub fn generalized_petersen(rule: KaylesRule, n: u8, k: u8) -> Result<Self, &'static str> {
+        if n > 32 { return Err(TOO_MANY_VERTICES); }
+        if k == 0 || 2*k >= n { return Err("generalized Petersen graph G(n, k) requires 0 < k < n/2"); }
+        let mut edges = Vec::new();
+        for i in 0..n {
+            edges.push((i, (i+1) % n));
+            edges.push((i, n+i));
+            edges.push((n+i, n+(i+k) % n));
+        }
+        Self::new(rule, 2*n, &edges)
+    }
+
+    /// Returns the rule of the game.
+    #[inline(always)] pub fn rule(&self) -> KaylesRule { self.rule }
+
+    /// Returns the number of vertices of the graph.
+    #[inline(always)] pub fn vertices(&self) -> u8 { self.neighbours.len() as u8 }
+
+    /// Returns the neighbours of the vertex `v`.
+    #[inline(always)] pub fn neighbours(&self, v: usize) -> u64 { self.neighbours[v] }
+
+    /// Returns the union of the neighbourhoods of all vertices in `set`.
+    #[inline] fn neighbourhood(&self, set: u64) -> u64 {
+        ones(set).fold(0, |result, v| result | self.neighbours[v])
+    }
+
+    /// Returns iterator over connected components of `position` that have moves.
+    /// Under Node-Kayles rule, all isolated vertices are represented by the same component, `1` (vertex `0`).
+    fn components(&self, mut position: u64) -> impl Iterator<Item=u64> + '_ {
+        std::iter::from_fn(move || loop {
+            if position == 0 { return None; }
+            let mut component = lowest_bit_of(position);
+            loop {
+                let prev = component;
+                component |= self.neighbourhood(component) & position;
+                if prev == component { break; }
+            }
+            position ^= component;
+            if component & (component - 1) != 0 { return Some(component); }
+            if self.rule == KaylesRule::Node { return Some(1); }
+        })
+    }
+}
+
+/// Iterator over successors of a position of `Kayles`.
+pub struct KaylesMovesIterator<'g> {
+    game: &'g Kayles,
+    position: u64,
+    /// Vertices of position that have not been considered yet.
+    rest: u64,
+    /// Under Dawson's rule: the vertex being considered and its neighbours (with greater indices) that have not been considered yet.
+    current: u64,
+    current_neighbours: u64
+}
+
+impl<'g> KaylesMovesIterator<'g> {
+    pub fn new(game: &'g Kayles, position: u64) -> Self {
+        Self { game, position, rest: position, current: 0, current_neighbours: 0 }
+    }
+}
+
+impl Iterator for KaylesMovesIterator<'_> {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.game.rule {
+            KaylesRule::Node => {
+                if self.rest == 0 { return None; }
+                let v = self.rest.trailing_zeros() as usize;
+                self.rest &= self.rest - 1;
+                Some(self.position & !(self.game.neighbours[v] | (1 << v)))
+            }
+            KaylesRule::Dawson => {
+                while self.current_neighbours == 0 {
+                    if self.rest == 0 { return None; }
+                    self.current = lowest_bit_of(self.rest);
+                    self.rest ^= self.current;
+                    self.current_neighbours = self.game.neighbours[self.current.trailing_zeros() as usize] & self.rest;
+                }
+                let neighbour = lowest_bit_of(self.current_neighbours);
+                self.current_neighbours ^= neighbour;
+                Some(self.position & !(self.current | neighbour))
+            }
+        }
+    }
+}
+
+/// Move sorter and difficult evaluator which evaluate positions with more vertices as harder to solve.
+#[derive(Copy, Clone)]
+pub struct SmallerComponentsFirst;
+
+impl DifficultEvaluator for SmallerComponentsFirst {
+    type Game = Kayles;
+    type PositionDifficult = u16;
+
+    fn difficult_of(&self, _game: &Kayles, to_evaluate: &u64) -> Self::PositionDifficult {
+        let v = to_evaluate.count_ones() as u16;
+        v * v
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::games::Cram;
+    use crate::solver::br::BRDecomposableGameSolver;
+    use crate::solver::def::DefDecomposableGameSolver;
+
+    fn nimber(game: &Kayles) -> u8 {
+        let result = Solver::new(game, HashMap::new(), (), SmallerComponentsFirst, ()).nimber_of_initial_br_aspset();
+        assert_eq!(Solver::new(game, HashMap::new(), (), (), ()).nimber_of_initial_def(), result);
+        result
+    }
+
+    #[test]
+    fn dawsons_chess() {   // Node-Kayles on paths, octal game 0.137
+        assert_eq!((0..=17).map(|n| nimber(&Kayles::path(KaylesRule::Node, n).unwrap())).collect::<Vec<_>>(),
+            [0, 1, 1, 2, 0, 3, 1, 1, 0, 3, 3, 2, 2, 4, 0, 5, 2, 2]);
+    }
+
+    #[test]
+    fn dawsons_kayles() {   // octal game 0.07
+        assert_eq!((0..=17).map(|n| nimber(&Kayles::path(KaylesRule::Dawson, n).unwrap())).collect::<Vec<_>>(),
+            [0, 0, 1, 1, 2, 0, 3, 1, 1, 0, 3, 3, 2, 2, 4, 0, 5, 2]);
+    }
+
+    #[test]
+    fn node_kayles_families() {
+        for n in 1..10 { assert_eq!(nimber(&Kayles::complete(KaylesRule::Node, n).unwrap()), 1); }
+        // on a cycle, the first move leaves a path with n-3 vertices
+        for n in 3..12 { assert_eq!(nimber(&Kayles::cycle(KaylesRule::Node, n).unwrap()), u8::from(nimber(&Kayles::path(KaylesRule::Node, n-3).unwrap()) == 0)); }
+        let petersen = Kayles::generalized_petersen(KaylesRule::Node, 5, 2).unwrap();
+        assert_eq!(petersen.moves_count(&petersen.initial_position()), 10);
+        assert_eq!(ones(petersen.initial_position()).map(|v| petersen.neighbours(v).count_ones()).collect::<Vec<_>>(), [3; 10]);
+    }
+
+    #[test]
+    fn grid_with_64_vertices() {
+        let node = Kayles::grid(KaylesRule::Node, 8, 8).unwrap();
+        assert_eq!(node.initial_position(), u64::MAX);
+        assert_eq!(node.moves_count(&node.initial_position()), 64);
+        let dawson = Kayles::grid(KaylesRule::Dawson, 8, 8).unwrap();
+        assert_eq!(dawson.moves_count(&dawson.initial_position()), 2*8*7);
+        assert_eq!(dawson.successors(&dawson.initial_position()).count(), 2*8*7);
+    }
+
+    #[test]
+    fn invalid_graphs() {
+        assert!(Kayles::path(KaylesRule::Node, 64).is_ok());
+        assert!(Kayles::path(KaylesRule::Node, 65).is_err());
+        assert!(Kayles::grid(KaylesRule::Node, 9, 8).is_err());
+        assert!(Kayles::grid(KaylesRule::Node, 255, 255).is_err());
+        assert!(Kayles::generalized_petersen(KaylesRule::Node, 33, 2).is_err());
+        for k in [0, 3, 5, 6] { assert!(Kayles::generalized_petersen(KaylesRule::Node, 5, k).is_err(), "k={}", k); }
+        assert!(Kayles::generalized_petersen(KaylesRule::Node, 6, 3).is_err());
+        assert!(Kayles::generalized_petersen(KaylesRule::Node, 5, 1).is_ok());
+        assert!(Kayles::complete(KaylesRule::Dawson, 23).is_ok());  // 253 edges
+        assert!(Kayles::complete(KaylesRule::Dawson, 24).is_err());
+        assert!(Kayles::complete(KaylesRule::Node, 64).is_ok());
+        assert!(Kayles::new(KaylesRule::Node, 2, &[(0, 2)]).is_err());
+        assert!(Kayles::new(KaylesRule::Node, 2, &[(1, 1)]).is_err());
+    }
+
+    #[test]
+    fn read_text() {
+        let game = Kayles::read_text(KaylesRule::Node, "# path with 3 vertices and an isolated one\n0 1\n1 2\n\n3\n").unwrap();
+        assert_eq!(game.vertices(), 4);
+        assert_eq!(game.neighbours(1), 0b101);
+        assert_eq!(game.neighbours(3), 0);
+        assert_eq!(nimber(&game), 1 ^ nimber(&Kayles::path(KaylesRule::Node, 3).unwrap()));
+        assert!(Kayles::read_text(KaylesRule::Node, "0 64").is_err());
+        assert!(Kayles::read_text(KaylesRule::Node, "1 1").is_err());
+        assert!(Kayles::read_text(KaylesRule::Node, "0 1 2").is_err());
+        assert!(Kayles::read_text(KaylesRule::Node, "0 x").is_err());
+    }
+
+    #[test]
+    fn dawsons_kayles_on_grids_is_cram() {
+        for (cols, rows) in [(2, 2), (3, 2), (3, 3), (4, 3)] {
+            let cram = Cram::new(cols, rows);
+            let expected = Solver::new(&cram, HashMap::new(), (), (), ()).nimber_of_initial_br_aspset();
+            assert_eq!(nimber(&Kayles::grid(KaylesRule::Dawson, cols, rows).unwrap()), expected, "{}x{}", cols, rows);
+        }
+    }
+}
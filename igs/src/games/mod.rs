@@ -1,9 +1,17 @@
 pub mod chomp;
 pub mod chomp_skyline;
 pub mod cram;
+pub mod graph;
 pub mod grundy_game;
+pub mod kayles;
+pub mod take_and_break;
+
+#[cfg(feature = "nauty-Traces-sys")] pub mod graph_canon;
 
 pub use chomp::Chomp;
 pub use chomp_skyline::Chomp as ChompSkyline;
 pub use cram::Cram;
-pub use grundy_game::GrundyGame;
\ No newline at end of file
+pub use graph::GraphGame;
+pub use grundy_game::GrundyGame;
+pub use kayles::{Kayles, KaylesRule};
+pub use take_and_break::TakeAndBreak;
\ No newline at end of file
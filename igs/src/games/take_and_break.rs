@@ -0,0 +1,355 @@
+use std::{collections::HashMap, iter::FusedIterator};
+
+use crate::{game::{Game, DecomposableGame, MisereDecomposableGame}, solver::{dedicated::DefSolver, Solver}, dbs::NimbersProvider, nimber_set::NimberSet};
+
+/// Take-and-break (heap) game, like octal, hexadecimal or subtraction game, with associated initial heap.
+///
+/// Rules of the game are given by a sequence of (hexadecimal) digits, one for each number of tokens.
+/// The bits of the digit `rules[t]` describe the moves that remove `t` tokens from a single heap:
+/// - `1`: the heap can be removed completely (if it has exactly `t` tokens),
+/// - `2`: the heap can be reduced by `t` (leaving one non-empty heap),
+/// - `4`: the rest of the heap can be split into two non-empty heaps,
+/// - `8`: the rest of the heap can be split into three non-empty heaps.
+///
+/// Positions with up to three heaps can be given as `DecomposablePosition` (zeros denote no heap).
+/// The nimber of a position with more heaps is the xor of the nimbers of its heaps.
+///
+/// The solvers support positions with at most [`TakeAndBreak::MAX_MOVES`] moves,
+/// so the initial heap and all smaller heaps must not have more moves (see [`TakeAndBreak::new`]).
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct TakeAndBreak {
+    /// `rules[t]` describes the moves that remove `t` tokens (see `TakeAndBreak` documentation).
+    rules: Box<[u8]>,
+    /// Size of the initial heap.
+    heap: u16
+}
+
+impl TakeAndBreak {
+    /// The largest number of moves of a position supported by the solvers.
+    pub const MAX_MOVES: u16 = u8::MAX as u16;
+
+    /// Constructs the game with the given rules (see `TakeAndBreak` documentation) and the initial `heap`.
+    /// The moves that remove no tokens and leave zero or one heap are ignored.
+    /// Returns error if the initial or a smaller heap has more than [`TakeAndBreak::MAX_MOVES`] moves.
+    pub fn new(rules: &[u8], heap: u16) -> Result<Self, &'static str> {
+        let mut rules = rules.to_vec();
+        if let Some(r) = rules.first_mut() { *r &= !3; }
+        while rules.last() == Some(&0) { rules.pop(); }
+        let result = Self { rules: rules.into_boxed_slice(), heap };
+        if (0..=heap).any(|h| result.moves_count(&h) > Self::MAX_MOVES) {
+            return Err("the heap (or a smaller one) has more than 255 moves, which is not supported by the solvers");
+        }
+        Ok(result)
+    }
+
+    /// Constructs the octal (or hexadecimal) game described by `game` and with the initial `heap`.
+    /// Returns error if some move of `game` leaves more than three heaps or is a restricted split, the code of `game` is infinite,
+    /// or the initial heap has too many moves (see [`TakeAndBreak::new`]).
+    pub fn from_ogs(game: &ogs::Game, heap: u16) -> Result<Self, &'static str> {
+        if game.has_restricted_breaking() { return Err("TakeAndBreak does not support restricted splits"); }
+        if !game.is_code_finite() { return Err("TakeAndBreak does not support infinite codes"); }
+        let rules: Vec<u8> = game.rules().into_iter().map(|d| u8::try_from(d).ok().filter(|d| *d < 16))
+            .collect::<Option<_>>().ok_or("TakeAndBreak supports only moves that leave up to three heaps")?;
+        Self::new(&rules, heap)
+    }
+
+    /// Constructs the game with the initial `heap` and the rules given by octal or hexadecimal `code`, like `0.137`, `4.07` or `0.F`.
+    /// Returns error if the code is invalid or the initial heap has too many moves (see [`TakeAndBreak::new`]).
+    pub fn from_code(code: &str, heap: u16) -> Result<Self, &'static str> {
+        const ERROR: &str = "game code must be in format [X.]DDD... where X is 0, 4, 8 or C, and Ds are (up to 255) hexadecimal digits";
+        let (first, digits) = match code.split_once(['.', ',', 'd']) {
+            Some(("", digits)) => (0, digits),
+            Some((first, digits)) => (u8::from_str_radix(first, 16).map_err(|_| ERROR)?, digits),
+            None => (0, code)
+        };
+        if first & 3 != 0 || digits.len() > 255 { return Err(ERROR); }
+        let mut rules = vec![first];
+        for c in digits.chars() {
+            rules.push(c.to_digit(16).ok_or(ERROR)? as u8);
+        }
+        Self::new(&rules, heap)
+    }
+
+    /// Constructs the subtraction game, in which each move removes from a heap a number of tokens included in `set`,
+    /// with the initial `heap`.
+    /// Returns error if the initial heap has too many moves (see [`TakeAndBreak::new`]).
+    pub fn subtraction(set: &[u8], heap: u16) -> Result<Self, &'static str> {
+        let mut rules = vec![0; set.iter().max().map_or(0, |m| *m as usize + 1)];
+        for s in set { rules[*s as usize] = 3; }
+        Self::new(&rules, heap)
+    }
+
+    /// Returns the rules of the game (see `TakeAndBreak` documentation).
+    #[inline] pub fn rules(&self) -> &[u8] { &self.rules }
+
+    /// Returns the size of the initial heap.
+    #[inline] pub fn heap(&self) -> u16 { self.heap }
+
+    /// Returns the code of the game, like `0.137`.
+    pub fn code(&self) -> String {
+        let digit = |d: &u8| char::from_digit(*d as u32, 16).unwrap().to_ascii_uppercase();
+        let mut result = String::with_capacity(self.rules.len() + 2);
+        result.push(self.rules.first().map_or('0', digit));
+        result.push('.');
+        result.extend(self.rules.iter().skip(1).map(digit));
+        result
+    }
+
+    /// Returns the nimbers of heaps up to `max_heap` (inclusive) or smaller,
+    /// if the nimber of some heap exceeds the range of `u8`.
+    pub fn heap_nimbers(&self, max_heap: u16) -> HeapNimbers {
+        let mut nimbers = Vec::<u8>::with_capacity(max_heap as usize + 1);
+        for heap in 0..=max_heap {
+            let mut options = <Self as Game>::NimberSet::empty();
+            for [a, b, c] in self.successors(&heap) {
+                options.append(nimbers[a as usize] ^ nimbers[b as usize] ^ nimbers[c as usize]);
+            }
+            if options == [u64::MAX; 4] { break; }
+            nimbers.push(options.mex());
+        }
+        HeapNimbers(nimbers.into_boxed_slice())
+    }
+}
+
+impl Game for TakeAndBreak {
+    type Position = u16;
+    type NimberSet = [u64; 4];
+
+    fn moves_count(&self, position: &Self::Position) -> u16 {
+        let mut result = 0u64;
+        for (taken, digit) in self.rules.iter().enumerate().take(*position as usize + 1) {
+            let rest = (*position as usize - taken) as u64;
+            if digit & 1 != 0 && rest == 0 { result += 1; }
+            if digit & 2 != 0 && rest >= 1 { result += 1; }
+            if digit & 4 != 0 { result += rest / 2; }
+            if digit & 8 != 0 { result += (rest * rest + 6) / 12; } // number of partitions of rest into 3 positive parts
+        }
+        result.min(u16::MAX as u64) as u16
+    }
+
+    #[inline] fn initial_position(&self) -> Self::Position {
+        self.heap
+    }
+}
+
+impl DecomposableGame for TakeAndBreak {
+    type DecomposablePosition = [u16; 3];
+
+    type Successors<'s> = TakeAndBreakMovesIterator<'s> where Self: 's;
+
+    type HeuristicallyOrderedSuccessors<'s> = TakeAndBreakMovesIterator<'s> where Self: 's;
+
+    type Components<'s> = arrayvec::IntoIter<u16, 3> where Self: 's;
+
+    fn successors(&self, position: &Self::Position) -> Self::Successors<'_> {
+        Self::Successors::new(&self.rules, *position)
+    }
+
+    fn successors_in_heuristic_ordered(&self, position: &Self::Position) -> Self::HeuristicallyOrderedSuccessors<'_> {
+        Self::HeuristicallyOrderedSuccessors::new(&self.rules, *position)
+    }
+
+    fn decompose(&self, position: &Self::DecomposablePosition) -> Self::Components<'_> {
+        let mut result = arrayvec::ArrayVec::new();
+        for heap in position.iter().copied().filter(|h| *h != 0) {
+            // two same heaps has same values and their xor is 0, we can skip both:
+            if let Some(index) = result.iter().position(|h| *h == heap) {
+                result.swap_remove(index);
+            } else {
+                result.push(heap);
+            }
+        }
+        result.into_iter()
+    }
+
+    fn solver_with_stats<'s, STATS: 's+crate::solver::StatsCollector>(&'s self, stats: STATS) -> Box<dyn crate::solver::SolverForDecomposableGame<Game=Self, StatsCollector=STATS> + 's>
+    {
+        Box::new(DefSolver{solver: Solver::new(self, HashMap::new(), (), (), stats)})
+    }
+}
+
+impl MisereDecomposableGame for TakeAndBreak {
+    type MisereComponents<'s> = arrayvec::IntoIter<u16, 3> where Self: 's;
+
+    #[inline] fn decompose_misere(&self, position: &Self::DecomposablePosition) -> Self::MisereComponents<'_> {
+        position.iter().copied().filter(|h| *h != 0).collect::<arrayvec::ArrayVec<_, 3>>().into_iter()
+    }
+}
+
+/// Iterator over the successors of a heap in `TakeAndBreak`.
+pub struct TakeAndBreakMovesIterator<'r> {
+    rules: &'r [u8],
+    heap: u16,
+    /// The number of tokens taken by the current move.
+    taken: u16,
+    /// The number of heaps left by the current move.
+    heaps: u8,
+    /// The first and the second heap left by the current move.
+    a: u16,
+    b: u16
+}
+
+impl<'r> TakeAndBreakMovesIterator<'r> {
+    pub fn new(rules: &'r [u8], heap: u16) -> Self {
+        Self { rules, heap, taken: 0, heaps: 0, a: 1, b: 1 }
+    }
+}
+
+impl Iterator for TakeAndBreakMovesIterator<'_> {
+    type Item = [u16; 3];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let digit = *self.rules.get(self.taken as usize).filter(|_| self.taken <= self.heap)?;
+            let rest = self.heap - self.taken;
+            match self.heaps {
+                0 => {
+                    self.heaps = 1;
+                    if digit & 1 != 0 && rest == 0 { return Some([0, 0, 0]); }
+                }
+                1 => {
+                    self.heaps = 2;
+                    self.a = 1;
+                    if digit & 2 != 0 && rest >= 1 { return Some([rest, 0, 0]); }
+                }
+                2 => {
+                    if digit & 4 != 0 && self.a <= rest / 2 {
+                        self.a += 1;
+                        return Some([self.a - 1, rest - (self.a - 1), 0]);
+                    }
+                    self.heaps = 3;
+                    self.a = 1;
+                    self.b = 1;
+                }
+                _ => {
+                    if digit & 8 != 0 && 3 * self.a <= rest {
+                        if self.a + 2 * self.b <= rest {   // the third heap is not smaller than the second
+                            self.b += 1;
+                            return Some([self.a, self.b - 1, rest - self.a - (self.b - 1)]);
+                        }
+                        self.a += 1;
+                        self.b = self.a;
+                    } else {
+                        self.taken += 1;
+                        self.heaps = 0;
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl FusedIterator for TakeAndBreakMovesIterator<'_> {}
+
+/// Nimbers of heaps of `TakeAndBreak` from `0` to some size, which can be used as an end database.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct HeapNimbers(pub Box<[u8]>);
+
+impl HeapNimbers {
+    /// Returns the database with the given nimbers (of heaps of size 0, 1, ...), for example calculated by `ogs` solver.
+    /// Only the prefix of nimbers that fit in `u8` is included.
+    pub fn from_nimbers(nimbers: &[ogs::Nimber]) -> Self {
+        Self(nimbers.iter().map_while(|n| u8::try_from(*n).ok()).collect())
+    }
+
+    /// Returns the size of the largest heap included or `None` if the database is empty.
+    #[inline] pub fn max_heap(&self) -> Option<u16> {
+        self.0.len().checked_sub(1).map(|m| m as u16)
+    }
+}
+
+impl NimbersProvider<u16> for HeapNimbers {
+    #[inline(always)] fn get_nimber(&self, position: &u16) -> Option<u8> {
+        self.0.get(*position as usize).copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::{br::BRDecomposableGameSolver, def::DefDecomposableGameSolver, lvb::LVBDecomposableGameSolver};
+    use ogs::Solver as _;
+
+    fn ogs_nimbers(code: &str, n: usize) -> Vec<ogs::Nimber> {
+        ogs::NaiveSolver::<()>::new(code.parse().unwrap()).take(n).collect()
+    }
+
+    /// Checks if igs solvers give the same nimbers as `expected`.
+    fn check(game: &TakeAndBreak, expected: &[ogs::Nimber]) {
+        let mut def = Solver::new(game, HashMap::new(), (), (), ());
+        let mut lvb = Solver::new(game, HashMap::new(), (), (), ());
+        let mut br = Solver::new(game, HashMap::new(), (), (), ());
+        for (heap, nimber) in expected.iter().enumerate() {
+            let heap = heap as u16;
+            let nimber = *nimber as u8;
+            assert_eq!(def.nimber_def([heap, 0, 0]), nimber, "def, {} heap {}", game.code(), heap);
+            assert_eq!(lvb.nimber_lvb([heap, 0, 0]), nimber, "lvb, {} heap {}", game.code(), heap);
+            assert_eq!(br.nimber_br_aspset(&[heap, 0, 0]), nimber, "br, {} heap {}", game.code(), heap);
+        }
+        assert_eq!(game.heap_nimbers(expected.len() as u16 - 1), HeapNimbers::from_nimbers(expected));
+    }
+
+    #[test]
+    fn games_agree_with_ogs() {
+        for code in ["0.137", "0.07", "0.77", "4.07", "0.6", "0.16", "0.4", "0.35", "8.", "0.F", "4.8", "0.B"] {
+            let game = TakeAndBreak::from_code(code, 0).unwrap();
+            assert_eq!(game, TakeAndBreak::from_ogs(&code.parse().unwrap(), 0).unwrap());
+            check(&game, &ogs_nimbers(code, 40));
+        }
+    }
+
+    #[test]
+    fn code() {
+        assert_eq!(TakeAndBreak::from_code("0.137", 3).unwrap().rules(), [0, 1, 3, 7]);
+        assert_eq!(TakeAndBreak::from_code(".137", 3).unwrap().code(), "0.137");
+        assert_eq!(TakeAndBreak::from_code("4.07", 3).unwrap().code(), "4.07");
+        assert_eq!(TakeAndBreak::from_code("0.f", 3).unwrap().code(), "0.F");
+        assert!(TakeAndBreak::from_code("1.07", 3).is_err());
+        assert!(TakeAndBreak::from_code("0.0G", 3).is_err());
+        assert_eq!(TakeAndBreak::subtraction(&[1, 3], 5).unwrap().code(), "0.303");
+        assert!(TakeAndBreak::from_ogs(&"0.+u0".parse().unwrap(), 3).is_err());
+    }
+
+    #[test]
+    fn subtraction() {
+        // subtraction game {1, 2, 3} has nimbers n mod 4
+        check(&TakeAndBreak::subtraction(&[1, 2, 3], 0).unwrap(), &(0..30).map(|n| n % 4).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn hexadecimal() {
+        // 8.: a move splits a heap into three non-empty heaps, without taking (so heaps >= 3 are not terminal)
+        let game = TakeAndBreak::from_code("8.", 7).unwrap();
+        assert_eq!(game.successors(&6).collect::<Vec<_>>(), [[1, 1, 4], [1, 2, 3], [2, 2, 2]]);
+        assert_eq!(game.moves_count(&6), 3);
+        for heap in 0..30 { assert_eq!(game.moves_count(&heap) as usize, game.successors(&heap).count()); }
+        let nimbers = game.heap_nimbers(30);
+        check(&game, &nimbers.0.iter().map(|n| *n as ogs::Nimber).collect::<Vec<_>>());
+        assert_eq!(&nimbers.0[..7], [0, 0, 0, 1, 1, 2, 2]);
+    }
+
+    #[test]
+    fn too_many_moves() {
+        // in 0.4, heap h has (h-1)/2 moves, so heap 512 is the largest one with at most 255 moves
+        let game = TakeAndBreak::from_code("0.4", 512).unwrap();
+        assert_eq!(game.moves_count(&512), 255);
+        let expected = ogs_nimbers("0.4", 513)[512] as u8;
+        assert_eq!(Solver::new(&game, HashMap::new(), (), (), ()).nimber_of_initial_br_aspset(), expected);
+        assert!(TakeAndBreak::from_code("0.4", 513).is_err());
+        assert!(TakeAndBreak::from_code("0.4", 516).is_err());
+        assert!(TakeAndBreak::from_code("0.4", 518).is_err());
+        // the number of moves of large heaps of digit-8 rules does not overflow
+        assert_eq!(TakeAndBreak::from_code("8.", 0).unwrap().moves_count(&u16::MAX), u16::MAX);
+    }
+
+    #[test]
+    fn multiple_heaps() {
+        let game = TakeAndBreak::from_code("0.137", 0).unwrap();
+        assert_eq!(game.decompose(&[5, 0, 5]).count(), 0);
+        assert_eq!(game.decompose(&[5, 3, 5]).collect::<Vec<_>>(), [3]);
+        assert_eq!(game.decompose_misere(&[5, 0, 5]).collect::<Vec<_>>(), [5, 5]);
+        let nimbers = ogs_nimbers("0.137", 20);
+        let mut solver = Solver::new(&game, HashMap::new(), HeapNimbers::from_nimbers(&nimbers[..10]), (), ());
+        assert_eq!(solver.nimber_br(&[12, 14, 17]) as ogs::Nimber, nimbers[12] ^ nimbers[14] ^ nimbers[17]);
+    }
+}
@@ -140,6 +140,7 @@ impl<DE> DecomposableGameMoveSorter<DE::Game> for DE
 }
 
 /// Move sorter that preserve order generated by game methods.
+#[derive(Copy, Clone)]
 pub struct PreserveGeneratedOrder;
 
 impl<G> SimpleGameMoveSorter<G> for PreserveGeneratedOrder where G: SimpleGame {
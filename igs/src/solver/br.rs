@@ -4,6 +4,11 @@ use crate::moves::{SimpleGameMoveSorter, DecomposableGameMoveSorter, ComponentsI
 use crate::dbs::{NimbersProvider, NimbersStorer};
 use crate::nimber_set::{NimberSet, ExtendendNimberSet, WithLowest};
 use crate::stats::{StatsCollector, ProgressReporter};
+use super::checkpoint::{Checkpoint, AspSetState};
+use crate::dbs::ConcurrentNimbersStorer;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::io;
 //use smallvec::{SmallVec};
 
 /// Simple game solver that uses the method described in:
@@ -33,6 +38,15 @@ pub trait BRSimpleGameSolver<G> where G: SimpleGame {
     fn nimber_of_initial_br_aspset(&mut self) -> u8 { 
         self.nimber_of_initial_br_aspset_report_progress(())
     }
+
+    /// Calculates nimber of `position` using aspiration sets method developed by Beling.
+    /// Reports search progress (nimber about to analyze) to `progress_reporter`.
+    /// Periodically saves the state of the top-level loop to `checkpoint`,
+    /// and resumes the search from the state loaded from `checkpoint` (if any).
+    /// Returns error if the checkpoint cannot be loaded or saved, or it has been created for another position.
+    fn nimber_br_aspset_checkpoint<PR: ProgressReporter, CP: Checkpoint>(&mut self, position: G::Position, position_is_winning: Option<bool>, progress_reporter: PR, checkpoint: CP) -> io::Result<u8>;
+
+    fn nimber_of_initial_br_aspset_checkpoint<PR: ProgressReporter, CP: Checkpoint>(&mut self, progress_reporter: PR, checkpoint: CP) -> io::Result<u8>;
 }
 
 /// Decomposable game solver that uses the method described in:
@@ -70,6 +84,61 @@ pub trait BRDecomposableGameSolver<G> where G: DecomposableGame {
     fn nimber_of_initial_br_aspset(&mut self) -> u8 { 
         self.nimber_of_initial_br_aspset_report_progress(())
     }
+
+    /// Calculates nimber of decomposed `position` using aspiration sets method developed by Beling.
+    /// Reports search progress (nimber about to analyze) to `progress_reporter`.
+    /// Periodically saves the state of the top-level loop to `checkpoint`,
+    /// and resumes the search from the state loaded from `checkpoint` (if any).
+    /// Returns error if the checkpoint cannot be loaded or saved, or it has been created for another position.
+    fn nimber_of_component_br_aspset_checkpoint<PR: ProgressReporter, CP: Checkpoint>(&mut self, position: &G::Position, position_is_winning: Option<bool>, progress_reporter: PR, checkpoint: CP) -> io::Result<u8>;
+
+    fn nimber_of_initial_br_aspset_checkpoint<PR: ProgressReporter, CP: Checkpoint>(&mut self, progress_reporter: PR, checkpoint: CP) -> io::Result<u8>;
+}
+
+/// Simple game solver that uses aspiration sets method developed by Beling
+/// and analyses the moves of the root position in parallel, by many threads that share the transposition table.
+///
+/// The result does not depend on the number of threads or their scheduling.
+/// Statistics are collected only for the root position.
+pub trait BRParallelSimpleGameSolver<G> where G: SimpleGame {
+
+    /// Calculates nimber of `position` using aspiration sets method developed by Beling and `threads` threads.
+    /// Reports search progress (nimber about to analyze) to `progress_reporter`.
+    fn nimber_br_aspset_parallel_report_progress<PR: ProgressReporter>(&mut self, position: G::Position, position_is_winning: Option<bool>, threads: usize, progress_reporter: PR) -> u8;
+
+    /// Calculates nimber of `position` using aspiration sets method developed by Beling and `threads` threads.
+    #[inline(always)] fn nimber_br_aspset_parallel(&mut self, position: G::Position, threads: usize) -> u8 {
+        self.nimber_br_aspset_parallel_report_progress(position, None, threads, ())
+    }
+
+    fn nimber_of_initial_br_aspset_parallel_report_progress<PR: ProgressReporter>(&mut self, threads: usize, progress_reporter: PR) -> u8;
+
+    fn nimber_of_initial_br_aspset_parallel(&mut self, threads: usize) -> u8 {
+        self.nimber_of_initial_br_aspset_parallel_report_progress(threads, ())
+    }
+}
+
+/// Decomposable game solver that uses aspiration sets method developed by Beling
+/// and analyses the moves of the root component in parallel, by many threads that share the transposition table.
+///
+/// The result does not depend on the number of threads or their scheduling.
+/// Statistics are collected only for the root component.
+pub trait BRParallelDecomposableGameSolver<G> where G: DecomposableGame {
+
+    /// Calculates nimber of `position` (component) using aspiration sets method developed by Beling and `threads` threads.
+    /// Reports search progress (nimber about to analyze) to `progress_reporter`.
+    fn nimber_of_component_br_aspset_parallel_report_progress<PR: ProgressReporter>(&mut self, position: &G::Position, position_is_winning: Option<bool>, threads: usize, progress_reporter: PR) -> u8;
+
+    /// Calculates nimber of `position` (component) using aspiration sets method developed by Beling and `threads` threads.
+    #[inline(always)] fn nimber_of_component_br_aspset_parallel(&mut self, position: &G::Position, threads: usize) -> u8 {
+        self.nimber_of_component_br_aspset_parallel_report_progress(position, None, threads, ())
+    }
+
+    fn nimber_of_initial_br_aspset_parallel_report_progress<PR: ProgressReporter>(&mut self, threads: usize, progress_reporter: PR) -> u8;
+
+    fn nimber_of_initial_br_aspset_parallel(&mut self, threads: usize) -> u8 {
+        self.nimber_of_initial_br_aspset_parallel_report_progress(threads, ())
+    }
 }
 
 const NOT_IN_SET: u8 = 255;
@@ -267,6 +336,82 @@ impl<G, TT, EDB, SORTER, STATS> BRSimpleGameSolver<G> for Solver<'_, G, TT, EDB,
     fn nimber_of_initial_br_aspset_report_progress<PR: ProgressReporter>(&mut self, progress_reporter: PR) -> u8 {
         self.nimber_br_aspset_report_progress(self.game.initial_position(), self.game.is_initial_position_winning(), progress_reporter)
     }
+
+    fn nimber_br_aspset_checkpoint<PR: ProgressReporter, CP: Checkpoint>(&mut self, position: G::Position, position_is_winning: Option<bool>, mut progress_reporter: PR, mut checkpoint: CP) -> io::Result<u8> {
+        if position_is_winning == Some(false) { checkpoint.finish()?; return Ok(0); }
+
+        self.stats.pre();
+        if let Some(v) = self.nimber_from_any_db(&position) {
+            self.stats.db_cut(v);
+            checkpoint.finish()?;
+            return Ok(v);
+        }
+
+        // unlike etc_simple, the moves are not filtered by databases, so their order does not depend on the content of the TT
+        self.stats.etc();
+        let moves_count = self.game.moves_count(&position);
+        let mut all_moves: Vec::<G::Position> = self.game.successors_in_heuristic_ordered(&position).collect();
+        self.move_sorter.sort_moves(self.game, &mut all_moves);
+        let mut state = AspSetState::new(all_moves.len() as u16, if position_is_winning == Some(true) {1} else {0});
+        if let Some(loaded) = checkpoint.load()? {
+            if loaded.moves != state.moves {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, "the checkpoint has been created for another position"));
+            }
+            state = loaded;
+        }
+        let mut nimbers_to_skip = G::NimberSet::empty();
+        for n in &state.excluded { nimbers_to_skip.append(*n); }
+        let mut moves: Vec::<G::Position> = state.remaining.iter().map(|i| all_moves[*i as usize].clone()).collect();
+        drop(all_moves);
+
+        self.stats.recursive();
+        progress_reporter.begin(moves_count);
+        'results: for result in state.nimber as u16 .. moves_count {
+            progress_reporter.progress(result);
+            debug_assert!(result < 256);
+            let result = result as u8;
+            if nimbers_to_skip.includes(result) { continue; }
+            let mut index = if result == state.nimber { state.checked as usize } else { 0 };
+            state.nimber = result;
+            while index < moves.len() {
+                if checkpoint.is_due() {
+                    state.checked = index as u16;
+                    checkpoint.save(&state)?;
+                }
+                self.stats.pre();
+                let m_nimber = self.simple_nimber_in_set(moves[index].clone(), G::NimberSet::singleton(result));
+                if m_nimber != NOT_IN_SET {
+                    // order of moves must be preserved to keep them consistent with state.remaining
+                    moves.remove(index);
+                    state.remaining.remove(index);
+                    if m_nimber == result {
+                        // as result is nimber of move, it is not nimber of position
+                        continue 'results;
+                    }
+                    nimbers_to_skip.append(m_nimber);
+                    state.excluded.push(m_nimber);
+                } else {
+                    index += 1;
+                }
+            }
+            // no move has nimber = result, so position has it
+            self.transposition_table.store_nimber(position, result);
+            self.stats.exact(result);
+            progress_reporter.end();
+            checkpoint.finish()?;
+            return Ok(result);
+        }
+        debug_assert!(moves_count < 256);
+        let moves_count = moves_count as u8;
+        self.transposition_table.store_nimber(position, moves_count);
+        self.stats.exact(moves_count);
+        checkpoint.finish()?;
+        Ok(moves_count) // we have proved that are lower nimbers are not correct result
+    }
+
+    fn nimber_of_initial_br_aspset_checkpoint<PR: ProgressReporter, CP: Checkpoint>(&mut self, progress_reporter: PR, checkpoint: CP) -> io::Result<u8> {
+        self.nimber_br_aspset_checkpoint(self.game.initial_position(), self.game.is_initial_position_winning(), progress_reporter, checkpoint)
+    }
 }
 
 
@@ -452,4 +597,370 @@ impl<G, TT, EDB, SORTER, STATS, DP> BRDecomposableGameSolver<G> for Solver<'_, G
     fn nimber_of_initial_br_aspset_report_progress<PR: ProgressReporter>(&mut self, progress_reporter: PR) -> u8 {
         self.nimber_of_component_br_aspset_report_progress(&self.game.initial_position(), self.game.is_initial_position_winning(), progress_reporter)
     }
+
+    fn nimber_of_component_br_aspset_checkpoint<PR: ProgressReporter, CP: Checkpoint>(&mut self, position: &G::Position, position_is_winning: Option<bool>, mut progress_reporter: PR, mut checkpoint: CP) -> io::Result<u8> {
+        if position_is_winning == Some(false) { checkpoint.finish()?; return Ok(0); }
+
+        self.stats.pre();
+        if let Some(v) = self.nimber_from_any_db(position) {
+            self.stats.db_cut(v);
+            checkpoint.finish()?;
+            return Ok(v);
+        }
+
+        // unlike etc_decomposable, the components are not filtered by databases, so the order of moves does not depend on the content of the TT
+        self.stats.etc();
+        let moves_count = self.game.moves_count(position);
+        let mut nimbers_to_skip = G::NimberSet::empty();
+        let mut move_components: Vec::<G::Position> = Vec::with_capacity(moves_count as usize * 2);
+        let mut all_moves: Vec::<ComponentsInfo> = Vec::with_capacity(moves_count as usize);
+        for composed_move in self.game.successors_in_heuristic_ordered(position) {
+            let mut info = ComponentsInfo::new(move_components.len());
+            for c in self.game.decompose(&composed_move) {
+                move_components.push(c);
+                info.len += 1;
+            }
+            if info.len == 0 {  // the move leads to the terminal position, with nimber 0
+                nimbers_to_skip.append(0);
+            } else {
+                all_moves.push(info);
+            }
+        }
+        self.move_sorter.sort_moves(self.game, &mut all_moves, &mut move_components);
+        let mut state = AspSetState::new(all_moves.len() as u16, if position_is_winning == Some(true) {1} else {0});
+        if let Some(loaded) = checkpoint.load()? {
+            if loaded.moves != state.moves {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, "the checkpoint has been created for another position"));
+            }
+            state = loaded;
+        }
+        for n in &state.excluded { nimbers_to_skip.append(*n); }
+        let mut moves: Vec::<ComponentsInfo> = state.remaining.iter().map(|i| all_moves[*i as usize]).collect();
+        drop(all_moves);
+
+        self.stats.recursive();
+        progress_reporter.begin(moves_count);
+        'results: for result in state.nimber as u16 .. moves_count {
+            progress_reporter.progress(result);
+            debug_assert!(result < 256);
+            let result = result as u8;
+            if nimbers_to_skip.includes(result) { continue; }
+            let mut index = if result == state.nimber { state.checked as usize } else { 0 };
+            state.nimber = result;
+            while index < moves.len() {
+                if checkpoint.is_due() {
+                    state.checked = index as u16;
+                    checkpoint.save(&state)?;
+                }
+                let m = &mut moves[index];
+                while m.len > 1 {
+                    self.stats.pre();
+                    let component = &move_components[m.first + m.len - 1];
+                    let requested_nimbers = G::NimberSet::with_lowest(self.game.moves_count(component)+1);
+                    m.nimber ^= self.decomposable_nimber_in_set(component, requested_nimbers);
+                    m.len -= 1;
+                }
+                self.stats.pre();
+                let mut m_nimber = self.decomposable_nimber_in_set(&move_components[m.first], G::NimberSet::singleton(result ^ m.nimber));
+                if m_nimber != NOT_IN_SET {
+                    m_nimber ^= m.nimber;
+                    // order of moves must be preserved to keep them consistent with state.remaining
+                    moves.remove(index);
+                    state.remaining.remove(index);
+                    if m_nimber == result {
+                        // as the result is the nimber of move, it is not the nimber of position
+                        continue 'results;
+                    }
+                    nimbers_to_skip.append(m_nimber);
+                    state.excluded.push(m_nimber);
+                } else {
+                    index += 1;
+                }
+            }
+            // no move has nimber = result, so position has it
+            self.transposition_table.store_nimber(position.clone(), result);
+            self.stats.exact(result);
+            progress_reporter.end();
+            checkpoint.finish()?;
+            return Ok(result);
+        }
+        debug_assert!(moves_count < 256);
+        let moves_count = moves_count as u8;
+        self.transposition_table.store_nimber(position.clone(), moves_count);
+        self.stats.exact(moves_count);
+        checkpoint.finish()?;
+        Ok(moves_count) // we have proved that are lower nimbers are not correct result
+    }
+
+    fn nimber_of_initial_br_aspset_checkpoint<PR: ProgressReporter, CP: Checkpoint>(&mut self, progress_reporter: PR, checkpoint: CP) -> io::Result<u8> {
+        self.nimber_of_component_br_aspset_checkpoint(&self.game.initial_position(), self.game.is_initial_position_winning(), progress_reporter, checkpoint)
+    }
+}
+
+
+impl<G, TT, EDB, SORTER, STATS> BRParallelSimpleGameSolver<G> for Solver<'_, G, &TT, EDB, SORTER, STATS>
+    where G: SimpleGame + Sync,
+          TT: ConcurrentNimbersStorer<G::Position>,
+          EDB: NimbersProvider<G::Position> + Sync,
+          SORTER: SimpleGameMoveSorter<G> + Clone + Sync,
+          G::Position: Clone + Send + Sync,
+          STATS: StatsCollector
+{
+    fn nimber_br_aspset_parallel_report_progress<PR: ProgressReporter>(&mut self, position: G::Position, position_is_winning: Option<bool>, threads: usize, mut progress_reporter: PR) -> u8 {
+        if position_is_winning == Some(false) { return 0; }
+
+        self.stats.pre();
+        if let Some(v) = self.nimber_from_any_db(&position) {
+            self.stats.db_cut(v);
+            return v;
+        }
+
+        let (moves_count, mut nimbers_to_skip, mut moves) = self.etc_simple(&position);
+
+        self.stats.recursive();
+        progress_reporter.begin(moves_count);
+        'results: for result in if position_is_winning == Some(true) {1} else {0} .. moves_count {
+            progress_reporter.progress(result);
+            debug_assert!(result < 256);
+            let result = result as u8;
+            if nimbers_to_skip.includes(result) { continue; }
+            // each thread takes the next move to analyze, until a move with nimber = result is found:
+            let next_index = AtomicUsize::new(0);
+            let result_found = AtomicBool::new(false);
+            let known = Mutex::new(Vec::new()); // indices and nimbers of the moves whose nimbers are known
+            let (game, tt, const_db, move_sorter, moves_ref) = (self.game, self.transposition_table, &self.const_db, &self.move_sorter, &moves);
+            std::thread::scope(|scope| {
+                for _ in 0..threads.max(1) {
+                    scope.spawn(|| {
+                        let mut worker = Solver::new(game, tt, const_db, move_sorter.clone(), ());
+                        while !result_found.load(Ordering::Relaxed) {
+                            let index = next_index.fetch_add(1, Ordering::Relaxed);
+                            if index >= moves_ref.len() { break; }
+                            let m_nimber = worker.simple_nimber_in_set(moves_ref[index].clone(), G::NimberSet::singleton(result));
+                            if m_nimber != NOT_IN_SET {
+                                if m_nimber == result { result_found.store(true, Ordering::Relaxed); }
+                                known.lock().unwrap().push((index, m_nimber));
+                            }
+                        }
+                    });
+                }
+            });
+            let mut known = known.into_inner().unwrap();
+            known.sort_unstable_by_key(|(index, _)| std::cmp::Reverse(*index));   // removing from the largest index keeps smaller ones valid
+            for (index, m_nimber) in known {
+                SORTER::remove(&mut moves, index);
+                nimbers_to_skip.append(m_nimber);
+            }
+            if result_found.into_inner() {
+                // as result is nimber of move, it is not nimber of position
+                continue 'results;
+            }
+            // no move has nimber = result, so position has it
+            self.transposition_table.store_nimber(position, result);
+            self.stats.exact(result);
+            progress_reporter.end();
+            return result;
+        }
+        debug_assert!(moves_count < 256);
+        let moves_count = moves_count as u8;
+        self.transposition_table.store_nimber(position, moves_count);
+        self.stats.exact(moves_count);
+        moves_count // we have proved that are lower nimbers are not correct result
+    }
+
+    fn nimber_of_initial_br_aspset_parallel_report_progress<PR: ProgressReporter>(&mut self, threads: usize, progress_reporter: PR) -> u8 {
+        self.nimber_br_aspset_parallel_report_progress(self.game.initial_position(), self.game.is_initial_position_winning(), threads, progress_reporter)
+    }
+}
+
+impl<G, TT, EDB, SORTER, STATS, DP> BRParallelDecomposableGameSolver<G> for Solver<'_, G, &TT, EDB, SORTER, STATS>
+    where G: DecomposableGame<DecomposablePosition=DP> + Sync,
+          TT: ConcurrentNimbersStorer<G::Position>,
+          EDB: NimbersProvider<G::Position> + Sync,
+          SORTER: DecomposableGameMoveSorter<G> + Clone + Sync,
+          G::Position: Clone + Send + Sync,
+          STATS: StatsCollector
+{
+    fn nimber_of_component_br_aspset_parallel_report_progress<PR: ProgressReporter>(&mut self, position: &G::Position, position_is_winning: Option<bool>, threads: usize, mut progress_reporter: PR) -> u8 {
+        if position_is_winning == Some(false) { return 0; }
+
+        self.stats.pre();
+        if let Some(v) = self.nimber_from_any_db(position) {
+            self.stats.db_cut(v);
+            return v;
+        }
+
+        let (moves_count, mut nimbers_to_skip, move_components, mut moves) = self.etc_decomposable(&position);
+
+        self.stats.recursive();
+        progress_reporter.begin(moves_count);
+        'results: for result in if position_is_winning == Some(true) {1} else {0}..moves_count {
+            progress_reporter.progress(result);
+            debug_assert!(result < 256);
+            let result = result as u8;
+            if nimbers_to_skip.includes(result) { continue; }
+            // each thread takes the next move to analyze, until a move with nimber = result is found:
+            let next_index = AtomicUsize::new(0);
+            let result_found = AtomicBool::new(false);
+            // indices, updated infos and nimbers (or NOT_IN_SET) of the moves analyzed:
+            let analyzed = Mutex::new(Vec::new());
+            let (game, tt, const_db, move_sorter, moves_ref, move_components) = (self.game, self.transposition_table, &self.const_db, &self.move_sorter, &moves, &move_components);
+            std::thread::scope(|scope| {
+                for _ in 0..threads.max(1) {
+                    scope.spawn(|| {
+                        let mut worker = Solver::new(game, tt, const_db, move_sorter.clone(), ());
+                        while !result_found.load(Ordering::Relaxed) {
+                            let index = next_index.fetch_add(1, Ordering::Relaxed);
+                            if index >= moves_ref.len() { break; }
+                            let mut m = moves_ref[index];
+                            while m.len > 1 {
+                                let component = &move_components[m.first + m.len - 1];
+                                let requested_nimbers = G::NimberSet::with_lowest(game.moves_count(component)+1);
+                                m.nimber ^= worker.decomposable_nimber_in_set(component, requested_nimbers);
+                                m.len -= 1;
+                            }
+                            let mut m_nimber = worker.decomposable_nimber_in_set(&move_components[m.first], G::NimberSet::singleton(result ^ m.nimber));
+                            if m_nimber != NOT_IN_SET {
+                                m_nimber ^= m.nimber;
+                                if m_nimber == result { result_found.store(true, Ordering::Relaxed); }
+                            }
+                            analyzed.lock().unwrap().push((index, m, m_nimber));
+                        }
+                    });
+                }
+            });
+            let mut analyzed = analyzed.into_inner().unwrap();
+            analyzed.sort_unstable_by_key(|(index, _, _)| std::cmp::Reverse(*index));   // removing from the largest index keeps smaller ones valid
+            for (index, m, m_nimber) in analyzed {
+                if m_nimber == NOT_IN_SET {
+                    moves[index] = m;   // nimbers of all but the first components are already known
+                } else {
+                    SORTER::remove(&mut moves, index);
+                    nimbers_to_skip.append(m_nimber);
+                }
+            }
+            if result_found.into_inner() {
+                // as the result is the nimber of move, it is not the nimber of position
+                continue 'results;
+            }
+            // no move has nimber = result, so position has it
+            self.transposition_table.store_nimber(position.clone(), result);
+            self.stats.exact(result);
+            progress_reporter.end();
+            return result;
+        }
+        debug_assert!(moves_count < 256);
+        let moves_count = moves_count as u8;
+        self.transposition_table.store_nimber(position.clone(), moves_count);
+        self.stats.exact(moves_count);
+        moves_count // we have proved that are lower nimbers are not correct result
+    }
+
+    fn nimber_of_initial_br_aspset_parallel_report_progress<PR: ProgressReporter>(&mut self, threads: usize, progress_reporter: PR) -> u8 {
+        self.nimber_of_component_br_aspset_parallel_report_progress(&self.game.initial_position(), self.game.is_initial_position_winning(), threads, progress_reporter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::def::{DefSimpleGameSolver, DefDecomposableGameSolver};
+    use crate::transposition_table::ShardedTT;
+    use crate::games::{Chomp, Cram, GrundyGame};
+    use crate::games::cram::SmallerComponentsFirst;
+    use crate::games::chomp::FewerBarsFirst;
+    use std::collections::HashMap;
+
+    #[test]
+    fn parallel_cram() {
+        for (cols, rows) in [(3, 3), (4, 3), (5, 3), (4, 4), (5, 4)] {
+            let cram = Cram::new(cols, rows);
+            let expected = Solver::new(&cram, HashMap::new(), (), (), ()).nimber_of_initial_def();
+            for threads in [1, 2, 4] {
+                let tt = ShardedTT::new(4, HashMap::new);
+                let mut solver = Solver::new(&cram, &tt, (), SmallerComponentsFirst{}, ());
+                assert_eq!(solver.nimber_of_component_br_aspset_parallel(&cram.initial_position(), threads), expected, "Cram {}x{}, {} threads", cols, rows, threads);
+            }
+        }
+    }
+
+    #[test]
+    fn parallel_grundy() {
+        for n in [10, 25, 40] {
+            let game = GrundyGame(n);
+            let expected = Solver::new(&game, HashMap::new(), (), (), ()).nimber_of_initial_def();
+            let tt = ShardedTT::new(2, HashMap::new);
+            let mut solver = Solver::new(&game, &tt, (), (), ());
+            assert_eq!(solver.nimber_of_initial_br_aspset_parallel(3), expected, "Grundy's game {}", n);
+        }
+    }
+
+    #[test]
+    fn parallel_chomp() {
+        for (cols, rows) in [(3, 2), (4, 3), (5, 4)] {
+            let chomp = Chomp::new(cols, rows);
+            let expected = Solver::new(&chomp, HashMap::new(), (), (), ()).nimber_def(chomp.initial_position());
+            let tt = ShardedTT::new(2, HashMap::new);
+            let mut solver = Solver::new(&chomp, &tt, (), FewerBarsFirst{}, ());
+            assert_eq!(solver.nimber_br_aspset_parallel(chomp.initial_position(), 4), expected, "Chomp {}x{}", cols, rows);
+        }
+    }
+
+    /// Checkpoint that saves every state and (optionally) resumes from the given one.
+    #[derive(Default)]
+    struct RecordingCheckpoint { to_load: Option<AspSetState>, saved: Vec<AspSetState>, finished: bool }
+
+    impl Checkpoint for &mut RecordingCheckpoint {
+        fn load(&mut self) -> io::Result<Option<AspSetState>> { Ok(self.to_load.take()) }
+        fn is_due(&mut self) -> bool { true }
+        fn save(&mut self, state: &AspSetState) -> io::Result<()> { self.saved.push(state.clone()); Ok(()) }
+        fn finish(&mut self) -> io::Result<()> { self.finished = true; Ok(()) }
+    }
+
+    #[test]
+    fn checkpoint_cram() {
+        for (cols, rows) in [(3, 3), (4, 3), (5, 3), (4, 4)] {
+            let cram = Cram::new(cols, rows);
+            let expected = Solver::new(&cram, HashMap::new(), (), (), ()).nimber_of_initial_def();
+            let mut checkpoint = RecordingCheckpoint::default();
+            let mut solver = Solver::new(&cram, HashMap::new(), (), SmallerComponentsFirst{}, ());
+            assert_eq!(solver.nimber_of_initial_br_aspset_checkpoint((), &mut checkpoint).unwrap(), expected, "Cram {}x{}", cols, rows);
+            assert!(checkpoint.finished);
+            for state in checkpoint.saved {
+                let mut resumed = RecordingCheckpoint { to_load: Some(state), ..Default::default() };
+                let mut solver = Solver::new(&cram, HashMap::new(), (), SmallerComponentsFirst{}, ());
+                assert_eq!(solver.nimber_of_initial_br_aspset_checkpoint((), &mut resumed).unwrap(), expected, "resumed Cram {}x{}", cols, rows);
+            }
+        }
+    }
+
+    #[test]
+    fn checkpoint_chomp() {
+        for (cols, rows) in [(3, 2), (4, 3), (5, 4)] {
+            let chomp = Chomp::new(cols, rows);
+            let expected = Solver::new(&chomp, HashMap::new(), (), (), ()).nimber_def(chomp.initial_position());
+            let mut checkpoint = RecordingCheckpoint::default();
+            let mut solver = Solver::new(&chomp, HashMap::new(), (), FewerBarsFirst{}, ());
+            assert_eq!(solver.nimber_br_aspset_checkpoint(chomp.initial_position(), None, (), &mut checkpoint).unwrap(), expected, "Chomp {}x{}", cols, rows);
+            assert!(checkpoint.finished);
+            assert!(!checkpoint.saved.is_empty());
+            for state in checkpoint.saved {
+                let mut resumed = RecordingCheckpoint { to_load: Some(state), ..Default::default() };
+                let mut solver = Solver::new(&chomp, HashMap::new(), (), FewerBarsFirst{}, ());
+                assert_eq!(solver.nimber_br_aspset_checkpoint(chomp.initial_position(), None, (), &mut resumed).unwrap(), expected, "resumed Chomp {}x{}", cols, rows);
+            }
+        }
+    }
+
+    #[test]
+    fn checkpoint_of_other_position() {
+        let chomp = Chomp::new(4, 3);
+        let mut solver = Solver::new(&chomp, HashMap::new(), (), FewerBarsFirst{}, ());
+        let mut checkpoint = RecordingCheckpoint { to_load: Some(AspSetState::new(1, 0)), ..Default::default() };
+        assert_eq!(solver.nimber_br_aspset_checkpoint(chomp.initial_position(), None, (), &mut checkpoint).unwrap_err().kind(), io::ErrorKind::InvalidData);
+        let cram = Cram::new(4, 3);
+        let mut solver = Solver::new(&cram, HashMap::new(), (), SmallerComponentsFirst{}, ());
+        let mut checkpoint = RecordingCheckpoint { to_load: Some(AspSetState::new(1, 0)), ..Default::default() };
+        assert_eq!(solver.nimber_of_initial_br_aspset_checkpoint((), &mut checkpoint).unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
 }
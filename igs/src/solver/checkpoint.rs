@@ -0,0 +1,189 @@
+use std::fs;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+/// State of the top-level loop of the aspiration set search, which allows resuming the search.
+///
+/// The moves of the root position are identified by their indices in the order
+/// produced by the heuristically ordered successors sorted by the move sorter,
+/// which does not depend on the content of the transposition table.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct AspSetState {
+    /// Number of moves of the root position (used to detect the checkpoints of other positions).
+    pub moves: u16,
+    /// The candidate nimber being analysed. All lower candidates have already been excluded.
+    pub nimber: u8,
+    /// Already known nimbers of some moves, which are therefore excluded.
+    pub excluded: Vec<u8>,
+    /// Indices of the moves whose nimbers are still unknown.
+    pub remaining: Vec<u16>,
+    /// Number of leading `remaining` moves, which are already known to have nimbers other than `nimber`.
+    pub checked: u16
+}
+
+impl AspSetState {
+    /// Returns the state of the search which is about to begin for the root position with the given number of `moves`.
+    pub fn new(moves: u16, first_nimber: u8) -> Self {
+        Self { moves, nimber: first_nimber, excluded: Vec::new(), remaining: (0..moves).collect(), checked: 0 }
+    }
+
+    /// Writes `self` to `output` in text format.
+    pub fn write(&self, output: &mut dyn Write) -> io::Result<()> {
+        fn write_list<T: ToString>(output: &mut dyn Write, name: &str, list: &[T]) -> io::Result<()> {
+            writeln!(output, "{}{}", name, list.iter().map(|v| format!(" {}", v.to_string())).collect::<String>())
+        }
+        writeln!(output, "igs aspset checkpoint 1")?;
+        writeln!(output, "moves {}", self.moves)?;
+        writeln!(output, "nimber {}", self.nimber)?;
+        write_list(output, "excluded", &self.excluded)?;
+        write_list(output, "remaining", &self.remaining)?;
+        writeln!(output, "checked {}", self.checked)
+    }
+
+    /// Reads the state written by `write`.
+    pub fn read(input: &str) -> io::Result<Self> {
+        fn invalid() -> io::Error { io::Error::new(io::ErrorKind::InvalidData, "invalid aspiration set search checkpoint") }
+        fn list<'a, T: std::str::FromStr>(lines: &mut impl Iterator<Item=&'a str>, name: &str) -> io::Result<Vec<T>> {
+            let mut words = lines.next().ok_or_else(invalid)?.split_ascii_whitespace();
+            if words.next() != Some(name) { return Err(invalid()); }
+            words.map(|w| w.parse().map_err(|_| invalid())).collect()
+        }
+        fn single<'a, T: std::str::FromStr + Copy>(lines: &mut impl Iterator<Item=&'a str>, name: &str) -> io::Result<T> {
+            match list(lines, name)?.as_slice() { [v] => Ok(*v), _ => Err(invalid()) }
+        }
+        let mut lines = input.lines();
+        if lines.next() != Some("igs aspset checkpoint 1") { return Err(invalid()); }
+        let result = Self {
+            moves: single(&mut lines, "moves")?,
+            nimber: single(&mut lines, "nimber")?,
+            excluded: list(&mut lines, "excluded")?,
+            remaining: list(&mut lines, "remaining")?,
+            checked: single(&mut lines, "checked")?
+        };
+        if result.checked as usize > result.remaining.len() || result.remaining.iter().any(|i| *i >= result.moves) { return Err(invalid()); }
+        Ok(result)
+    }
+}
+
+/// Saves and restores the state of the top-level loop of the aspiration set search.
+pub trait Checkpoint {
+    /// Returns the previously saved state, if any.
+    #[inline(always)] fn load(&mut self) -> io::Result<Option<AspSetState>> { Ok(None) }
+
+    /// Checks if the state should be saved now. Called before analysing each move of the root position.
+    #[inline(always)] fn is_due(&mut self) -> bool { false }
+
+    /// Saves the given `state`.
+    #[inline(always)] fn save(&mut self, _state: &AspSetState) -> io::Result<()> { Ok(()) }
+
+    /// Called when the search is finished.
+    #[inline(always)] fn finish(&mut self) -> io::Result<()> { Ok(()) }
+}
+
+impl Checkpoint for () {}
+
+/// Checkpoint kept in a text file, which is saved periodically and removed when the search is finished.
+///
+/// The file begins with the description of the game and its position (see [`CheckpointFile::with_game`]),
+/// followed by the state, and it is resumed only by the search of the same game.
+pub struct CheckpointFile {
+    /// Path of the file.
+    path: PathBuf,
+    /// Description of the game and the position searched.
+    game: String,
+    /// Minimal time between two saves.
+    interval: Duration,
+    /// Time of the last save.
+    last_save: Instant
+}
+
+impl CheckpointFile {
+    /// Returns the checkpoint kept in the file with the given `path`, that is saved every `interval`.
+    pub fn new<P: Into<PathBuf>>(path: P, interval: Duration) -> Self {
+        Self { path: path.into(), game: String::new(), interval, last_save: Instant::now() }
+    }
+
+    /// Returns `self` for the search of the given `game`, which should describe the game and the searched position.
+    /// The checkpoint saved for another game is not resumed.
+    pub fn with_game<S: Into<String>>(self, game: S) -> Self {
+        Self { game: game.into().replace('\n', " "), ..self }
+    }
+}
+
+impl Checkpoint for CheckpointFile {
+    fn load(&mut self) -> io::Result<Option<AspSetState>> {
+        let content = match fs::read_to_string(&self.path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e)
+        };
+        let (game, state) = content.split_once('\n').unwrap_or((&content, ""));
+        match game.strip_prefix("game ") {
+            Some(game) if game == self.game => {},
+            Some(game) => return Err(io::Error::new(io::ErrorKind::InvalidData,
+                format!("the checkpoint {} has been saved for another game ({}), expected {}", self.path.display(), game, self.game))),
+            None => return Err(io::Error::new(io::ErrorKind::InvalidData, "invalid aspiration set search checkpoint"))
+        }
+        AspSetState::read(state).map(Some)
+    }
+
+    #[inline] fn is_due(&mut self) -> bool {
+        self.last_save.elapsed() >= self.interval
+    }
+
+    fn save(&mut self, state: &AspSetState) -> io::Result<()> {
+        // the state is written to temporary file first, so the checkpoint is never corrupted by crash during writing
+        let mut tmp_path = self.path.clone().into_os_string();
+        tmp_path.push(".tmp");
+        let mut content = format!("game {}\n", self.game).into_bytes();
+        state.write(&mut content)?;
+        fs::write(&tmp_path, content)?;
+        fs::rename(&tmp_path, &self.path)?;
+        self.last_save = Instant::now();
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.load()?;   // the checkpoint of another game is never removed
+        match fs::remove_file(&self.path) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn write_read() {
+        let state = AspSetState { moves: 5, nimber: 2, excluded: vec![4, 3], remaining: vec![1, 3, 4], checked: 1 };
+        let mut buff = Vec::new();
+        state.write(&mut buff).unwrap();
+        assert_eq!(AspSetState::read(std::str::from_utf8(&buff).unwrap()).unwrap(), state);
+        let state = AspSetState::new(0, 1);
+        let mut buff = Vec::new();
+        state.write(&mut buff).unwrap();
+        assert_eq!(AspSetState::read(std::str::from_utf8(&buff).unwrap()).unwrap(), state);
+        assert!(AspSetState::read("igs aspset checkpoint 1\nmoves 2\nnimber 0\nexcluded\nremaining 2\nchecked 0").is_err());
+    }
+
+    #[test]
+    fn file() {
+        let path = std::env::temp_dir().join(format!("igs_checkpoint_test_{}.txt", std::process::id()));
+        let state = AspSetState { moves: 5, nimber: 2, excluded: vec![4, 3], remaining: vec![1, 3, 4], checked: 1 };
+        let mut checkpoint = CheckpointFile::new(&path, Duration::ZERO).with_game("Cram 3x4");
+        assert_eq!(checkpoint.load().unwrap(), None);
+        checkpoint.save(&state).unwrap();
+        assert_eq!(checkpoint.load().unwrap(), Some(state));
+        let mut other = CheckpointFile::new(&path, Duration::ZERO).with_game("Cram 4x3");
+        assert_eq!(other.load().unwrap_err().kind(), io::ErrorKind::InvalidData);
+        assert_eq!(other.finish().unwrap_err().kind(), io::ErrorKind::InvalidData);
+        assert!(path.exists());
+        checkpoint.finish().unwrap();
+        assert!(!path.exists());
+        checkpoint.finish().unwrap();
+    }
+}
@@ -0,0 +1,235 @@
+pub use super::Solver;
+use crate::game::{Game, SimpleGame, MisereDecomposableGame};
+use crate::dbs::{NimbersProvider, NimbersStorer};
+use crate::nimber_set::NimberSet;
+use crate::solver::StatsCollector;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// Solver that calculates misère Grundy values of simple games using the mex function directly.
+///
+/// The misère Grundy value of a terminal position is 1,
+/// and of any other position, the mex of the values of its successors.
+/// A position is losing under misère play (i.e. when the player who makes the last move loses)
+/// iff its misère Grundy value is 0.
+///
+/// Note that the transposition table of the solver stores misère Grundy values,
+/// so it cannot be shared with searches under normal play. The const database is not used.
+pub trait MisereSimpleGameSolver<G> where G: SimpleGame {
+    /// Returns the misère Grundy value of the given `position`.
+    fn misere_nimber_def(&mut self, position: G::Position) -> u8;
+
+    /// Returns the misère Grundy value of the initial position of the game.
+    fn misere_nimber_of_initial_def(&mut self) -> u8;
+
+    /// Checks if the given `position` is winning under misère play.
+    #[inline] fn is_misere_winning(&mut self, position: G::Position) -> bool {
+        self.misere_nimber_def(position) != 0
+    }
+
+    /// Checks if the initial position of the game is winning under misère play.
+    #[inline] fn is_initial_misere_winning(&mut self) -> bool {
+        self.misere_nimber_of_initial_def() != 0
+    }
+}
+
+impl<G, TT, EDB, SORTER, STATS> MisereSimpleGameSolver<G> for Solver<'_, G, TT, EDB, SORTER, STATS>
+    where G: SimpleGame,
+          TT: NimbersProvider<G::Position> + NimbersStorer<G::Position>,
+          EDB: NimbersProvider<G::Position>,
+          STATS: StatsCollector
+{
+    fn misere_nimber_def(&mut self, position: G::Position) -> u8 {
+        self.stats.pre();
+        if let Some(v) = self.nimber_from_tt(&position) {
+            self.stats.db_cut(v);
+            return v;
+        }
+        self.stats.recursive();
+        let mut nimbers = <G as Game>::NimberSet::empty();
+        let mut has_moves = false;
+        for m in self.game.successors(&position) {
+            has_moves = true;
+            nimbers.append(self.misere_nimber_def(m));
+        }
+        let result = if has_moves { nimbers.mex() } else { 1 };
+        self.transposition_table.store_nimber(position, result);
+        self.stats.exact(result);
+        result
+    }
+
+    fn misere_nimber_of_initial_def(&mut self) -> u8 {
+        let initial_position = self.game.initial_position();
+        self.misere_nimber_def(initial_position)
+    }
+}
+
+/// Solver that calculates outcomes of games with decomposable positions under misère play.
+///
+/// Since the Sprague–Grundy theory does not apply to misère play,
+/// the solver searches the sums of components directly
+/// (represented by sorted sequences of components that have moves).
+///
+/// The outcomes of single components are stored in the transposition table of the solver (as 1 for winning and 0 for losing),
+/// so the table cannot be shared with searches under normal play. The const database is not used.
+/// The outcomes of sums of two or more components, which the table cannot hold, are memorized during a single call of any method.
+pub trait MisereDecomposableGameSolver<G> where G: MisereDecomposableGame {
+    /// Checks if the sum of the given `components` is winning under misère play.
+    fn is_misere_winning_sum(&mut self, components: Vec<G::Position>) -> bool;
+
+    /// Checks if the given (possibly decomposable) `position` is winning under misère play.
+    fn is_misere_winning(&mut self, position: &G::DecomposablePosition) -> bool;
+
+    /// Checks if the initial position of the game is winning under misère play.
+    fn is_initial_misere_winning(&mut self) -> bool;
+}
+
+impl<G, TT, EDB, SORTER, STATS> Solver<'_, G, TT, EDB, SORTER, STATS>
+    where G: MisereDecomposableGame,
+          G::Position: Clone + Ord + Hash,
+          TT: NimbersProvider<G::Position> + NimbersStorer<G::Position>,
+          EDB: NimbersProvider<G::Position>,
+          STATS: StatsCollector
+{
+    /// Appends to `sum` these components of `position` that have moves.
+    #[inline]
+    fn push_misere_components(&self, sum: &mut Vec<G::Position>, position: &G::DecomposablePosition) {
+        sum.extend(self.game.decompose_misere(position).filter(|c| self.game.moves_count(c) != 0));
+    }
+
+    /// Checks if the sorted sum of components `sum` is winning under misère play.
+    /// Outcomes of already searched single components are stored in the transposition table, and of longer sums in `memo`.
+    fn is_misere_winning_sorted_sum(&mut self, sum: Vec<G::Position>, memo: &mut HashMap<Box<[G::Position]>, bool>) -> bool {
+        self.stats.pre();
+        if sum.is_empty() { return true; }  // the player to move cannot move and so wins
+        let known = if sum.len() == 1 {
+            self.nimber_from_tt(&sum[0]).map(|v| v != 0)
+        } else {
+            self.stats.tt_read();
+            memo.get(sum.as_slice()).copied()
+        };
+        if let Some(is_winning) = known {
+            self.stats.db_cut(is_winning as u8);
+            return is_winning;
+        }
+        self.stats.recursive();
+        let mut is_winning = false;
+        'search: for i in 0..sum.len() {
+            if i != 0 && sum[i] == sum[i-1] { continue; }   // same component gives the same moves
+            for m in self.game.successors(&sum[i]) {
+                let mut next = Vec::with_capacity(sum.len() + 1);
+                next.extend_from_slice(&sum[..i]);
+                next.extend_from_slice(&sum[i+1..]);
+                self.push_misere_components(&mut next, &m);
+                next.sort_unstable();
+                if !self.is_misere_winning_sorted_sum(next, memo) {
+                    is_winning = true;
+                    break 'search;
+                }
+            }
+        }
+        self.stats.exact(is_winning as u8);
+        if sum.len() == 1 {
+            self.transposition_table.store_nimber(sum.into_iter().next().unwrap(), is_winning as u8);
+        } else {
+            memo.insert(sum.into_boxed_slice(), is_winning);
+        }
+        is_winning
+    }
+}
+
+impl<G, TT, EDB, SORTER, STATS> MisereDecomposableGameSolver<G> for Solver<'_, G, TT, EDB, SORTER, STATS>
+    where G: MisereDecomposableGame,
+          G::Position: Clone + Ord + Hash,
+          TT: NimbersProvider<G::Position> + NimbersStorer<G::Position>,
+          EDB: NimbersProvider<G::Position>,
+          STATS: StatsCollector
+{
+    fn is_misere_winning_sum(&mut self, mut components: Vec<G::Position>) -> bool {
+        components.retain(|c| self.game.moves_count(c) != 0);
+        components.sort_unstable();
+        self.is_misere_winning_sorted_sum(components, &mut HashMap::new())
+    }
+
+    fn is_misere_winning(&mut self, position: &G::DecomposablePosition) -> bool {
+        let mut sum = Vec::new();
+        self.push_misere_components(&mut sum, position);
+        sum.sort_unstable();
+        self.is_misere_winning_sorted_sum(sum, &mut HashMap::new())
+    }
+
+    fn is_initial_misere_winning(&mut self) -> bool {
+        let initial_position = self.game.initial_position();
+        self.is_misere_winning_sum(vec![initial_position])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::games::{Chomp, Cram, GrundyGame};
+
+    #[test]
+    fn misere_chomp_single_row() {
+        // misère Grundy values of 1xn Chomp: 1, 0, 2, 3, 4, ...
+        for (n, expected) in [(1, 1), (2, 0), (3, 2), (4, 3), (5, 4), (6, 5)] {
+            let chomp = Chomp::new(n, 1);
+            let mut solver = Solver::new(&chomp, HashMap::new(), (), (), ());
+            assert_eq!(solver.misere_nimber_of_initial_def(), expected, "1x{}", n);
+        }
+    }
+
+    #[test]
+    fn misere_chomp_2x2() {
+        // from 2x2 (3 moves), removing the top-right square leaves an L-shape,
+        // whose successors (2 squares) have value 0, so L-shape has value 1;
+        // other successors are 1x2 with value 0, so 2x2 has value mex{0,1} = 2
+        let chomp = Chomp::new(2, 2);
+        let mut solver = Solver::new(&chomp, HashMap::new(), (), (), ());
+        assert_eq!(solver.misere_nimber_of_initial_def(), 2);
+        assert!(solver.is_initial_misere_winning());
+    }
+
+    #[test]
+    fn misere_grundy_game() {
+        // heaps of sizes 0, 1, 2 have no moves, so they are winning under misère play
+        let losing = [3, 6, 9, 12, 15, 18, 21, 24, 27, 30, 33, 36, 39, 42, 45];
+        for n in 0..=47u16 {
+            let game = GrundyGame(n);
+            let mut solver = Solver::new(&game, HashMap::new(), (), (), ());
+            assert_eq!(solver.is_initial_misere_winning(), !losing.contains(&n), "heap of size {}", n);
+        }
+    }
+
+    #[test]
+    fn misere_grundy_game_sums() {
+        let game = GrundyGame(0);
+        let mut solver = Solver::new(&game, HashMap::new(), (), (), ());
+        // positions are heap sizes decreased by 2
+        assert!(!solver.is_misere_winning_sum(vec![1]));
+        assert!(solver.is_misere_winning_sum(vec![1, 1]));  // 3+3 is losing under normal play
+        assert!(solver.is_misere_winning_sum(vec![0, 0]));  // no moves
+        assert!(solver.is_misere_winning_sum(vec![]));
+    }
+
+    #[test]
+    fn misere_outcomes_in_tt() {
+        let game = GrundyGame(0);
+        let mut solver = Solver::new(&game, HashMap::new(), (), (), ());
+        assert!(!solver.is_misere_winning_sum(vec![1]));
+        assert_eq!(solver.transposition_table.get(&1), Some(&0));
+        assert!(solver.is_misere_winning_sum(vec![2]));
+        assert_eq!(solver.transposition_table.get(&2), Some(&1));
+        assert!(!solver.is_misere_winning_sum(vec![1])); // read from the table
+    }
+
+    #[test]
+    fn misere_cram() {
+        for (cols, rows, expected) in [(1, 1, true), (1, 2, false), (1, 3, false), (1, 4, true), (2, 2, true),
+                                       (1, 5, true), (2, 3, false), (3, 3, true), (2, 4, true), (1, 7, false), (3, 4, false)] {
+            let cram = Cram::new(cols, rows);
+            let mut solver = Solver::new(&cram, HashMap::new(), (), (), ());
+            assert_eq!(solver.is_initial_misere_winning(), expected, "{}x{}", cols, rows);
+        }
+    }
+}
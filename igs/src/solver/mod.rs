@@ -14,6 +14,15 @@ pub use self::lvb::LVBDecomposableGameSolver as _;
 pub mod br;
 pub use self::br::BRSimpleGameSolver as _;
 pub use self::br::BRDecomposableGameSolver as _;
+pub use self::br::BRParallelSimpleGameSolver as _;
+pub use self::br::BRParallelDecomposableGameSolver as _;
+
+pub mod checkpoint;
+pub use self::checkpoint::{Checkpoint, CheckpointFile};
+
+pub mod misere;
+pub use self::misere::MisereSimpleGameSolver as _;
+pub use self::misere::MisereDecomposableGameSolver as _;
 
 pub mod dedicated;
 pub use self::dedicated::{SolverForSimpleGame, SolverForDecomposableGame};
@@ -526,6 +526,24 @@ impl StatsCollector for NimberStats {
 
 impl StatsCollector for () {}
 
+/// `None` collects nothing, which allows to decide at run-time whether the statistics are collected.
+impl<SC: StatsCollector> StatsCollector for Option<SC> {
+    #[inline(always)] fn pre(&mut self) { if let Some(sc) = self { sc.pre(); } }
+    #[inline(always)] fn etc(&mut self) { if let Some(sc) = self { sc.etc(); } }
+    #[inline(always)] fn recursive(&mut self) { if let Some(sc) = self { sc.recursive(); } }
+
+    #[inline(always)] fn tt_read(&mut self) { if let Some(sc) = self { sc.tt_read(); } }
+    #[inline(always)] fn const_db_read(&mut self) { if let Some(sc) = self { sc.const_db_read(); } }
+
+    #[inline(always)] fn db_skip(&mut self, nimber: u8) { if let Some(sc) = self { sc.db_skip(nimber); } }
+
+    #[inline(always)] fn db_cut(&mut self, nimber: u8) { if let Some(sc) = self { sc.db_cut(nimber); } }
+    #[inline(always)] fn unknown(&mut self) { if let Some(sc) = self { sc.unknown(); } }
+    #[inline(always)] fn exact(&mut self, nimber: u8) { if let Some(sc) = self { sc.exact(nimber); } }
+
+    #[inline(always)] fn reset(&mut self) { if let Some(sc) = self { sc.reset(); } }
+}
+
 impl<SC1: StatsCollector> StatsCollector for (SC1,) {
     #[inline(always)] fn pre(&mut self) { self.0.pre(); }
     #[inline(always)] fn etc(&mut self) { self.0.etc(); }
@@ -51,4 +51,28 @@ pub fn degski(mut x: u64, mask: u64) -> u64 {
 
 /// Returns `x`.
 #[inline]
-pub fn without_mixing(x: u64, _mask: u64) -> u64 { x }
\ No newline at end of file
+pub fn without_mixing(x: u64, _mask: u64) -> u64 { x }
+/// Bit mixer identified by its name, which is recorded in the header of a transposition table file
+/// (see `TTSuccinct64::with_file`). The module includes the implementation for each mixing function.
+pub trait NamedBitMixer {
+    /// Name of the bit mixer.
+    const NAME: &'static str;
+
+    /// Mixes the bits of `x` shown by `mask`.
+    fn mix(x: u64, mask: u64) -> u64;
+}
+
+macro_rules! impl_named_bit_mixer {
+    ($($type:ident => $fn:ident),*) => {$(
+        #[doc = concat!("Named bit mixer that uses [`", stringify!($fn), "`].")]
+        #[derive(Copy, Clone)]
+        pub struct $type;
+
+        impl NamedBitMixer for $type {
+            const NAME: &'static str = stringify!($fn);
+            #[inline(always)] fn mix(x: u64, mask: u64) -> u64 { $fn(x, mask) }
+        }
+    )*}
+}
+
+impl_named_bit_mixer!(Stafford13 => stafford13, Moremur => moremur, Mx3 => mx3, Xmxmx => xmxmx, Degski => degski, WithoutMixing => without_mixing);
@@ -0,0 +1,251 @@
+use crate::dbs::{NimbersProvider, NimbersStorer, ConcurrentNimbersStorer, HasLen};
+use super::{ClusterConf, StatelessClusterPolicy, NamedClusterPolicy, TTFile, TTFileConf, EMPTY_ENTRY, bit_mixer::NamedBitMixer};
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hash};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+/// Transposition table that can be shared by many threads.
+///
+/// It consists of a number of shards, each is a (single-threaded) transposition table guarded by a mutex.
+/// The shard of each position is selected by its hash.
+pub struct ShardedTT<TT, S = RandomState> {
+    shards: Box<[Mutex<TT>]>,
+    hasher: S
+}
+
+impl<TT> ShardedTT<TT> {
+    /// Constructs `ShardedTT` with `2^shards_log2` shards, each constructed by `new_shard`.
+    pub fn new<F: FnMut() -> TT>(shards_log2: u8, new_shard: F) -> Self {
+        Self::with_hasher(shards_log2, new_shard, Default::default())
+    }
+}
+
+impl<TT, S: BuildHasher> ShardedTT<TT, S> {
+    /// Constructs `ShardedTT` with `2^shards_log2` shards, each constructed by `new_shard`,
+    /// that uses `hasher` to select shards.
+    pub fn with_hasher<F: FnMut() -> TT>(shards_log2: u8, mut new_shard: F, hasher: S) -> Self {
+        Self {
+            shards: (0..1usize<<shards_log2).map(|_| Mutex::new(new_shard())).collect(),
+            hasher
+        }
+    }
+
+    /// Returns the shard that can contain the given `position`.
+    #[inline(always)] fn shard<P: Hash>(&self, position: &P) -> &Mutex<TT> {
+        &self.shards[self.hasher.hash_one(position) as usize & (self.shards.len()-1)]
+    }
+}
+
+impl<P: Hash, TT: NimbersProvider<P>, S: BuildHasher> NimbersProvider<P> for ShardedTT<TT, S> {
+    #[inline]
+    fn get_nimber(&self, position: &P) -> Option<u8> {
+        self.shard(position).lock().unwrap().get_nimber_and_self_organize(position)
+    }
+}
+
+impl<P: Hash, TT: NimbersStorer<P> + Send, S: BuildHasher + Sync> ConcurrentNimbersStorer<P> for ShardedTT<TT, S> {
+    #[inline]
+    fn store_nimber_concurrently(&self, position: P, nimber: u8) {
+        self.shard(&position).lock().unwrap().store_nimber(position, nimber)
+    }
+}
+
+impl<TT: HasLen, S> HasLen for ShardedTT<TT, S> {
+    fn len(&self) -> usize {
+        self.shards.iter().map(|s| s.lock().unwrap().len()).sum()
+    }
+}
+
+/// Succinct transposition table for 64-bit positions, that can be shared by many threads.
+///
+/// It is a lock-free counterpart of `TTSuccinct64` and has the same layout and limitations.
+/// Each entry (which encodes a fragment of position and its nimber) is stored in a single atomic `u32`,
+/// so the entries are never torn.
+/// Clusters are updated by copying them, applying the cluster policy to the copy,
+/// and writing back (with compare-and-swap) the entries that have been changed.
+/// Therefore, concurrent updates of the same cluster can lose some entries, but never make the table return wrong nimbers.
+///
+/// Note that `get_nimber` self-organizes the clusters if the policy does it (like `Lru`).
+///
+/// The entries are stored in `Data`, which is either a boxed slice (by default) or a memory-mapped `TTFile`.
+pub struct ConcurrentTTSuccinct64<BitMixer: Fn(u64, u64) -> u64, Policy: StatelessClusterPolicy = super::cluster_policy::Fifo, Data = Box<[AtomicU32]>> {
+    data: Data,
+    /// Clusters configuration.
+    cluster_conf: ClusterConf,
+    /// maximal position number which can be stored (larger are ignored)
+    key_mask: u64,
+    /// mix_bits(position, mask) is bijection that returns position with mixed bits shown by 0..01..1 mask (key_mask)
+    mix_bits: BitMixer,
+    /// Used to update clusters or search in clusters.
+    cluster_policy: Policy
+}
+
+impl<BitMixer: Fn(u64, u64) -> u64, Policy: StatelessClusterPolicy> ConcurrentTTSuccinct64<BitMixer, Policy> {
+
+    /// Construct ConcurrentTTSuccinct64 which stores `2` to power of `capacity_log2` entries.
+    /// Each entry has `4` bytes, and the result consumes `2` to power `capacity_log2+2` bytes.
+    /// Each entry encodes nimber (using `bits_per_nimber` bits) and `32-bits_per_nimber` bits of position id.
+    /// Entries are grouped in clusters, and each cluster stores `2` to power `cluster_capacity_log2` entries.
+    pub fn new(capacity_log2: u8, cluster_capacity_log2: u8, bits_per_nimber: u8, bit_mixer: BitMixer, cluster_policy: Policy) -> Self {
+        Self::with_data((0..1usize<<capacity_log2).map(|_| AtomicU32::new(EMPTY_ENTRY)).collect(),
+            capacity_log2, cluster_capacity_log2, bits_per_nimber, bit_mixer, cluster_policy)
+    }
+}
+
+impl<Policy: StatelessClusterPolicy + NamedClusterPolicy> ConcurrentTTSuccinct64<fn(u64, u64) -> u64, Policy, TTFile> {
+
+    /// Construct ConcurrentTTSuccinct64 whose entries are kept in the memory-mapped file described by `file`.
+    /// The file has the same format as the file of `TTSuccinct64::with_file`, and can be used by both tables.
+    /// The names of `BitMixer` and `Policy` are recorded in the file header.
+    ///
+    /// The meaning of the other parameters is the same as in `new`.
+    /// Returns error if the existing file has been created with a different configuration.
+    pub fn with_file<P: AsRef<std::path::Path>, BitMixer: NamedBitMixer>(file: &TTFileConf<P>,
+        capacity_log2: u8, cluster_capacity_log2: u8, bits_per_nimber: u8, _bit_mixer: BitMixer, cluster_policy: Policy) -> std::io::Result<Self>
+    {
+        Ok(Self::with_data(file.open::<BitMixer, Policy>(capacity_log2, cluster_capacity_log2, bits_per_nimber)?,
+            capacity_log2, cluster_capacity_log2, bits_per_nimber, BitMixer::mix, cluster_policy))
+    }
+
+    /// Writes all changes to the file.
+    pub fn flush(&self) -> std::io::Result<()> { self.data.flush() }
+}
+
+impl<BitMixer: Fn(u64, u64) -> u64, Policy: StatelessClusterPolicy, Data: AsRef<[AtomicU32]>> ConcurrentTTSuccinct64<BitMixer, Policy, Data> {
+
+    /// Construct ConcurrentTTSuccinct64 whose `2` to power of `capacity_log2` entries are stored in `data`.
+    fn with_data(data: Data, capacity_log2: u8, cluster_capacity_log2: u8, bits_per_nimber: u8, bit_mixer: BitMixer, cluster_policy: Policy) -> Self {
+        assert!(capacity_log2 >= cluster_capacity_log2);
+        assert!(cluster_capacity_log2 < 8);
+        assert!(bits_per_nimber <= 8);
+        let cluster_conf = ClusterConf::new_log2(cluster_capacity_log2, bits_per_nimber);
+        let clusters_num_log2 = capacity_log2 - cluster_capacity_log2;
+        let bits_per_key = clusters_num_log2 + cluster_conf.id_size;
+        assert!(bits_per_key <= 64);
+        assert_eq!(data.as_ref().len(), 1usize << capacity_log2);
+        Self {
+            data,
+            key_mask: (1u64 << bits_per_key).wrapping_sub(1),
+            cluster_conf,
+            mix_bits: bit_mixer,
+            cluster_policy
+        }
+    }
+
+    /// Returns the capacity of the table (total number of entries).
+    pub fn capacity(&self) -> usize { self.data.as_ref().len() }
+
+    /// Returns the cluster for the given `key`.
+    #[inline(always)] fn cluster(&self, key: u64) -> &[AtomicU32] {
+        let cl_beg = ((key >> self.cluster_conf.id_size) as usize) * (self.cluster_conf.capacity as usize);
+        &self.data.as_ref()[cl_beg..cl_beg+(self.cluster_conf.capacity as usize)]
+    }
+
+    /// Copies the content of `cluster` to `copy` and calls `f` for the copy.
+    /// Then writes back the entries of the copy changed by `f`, unless they have been changed concurrently.
+    #[inline(always)] fn update<R, F: FnOnce(&mut [u32]) -> R>(cluster: &[AtomicU32], f: F) -> R {
+        let mut buffer = [EMPTY_ENTRY; 128];
+        let original = &mut buffer[..cluster.len()];
+        for (o, e) in original.iter_mut().zip(cluster) { *o = e.load(Ordering::Relaxed); }
+        let mut copy = [EMPTY_ENTRY; 128];
+        let copy = &mut copy[..cluster.len()];
+        copy.copy_from_slice(original);
+        let result = f(copy);
+        for ((e, o), c) in cluster.iter().zip(original.iter()).zip(copy.iter()) {
+            if o != c { let _ = e.compare_exchange(*o, *c, Ordering::Relaxed, Ordering::Relaxed); }
+        }
+        result
+    }
+}
+
+impl<BitMixer: Fn(u64, u64) -> u64, Policy: StatelessClusterPolicy, Data: AsRef<[AtomicU32]>> HasLen for ConcurrentTTSuccinct64<BitMixer, Policy, Data> {
+    /// Returns the number of elements in the table (number of occupied entries).
+    fn len(&self) -> usize {
+        self.data.as_ref().iter().filter(|e| e.load(Ordering::Relaxed) != EMPTY_ENTRY).count()
+    }
+}
+
+impl<BitMixer: Fn(u64, u64) -> u64, Policy: StatelessClusterPolicy, Data: AsRef<[AtomicU32]>> NimbersProvider<u64> for ConcurrentTTSuccinct64<BitMixer, Policy, Data> {
+    fn get_nimber(&self, position: &u64) -> Option<u8> {
+        if *position > self.key_mask { return None; }
+        let key = (self.mix_bits)(*position, self.key_mask);
+        let id_to_find = self.cluster_conf.id(key as u32);
+        Self::update(self.cluster(key), |cluster|
+            self.cluster_policy.get_nimber_and_self_organize(&self.cluster_conf, cluster, id_to_find))
+    }
+}
+
+impl<BitMixer: Fn(u64, u64) -> u64 + Sync, Policy: StatelessClusterPolicy, Data: AsRef<[AtomicU32]> + Sync> ConcurrentNimbersStorer<u64> for ConcurrentTTSuccinct64<BitMixer, Policy, Data> {
+    fn store_nimber_concurrently(&self, position: u64, nimber: u8) {
+        if position > self.key_mask || nimber > self.cluster_conf.max_nimber { return; }
+        let key = (self.mix_bits)(position, self.key_mask);
+        let to_store = self.cluster_conf.entry(key, nimber);
+        if to_store == EMPTY_ENTRY { return; }
+        let mut policy = self.cluster_policy;
+        Self::update(self.cluster(key), |cluster|
+            policy.store_entry(&self.cluster_conf, cluster, to_store, nimber))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::{bit_mixer, cluster_policy};
+    use std::collections::HashMap;
+
+    #[test]
+    fn store_and_get() {
+        let tt = ShardedTT::new(3, HashMap::<u64, u8>::new);
+        std::thread::scope(|s| {
+            for t in 0..4u64 {
+                let tt = &tt;
+                s.spawn(move || for p in 0..100u64 { tt.store_nimber_concurrently(4*p + t, (p % 7) as u8) });
+            }
+        });
+        assert_eq!(tt.len(), 400);
+        for p in 0..400u64 {
+            assert_eq!(tt.get_nimber(&p), Some(((p / 4) % 7) as u8));
+        }
+        assert_eq!(tt.get_nimber(&400), None);
+    }
+
+    #[test]
+    fn concurrent_tt_succinct64() {
+        let tt = ConcurrentTTSuccinct64::new(4, 2, 2, bit_mixer::stafford13, cluster_policy::Fifo);
+        tt.store_nimber_concurrently(1, 0);
+        tt.store_nimber_concurrently(3, 1);
+        tt.store_nimber_concurrently(4, 2);
+        tt.store_nimber_concurrently(5, 4);  // nimber too large, should be ignored
+        assert_eq!(tt.get_nimber(&1), Some(0));
+        assert_eq!(tt.get_nimber(&3), Some(1));
+        assert_eq!(tt.get_nimber(&4), Some(2));
+        assert_eq!(tt.get_nimber(&5), None);
+        assert_eq!(tt.capacity(), 16);
+        assert_eq!(tt.len(), 3);
+    }
+
+    #[test]
+    fn concurrent_tt_succinct64_threads() {
+        let tt = ConcurrentTTSuccinct64::new(10, 2, 4, bit_mixer::stafford13, cluster_policy::Lru);
+        std::thread::scope(|s| {
+            for t in 0..4u64 {
+                let tt = &tt;
+                s.spawn(move || for p in 0..2000u64 {
+                    let position = 4*p + t;
+                    tt.store_nimber_concurrently(position, (position % 13) as u8);
+                    if let Some(n) = tt.get_nimber(&(position / 2)) { assert_eq!(n, ((position / 2) % 13) as u8); }
+                });
+            }
+        });
+        // entries can be lost, but never wrong:
+        let mut found = 0;
+        for p in 0..8000u64 {
+            if let Some(n) = tt.get_nimber(&p) {
+                assert_eq!(n, (p % 13) as u8);
+                found += 1;
+            }
+        }
+        assert_eq!(found, tt.len());
+    }
+}
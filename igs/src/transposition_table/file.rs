@@ -0,0 +1,245 @@
+use std::fs::OpenOptions;
+use std::io;
+use std::path::Path;
+use std::sync::atomic::AtomicU32;
+use memmap2::MmapRaw;
+
+/// Magic bytes at the beginning of each transposition table file.
+const MAGIC: [u8; 8] = *b"IGSTT64\0";
+
+/// Version of the transposition table file format.
+const VERSION: u32 = 2;
+
+/// Written in native byte order, allows to detect the files created on machines with different endianness.
+const BYTE_ORDER_MARK: u32 = 0x01020304;
+
+/// Size of the header in bytes. The entries begin just after the header, which keeps them page-aligned.
+const HEADER_SIZE: usize = 4096;
+
+/// Configuration of the transposition table stored in the header of its file.
+/// Tables with different headers are incompatible.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct TTFileHeader {
+    /// Description of the game and its initial position (e.g. board size).
+    pub game: String,
+    /// Name of the bit mixer used.
+    pub bit_mixer: String,
+    /// Name of the cluster policy used.
+    pub cluster_policy: String,
+    /// Base 2 logarithm of the number of entries.
+    pub capacity_log2: u8,
+    /// Base 2 logarithm of the number of entries in each cluster.
+    pub cluster_capacity_log2: u8,
+    /// Number of bits used to store nimber in each entry.
+    pub bits_per_nimber: u8
+}
+
+impl TTFileHeader {
+    /// Returns the header serialized to bytes or error if it is too long to fit `HEADER_SIZE`.
+    fn to_bytes(&self) -> io::Result<Vec<u8>> {
+        let mut result = Vec::with_capacity(HEADER_SIZE);
+        result.extend_from_slice(&MAGIC);
+        result.extend_from_slice(&VERSION.to_ne_bytes());
+        result.extend_from_slice(&BYTE_ORDER_MARK.to_ne_bytes());
+        result.extend_from_slice(&[self.capacity_log2, self.cluster_capacity_log2, self.bits_per_nimber]);
+        for s in [&self.game, &self.bit_mixer, &self.cluster_policy] {
+            result.extend_from_slice(&(s.len() as u32).to_ne_bytes());
+            result.extend_from_slice(s.as_bytes());
+        }
+        if result.len() > HEADER_SIZE {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "transposition table file header is too long"));
+        }
+        Ok(result)
+    }
+
+    /// Reads the header from the given `bytes`.
+    fn from_bytes(mut bytes: &[u8]) -> io::Result<Self> {
+        fn invalid(msg: &str) -> io::Error { io::Error::new(io::ErrorKind::InvalidData, msg) }
+        fn take<'a>(bytes: &mut &'a [u8], len: usize) -> io::Result<&'a [u8]> {
+            if bytes.len() < len { return Err(invalid("transposition table file header is truncated")); }
+            let (result, rest) = bytes.split_at(len);
+            *bytes = rest;
+            Ok(result)
+        }
+        fn take_u32(bytes: &mut &[u8]) -> io::Result<u32> {
+            Ok(u32::from_ne_bytes(take(bytes, 4)?.try_into().unwrap()))
+        }
+        fn take_string(bytes: &mut &[u8]) -> io::Result<String> {
+            let len = take_u32(bytes)? as usize;
+            String::from_utf8(take(bytes, len)?.to_vec()).map_err(|_| invalid("transposition table file header is corrupted"))
+        }
+        if take(&mut bytes, MAGIC.len())? != MAGIC { return Err(invalid("the file is not a transposition table")); }
+        let version = take_u32(&mut bytes)?;
+        if version != VERSION {
+            return Err(invalid(&format!("unsupported version {} of transposition table file (expected {})", version, VERSION)));
+        }
+        if take_u32(&mut bytes)? != BYTE_ORDER_MARK {
+            return Err(invalid("transposition table file has been created on a machine with different byte order"));
+        }
+        let conf = take(&mut bytes, 3)?;
+        let (capacity_log2, cluster_capacity_log2, bits_per_nimber) = (conf[0], conf[1], conf[2]);
+        Ok(Self {
+            game: take_string(&mut bytes)?,
+            bit_mixer: take_string(&mut bytes)?,
+            cluster_policy: take_string(&mut bytes)?,
+            capacity_log2, cluster_capacity_log2, bits_per_nimber
+        })
+    }
+}
+
+/// Entries of a transposition table kept in a memory-mapped file, preceded by a versioned header.
+///
+/// The content of the file survives killing of the process,
+/// since all changes are made directly in the (shared) memory mapping.
+/// The header of a new file is written only after its entries are initialized and flushed,
+/// so a file whose initialization has been interrupted has no header and is initialized again when opened.
+pub struct TTFile {
+    /// The mapping is accessed only by raw pointers, which allows to view the entries as atomics shared by many threads.
+    mmap: MmapRaw
+}
+
+impl TTFile {
+    /// Opens the file with the given `path` and checks if it has been created with the given `header`.
+    /// If the file does not exist, is empty, or its initialization has not been completed
+    /// (it has the expected size but no header), it is (re)initialized: filled with `empty_entry` and then given the `header`.
+    /// Returns error if the file is not a transposition table or has been created with a different configuration.
+    pub fn open<P: AsRef<Path>>(path: P, header: &TTFileHeader, empty_entry: u32) -> io::Result<Self> {
+        let header_bytes = header.to_bytes()?;
+        let file = OpenOptions::new().read(true).write(true).create(true).truncate(false).open(path)?;
+        let file_len = (HEADER_SIZE + (4usize << header.capacity_log2)) as u64;
+        let current_len = file.metadata()?.len();
+        if current_len == 0 { file.set_len(file_len)?; }
+        let mut result = Self { mmap: MmapRaw::map_raw(&file)? };
+        if current_len == 0 || (current_len == file_len && result.header_bytes()[..MAGIC.len()].iter().all(|b| *b == 0)) {
+            result.header_bytes_mut().fill(0);
+            result.entries_mut().fill(empty_entry);
+            result.mmap.flush()?;
+            result.header_bytes_mut()[..header_bytes.len()].copy_from_slice(&header_bytes);
+            result.mmap.flush_range(0, HEADER_SIZE)?;
+            return Ok(result);
+        }
+        let stored = TTFileHeader::from_bytes(result.header_bytes())?;
+        if stored != *header {
+            return Err(io::Error::new(io::ErrorKind::InvalidData,
+                format!("transposition table file has been created with incompatible configuration {:?} (expected {:?})", stored, header)));
+        }
+        if current_len != file_len {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "transposition table file has unexpected size"));
+        }
+        Ok(result)
+    }
+
+    /// Returns the header read from the file with the given `path`.
+    pub fn read_header<P: AsRef<Path>>(path: P) -> io::Result<TTFileHeader> {
+        let mut bytes = vec![0u8; HEADER_SIZE];
+        let mut file = std::fs::File::open(path)?;
+        let read = io::Read::read(&mut file, &mut bytes)?;
+        TTFileHeader::from_bytes(&bytes[..read])
+    }
+
+    /// Writes all changes to the file.
+    pub fn flush(&self) -> io::Result<()> {
+        self.mmap.flush()
+    }
+
+    /// Returns (at most `HEADER_SIZE`) bytes at the beginning of the file.
+    fn header_bytes(&self) -> &[u8] {
+        unsafe { std::slice::from_raw_parts(self.mmap.as_ptr(), HEADER_SIZE.min(self.mmap.len())) }
+    }
+
+    fn header_bytes_mut(&mut self) -> &mut [u8] {
+        unsafe { std::slice::from_raw_parts_mut(self.mmap.as_mut_ptr(), HEADER_SIZE.min(self.mmap.len())) }
+    }
+
+    /// Returns the pointer to the first entry and the number of entries.
+    #[inline(always)] fn entries_ptr(&self) -> (*mut u32, usize) {
+        // safety: the mapping is page-aligned and HEADER_SIZE is a multiple of 4,
+        // and the files that are shorter than HEADER_SIZE are not mapped (they are resized or rejected by `open`)
+        (unsafe { self.mmap.as_mut_ptr().add(HEADER_SIZE) } as *mut u32, self.mmap.len().saturating_sub(HEADER_SIZE) / 4)
+    }
+
+    #[inline(always)] fn entries(&self) -> &[u32] {
+        let (ptr, len) = self.entries_ptr();
+        unsafe { std::slice::from_raw_parts(ptr, len) }
+    }
+
+    #[inline(always)] fn entries_mut(&mut self) -> &mut [u32] {
+        let (ptr, len) = self.entries_ptr();
+        unsafe { std::slice::from_raw_parts_mut(ptr, len) }
+    }
+}
+
+impl AsRef<[u32]> for TTFile {
+    #[inline(always)] fn as_ref(&self) -> &[u32] { self.entries() }
+}
+
+impl AsMut<[u32]> for TTFile {
+    #[inline(always)] fn as_mut(&mut self) -> &mut [u32] { self.entries_mut() }
+}
+
+impl AsRef<[AtomicU32]> for TTFile {
+    /// Returns the entries as atomics, which allows many threads to update the shared mapping.
+    #[inline(always)] fn as_ref(&self) -> &[AtomicU32] {
+        let (ptr, len) = self.entries_ptr();
+        // safety: AtomicU32 has the same in-memory representation as u32,
+        // and the pointer comes directly from the writable mapping (not from a shared borrow of its content)
+        unsafe { std::slice::from_raw_parts(ptr as *const AtomicU32, len) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{TTSuccinct64, TTFileConf, ConcurrentTTSuccinct64, NimbersProvider, NimbersStorer, ConcurrentNimbersStorer, bit_mixer, cluster_policy};
+    use crate::dbs::HasLen;
+
+    #[test]
+    fn reopen() {
+        let path = std::env::temp_dir().join(format!("igs_tt_file_test_{}.bin", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let conf = TTFileConf { path: &path, game: "test" };
+        {
+            let mut tt = TTSuccinct64::with_file(&conf, 4, 2, 2, bit_mixer::Stafford13, cluster_policy::Fifo).unwrap();
+            assert_eq!(tt.len(), 0);
+            tt.store_nimber(1, 0);
+            tt.store_nimber(3, 1);
+            tt.store_nimber(4, 2);
+        }
+        {
+            let tt = TTSuccinct64::with_file(&conf, 4, 2, 2, bit_mixer::Stafford13, cluster_policy::Fifo).unwrap();
+            assert_eq!(tt.get_nimber(&1), Some(0));
+            assert_eq!(tt.get_nimber(&3), Some(1));
+            assert_eq!(tt.get_nimber(&4), Some(2));
+            assert_eq!(tt.len(), 3);
+        }
+        {   // the concurrent table has the same layout
+            let tt = ConcurrentTTSuccinct64::with_file(&conf, 4, 2, 2, bit_mixer::Stafford13, cluster_policy::Fifo).unwrap();
+            assert_eq!(tt.get_nimber(&3), Some(1));
+            tt.store_nimber_concurrently(5, 3);
+            assert_eq!(tt.len(), 4);
+        }
+        assert!(TTSuccinct64::with_file(&TTFileConf { game: "other", ..conf }, 4, 2, 2, bit_mixer::Stafford13, cluster_policy::Fifo).is_err());
+        assert!(TTSuccinct64::with_file(&conf, 4, 2, 2, bit_mixer::Stafford13, cluster_policy::Lru).is_err());
+        assert!(TTSuccinct64::with_file(&conf, 4, 2, 2, bit_mixer::Moremur, cluster_policy::Fifo).is_err());
+        assert!(TTSuccinct64::with_file(&conf, 5, 2, 2, bit_mixer::Stafford13, cluster_policy::Fifo).is_err());
+        assert!(TTSuccinct64::with_file(&conf, 4, 2, 3, bit_mixer::Stafford13, cluster_policy::Fifo).is_err());
+        let header = super::TTFile::read_header(&path).unwrap();
+        assert_eq!((header.game.as_str(), header.bit_mixer.as_str(), header.cluster_policy.as_str()), ("test", "stafford13", "fifo"));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn interrupted_initialization() {
+        let path = std::env::temp_dir().join(format!("igs_tt_file_interrupted_test_{}.bin", std::process::id()));
+        let conf = TTFileConf { path: &path, game: "test" };
+        // the file of the expected size, whose header has not been written yet
+        std::fs::write(&path, vec![0u8; super::HEADER_SIZE + 4 * 16]).unwrap();
+        let tt = TTSuccinct64::with_file(&conf, 4, 2, 2, bit_mixer::Stafford13, cluster_policy::Fifo).unwrap();
+        assert_eq!(tt.len(), 0);
+        drop(tt);
+        assert_eq!(super::TTFile::read_header(&path).unwrap().game, "test");
+        // other file of unexpected size is not overwritten
+        std::fs::write(&path, vec![0u8; 100]).unwrap();
+        assert!(TTSuccinct64::with_file(&conf, 4, 2, 2, bit_mixer::Stafford13, cluster_policy::Fifo).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
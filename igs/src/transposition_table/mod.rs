@@ -1,13 +1,18 @@
 use core::ptr;
 
 pub use protected::ProtectedTT;
+pub use concurrent::{ShardedTT, ConcurrentTTSuccinct64};
+pub use file::{TTFile, TTFileHeader};
 
-pub use crate::dbs::{NimbersProvider, NimbersStorer};
+pub use crate::dbs::{NimbersProvider, NimbersStorer, ConcurrentNimbersStorer};
 use crate::dbs::HasLen;
+use bit_mixer::NamedBitMixer;
 
 pub mod bit_mixer;
 
 mod protected;
+mod concurrent;
+mod file;
 
 const EMPTY_ENTRY: u32 = u32::MAX;
 
@@ -86,15 +91,32 @@ pub trait ClusterPolicy {
     }
 }
 
+/// Cluster policy identified by its name, which is recorded in the header of a transposition table file
+/// (see `TTSuccinct64::with_file`).
+pub trait NamedClusterPolicy: ClusterPolicy {
+    /// Name of the policy.
+    const NAME: &'static str;
+}
+
+/// Cluster policy without internal state, which can be used by many threads at once
+/// (for example by `ConcurrentTTSuccinct64`).
+pub trait StatelessClusterPolicy: ClusterPolicy + Copy + Sync {}
+
 pub mod cluster_policy {
     use core::ptr;
 
-    use super::{ClusterConf, ClusterPolicy, EMPTY_ENTRY};
+    use super::{ClusterConf, ClusterPolicy, NamedClusterPolicy, StatelessClusterPolicy, EMPTY_ENTRY};
 
+    #[derive(Copy, Clone)]
     pub struct Fifo;
     impl ClusterPolicy for Fifo {}
+    impl NamedClusterPolicy for Fifo { const NAME: &'static str = "fifo"; }
+    impl StatelessClusterPolicy for Fifo {}
 
+    #[derive(Copy, Clone)]
     pub struct FifoLru;
+    impl StatelessClusterPolicy for FifoLru {}
+    impl NamedClusterPolicy for FifoLru { const NAME: &'static str = "fifo_lru"; }
     impl ClusterPolicy for FifoLru {
         #[inline(always)]
         fn get_nimber_and_self_organize(&self, cluster_conf: &ClusterConf, cluster: &mut [u32], id_to_find: u32) -> Option<u8> {
@@ -113,7 +135,10 @@ pub mod cluster_policy {
         }
     }
 
+    #[derive(Copy, Clone)]
     pub struct Lru;
+    impl StatelessClusterPolicy for Lru {}
+    impl NamedClusterPolicy for Lru { const NAME: &'static str = "lru"; }
     impl ClusterPolicy for Lru {
         #[inline(always)]
         fn get_nimber_and_self_organize(&self, cluster_conf: &ClusterConf, cluster: &mut [u32], id_to_find: u32) -> Option<u8> {
@@ -133,16 +158,22 @@ pub mod cluster_policy {
         }
     }
 
+    #[derive(Copy, Clone)]
     pub struct LowestNimbers;
+    impl StatelessClusterPolicy for LowestNimbers {}
 
+    impl NamedClusterPolicy for LowestNimbers { const NAME: &'static str = "lowest_nimbers"; }
     impl ClusterPolicy for LowestNimbers {
         #[inline(always)] fn store_entry(&mut self, cluster_conf: &ClusterConf, cluster: &mut [u32], to_store: u32, nimber: u8) {
             nimbers_store_entry(cluster_conf, cluster, to_store, |stored| nimber <= stored)
         }
     }
 
+    #[derive(Copy, Clone)]
     pub struct LargestNimbers;
+    impl StatelessClusterPolicy for LargestNimbers {}
 
+    impl NamedClusterPolicy for LargestNimbers { const NAME: &'static str = "largest_nimbers"; }
     impl ClusterPolicy for LargestNimbers {
         #[inline(always)] fn store_entry(&mut self, cluster_conf: &ClusterConf, cluster: &mut [u32], to_store: u32, nimber: u8) {
             nimbers_store_entry(cluster_conf, cluster, to_store, |stored| nimber >= stored)
@@ -174,6 +205,7 @@ pub mod cluster_policy {
     #[derive(Default, Copy, Clone)]
     pub struct BalancedRandom { index: u32 }
 
+    impl NamedClusterPolicy for BalancedRandom { const NAME: &'static str = "balanced_random"; }
     impl ClusterPolicy for BalancedRandom {
         fn store_entry(&mut self, _cluster_conf: &ClusterConf, cluster: &mut [u32], to_store: u32, _nimber: u8) {
             let mut i = cluster.len() - 1;
@@ -202,8 +234,10 @@ pub mod cluster_policy {
 /// index of the cluster assigned to the position. So, when the cluster is full,
 /// more positions assigned to the cluster cannot be stored, even if the whole table is not full
 /// (then LIFO strategy is used).
-pub struct TTSuccinct64<BitMixer: Fn(u64, u64) -> u64, Policy: ClusterPolicy = cluster_policy::Fifo> {
-    data: Box<[u32]>,
+///
+/// The entries are stored in `Data`, which is either a boxed slice (by default) or a memory-mapped `TTFile`.
+pub struct TTSuccinct64<BitMixer: Fn(u64, u64) -> u64, Policy: ClusterPolicy = cluster_policy::Fifo, Data = Box<[u32]>> {
+    data: Data,
     /// Clusters configuration.
     cluster_conf: ClusterConf,
     /// maximal position number which can be stored (larger are ignored)
@@ -221,14 +255,68 @@ impl<BitMixer: Fn(u64, u64) -> u64, Policy: ClusterPolicy> TTSuccinct64<BitMixer
     /// Each entry encodes nimber (using `bits_per_nimber` bits) and `32-bits_per_nimber` bits of position id.
     /// Entries are grouped in clusters, and each cluster stores `2` to power `cluster_capacity_log2` entries.
     pub fn new(capacity_log2: u8, cluster_capacity_log2: u8, bits_per_nimber: u8, bit_mixer: BitMixer, cluster_policy: Policy) -> Self {
+        Self::with_data(vec![EMPTY_ENTRY; 1usize<< capacity_log2].into_boxed_slice(),
+            capacity_log2, cluster_capacity_log2, bits_per_nimber, bit_mixer, cluster_policy)
+    }
+}
+
+/// Description of the transposition table kept in a file, stored in the file header (see `TTFileHeader`),
+/// together with the names of the bit mixer and the cluster policy, and the configuration of entries and clusters.
+/// The file can be reused only with the same description.
+#[derive(Clone, Copy)]
+pub struct TTFileConf<'a, P: AsRef<std::path::Path>> {
+    /// Path of the file.
+    pub path: P,
+    /// Description of the game and its initial position (e.g. board size).
+    pub game: &'a str
+}
+
+impl<P: AsRef<std::path::Path>> TTFileConf<'_, P> {
+    /// Opens (or creates) the file with the entries of the table of the given configuration,
+    /// which uses `BitMixer` and `Policy`.
+    fn open<BitMixer: NamedBitMixer, Policy: NamedClusterPolicy>(&self, capacity_log2: u8, cluster_capacity_log2: u8, bits_per_nimber: u8) -> std::io::Result<TTFile> {
+        let header = TTFileHeader {
+            game: self.game.to_owned(),
+            bit_mixer: BitMixer::NAME.to_owned(),
+            cluster_policy: Policy::NAME.to_owned(),
+            capacity_log2, cluster_capacity_log2, bits_per_nimber
+        };
+        TTFile::open(&self.path, &header, EMPTY_ENTRY)
+    }
+}
+
+impl<Policy: NamedClusterPolicy> TTSuccinct64<fn(u64, u64) -> u64, Policy, TTFile> {
+
+    /// Construct TTSuccinct64 whose entries are kept in the memory-mapped file described by `file`.
+    /// The content of the file (if it exists) is reused, so the table is warm after restart.
+    /// The names of `BitMixer` and `Policy` are recorded in the file header.
+    ///
+    /// The meaning of the other parameters is the same as in `new`.
+    /// Returns error if the existing file has been created with a different configuration.
+    pub fn with_file<P: AsRef<std::path::Path>, BitMixer: NamedBitMixer>(file: &TTFileConf<P>,
+        capacity_log2: u8, cluster_capacity_log2: u8, bits_per_nimber: u8, _bit_mixer: BitMixer, cluster_policy: Policy) -> std::io::Result<Self>
+    {
+        Ok(Self::with_data(file.open::<BitMixer, Policy>(capacity_log2, cluster_capacity_log2, bits_per_nimber)?,
+            capacity_log2, cluster_capacity_log2, bits_per_nimber, BitMixer::mix, cluster_policy))
+    }
+
+    /// Writes all changes to the file.
+    pub fn flush(&self) -> std::io::Result<()> { self.data.flush() }
+}
+
+impl<BitMixer: Fn(u64, u64) -> u64, Policy: ClusterPolicy, Data: AsRef<[u32]> + AsMut<[u32]>> TTSuccinct64<BitMixer, Policy, Data> {
+
+    /// Construct TTSuccinct64 whose `2` to power of `capacity_log2` entries are stored in `data`.
+    fn with_data(data: Data, capacity_log2: u8, cluster_capacity_log2: u8, bits_per_nimber: u8, bit_mixer: BitMixer, cluster_policy: Policy) -> Self {
         assert!(capacity_log2 >= cluster_capacity_log2);
         assert!(bits_per_nimber <= 8);
+        assert_eq!(data.as_ref().len(), 1usize << capacity_log2);
         let cluster_conf = ClusterConf::new_log2(cluster_capacity_log2, bits_per_nimber);
         let clusters_num_log2 = capacity_log2 - cluster_capacity_log2;  // log2 z liczby klastrów
         let bits_per_key = clusters_num_log2 + cluster_conf.id_size;    // całkowita liczba bitów użyta z klucza
         assert!(bits_per_key <= 64);
         Self {
-            data: vec![EMPTY_ENTRY; 1usize<< capacity_log2].into_boxed_slice(),
+            data,
             key_mask: (1u64 << bits_per_key).wrapping_sub(1),
             cluster_conf,
             mix_bits: bit_mixer,
@@ -237,7 +325,7 @@ impl<BitMixer: Fn(u64, u64) -> u64, Policy: ClusterPolicy> TTSuccinct64<BitMixer
     }
 
     /// Returns the capacity of the table (total number of entries).
-    pub fn capacity(&self) -> usize { self.data.len() }
+    pub fn capacity(&self) -> usize { self.data.as_ref().len() }
 
     /// Returns first index of the cluster for the given `key`.
     #[inline(always)] fn cluster_begin(&self, key: u64) -> usize {
@@ -247,7 +335,7 @@ impl<BitMixer: Fn(u64, u64) -> u64, Policy: ClusterPolicy> TTSuccinct64<BitMixer
     /// Returns the cluster for the given `key`.
     #[inline(always)] fn cluster(&self, key: u64) -> &[u32] {
         let cl_beg = self.cluster_begin(key);
-        &self.data[cl_beg..cl_beg+(self.cluster_conf.capacity as usize)]
+        &self.data.as_ref()[cl_beg..cl_beg+(self.cluster_conf.capacity as usize)]
     }
 
     fn pos_id_and_cluster(&self, position: u64) -> (u32, &[u32]) {
@@ -256,14 +344,14 @@ impl<BitMixer: Fn(u64, u64) -> u64, Policy: ClusterPolicy> TTSuccinct64<BitMixer
     }
 }
 
-impl<BitMixer: Fn(u64, u64) -> u64, Policy: ClusterPolicy> HasLen for TTSuccinct64<BitMixer, Policy> {
+impl<BitMixer: Fn(u64, u64) -> u64, Policy: ClusterPolicy, Data: AsRef<[u32]> + AsMut<[u32]>> HasLen for TTSuccinct64<BitMixer, Policy, Data> {
     /// Returns the number of elements in the table (number of occupied entries).
     fn len(&self) -> usize {
-        self.data.iter().filter(|e| **e != EMPTY_ENTRY).count()
+        self.data.as_ref().iter().filter(|e| **e != EMPTY_ENTRY).count()
     }
 }
 
-impl<BitMixer: Fn(u64, u64) -> u64, Policy: ClusterPolicy> NimbersProvider<u64> for TTSuccinct64<BitMixer, Policy> {
+impl<BitMixer: Fn(u64, u64) -> u64, Policy: ClusterPolicy, Data: AsRef<[u32]> + AsMut<[u32]>> NimbersProvider<u64> for TTSuccinct64<BitMixer, Policy, Data> {
     fn get_nimber(&self, position: &u64) -> Option<u8> {
         if *position > self.key_mask { return None; }
         let (id_to_find, cluster) = self.pos_id_and_cluster(*position);
@@ -277,12 +365,12 @@ impl<BitMixer: Fn(u64, u64) -> u64, Policy: ClusterPolicy> NimbersProvider<u64>
         let cl_beg = self.cluster_begin(key);
         self.cluster_policy.get_nimber_and_self_organize(
             &self.cluster_conf,
-            &mut self.data[cl_beg..cl_beg+(self.cluster_conf.capacity as usize)],
+            &mut self.data.as_mut()[cl_beg..cl_beg+(self.cluster_conf.capacity as usize)],
             id_to_find)
     }
 }
 
-impl<BitMixer: Fn(u64, u64) -> u64, Policy: ClusterPolicy> NimbersStorer<u64> for TTSuccinct64<BitMixer, Policy> {
+impl<BitMixer: Fn(u64, u64) -> u64, Policy: ClusterPolicy, Data: AsRef<[u32]> + AsMut<[u32]>> NimbersStorer<u64> for TTSuccinct64<BitMixer, Policy, Data> {
     fn store_nimber(&mut self, position: u64, nimber: u8) {
         if position > self.key_mask || nimber > self.cluster_conf.max_nimber { return; }
         let key = (self.mix_bits)(position, self.key_mask);
@@ -291,7 +379,7 @@ impl<BitMixer: Fn(u64, u64) -> u64, Policy: ClusterPolicy> NimbersStorer<u64> fo
         let cl_beg = self.cluster_begin(key);
         self.cluster_policy.store_entry(
             &self.cluster_conf,
-            &mut self.data[cl_beg..cl_beg+(self.cluster_conf.capacity as usize)],
+            &mut self.data.as_mut()[cl_beg..cl_beg+(self.cluster_conf.capacity as usize)],
             to_store, nimber)
     }
 }
@@ -2,8 +2,8 @@ use std::collections::HashMap;
 
 use clap::Args;
 
-use crate::{solver::{PruningMethod, Without, print_nimber_of_simple}, tt::{TTConf, TTKind}, constdb::ConstDBConf};
-use igs::{games::chomp::{self, FewerBarsFirst}, transposition_table::{NimbersProvider, ProtectedTT, NimbersStorer, TTSuccinct64, bit_mixer::stafford13, cluster_policy::Fifo}, game::Game, solver::Solver};
+use crate::{report, solver::{PruningMethod, stats, print_misere_outcome_of_simple, print_nimber_of_simple, print_nimber_of_simple_parallel}, tt::{TTConf, TTKind}, constdb::ConstDBConf};
+use igs::{games::chomp::{self, FewerBarsFirst}, transposition_table::{ConcurrentNimbersStorer, ShardedTT, ConcurrentTTSuccinct64, NimbersProvider, ProtectedTT, NimbersStorer, TTSuccinct64, bit_mixer::stafford13, cluster_policy::Fifo}, game::Game, solver::{Solver, CheckpointFile}};
 
 /// Returns min( (n k), u64::MAX )
 fn saturating_combinations(n: u64, mut k: u64) -> u64 {
@@ -44,31 +44,46 @@ pub struct Conf {
 }
 
 impl Conf {
-    pub fn run(self, method: Option<PruningMethod>, tt_conf: TTConf, cdb: ConstDBConf) {
-        let method = method.unwrap_or(PruningMethod::Def);
-        println!("---=== Chomp {}x{} {:?} ===---", self.cols, self.rows, method);
+    pub fn run_misere(self) {
+        report::title(&format!("Chomp {}x{}", self.cols, self.rows), None);
         let game = chomp::Chomp::new(self.cols, self.rows);
-        if cdb.segments == 0 {
-            self.run_with_cdb(&game, method, tt_conf, ())
-        } else {
-            todo!("end-db is not yet supported for Chomp")
-        }
+        let mut solver = Solver::new(&game, HashMap::<<chomp::Chomp as Game>::Position, u8>::new(), (), (), stats());
+        print_misere_outcome_of_simple(&mut solver);
+        report::stats_only(&solver.stats);
     }
 
-    fn run_with_cdb<CDB>(self, game: &chomp::Chomp, method: PruningMethod, tt_conf: TTConf, cdb: CDB) 
-        where CDB: NimbersProvider<<chomp::Chomp as Game>::Position>,
+    pub fn run(self, method: PruningMethod, tt_conf: TTConf, _cdb: ConstDBConf, threads: usize, checkpoint: Option<CheckpointFile>) {
+        let game_desc = format!("Chomp {}x{}", self.cols, self.rows);
+        report::title(&game_desc, Some(&method));
+        let checkpoint = checkpoint.map(|c| c.with_game(game_desc));
+        let game = chomp::Chomp::new(self.cols, self.rows);
+        self.run_with_cdb(&game, method, checkpoint, tt_conf, threads, ())
+    }
+
+    fn run_with_cdb<CDB>(self, game: &chomp::Chomp, method: PruningMethod, checkpoint: Option<CheckpointFile>, tt_conf: TTConf, threads: usize, cdb: CDB) 
+        where CDB: NimbersProvider<<chomp::Chomp as Game>::Position> + Sync,
     {   // TODO copied from Cram, should be fixed
-        match tt_conf.kind.unwrap_or_else(|| if aproximate_position_num(self.cols, self.rows) > (1<<28) { crate::tt::TTKind::Succinct } else { crate::tt::TTKind::HashMap }) {
-            TTKind::None => self.run_with_prot_tt_cdb(game, method, (), tt_conf.protect, cdb),
-            TTKind::HashMap => self.run_with_prot_tt_cdb(game, method, HashMap::new(), tt_conf.protect, cdb),
-            TTKind::Succinct => self.run_with_prot_tt_cdb(
-                game, method,
-                 TTSuccinct64::new(tt_conf.size_log2(8) + (32-4) /*GB*/, 2, 4, stafford13, Fifo),
-                  tt_conf.protect, cdb),
+        if threads > 1 && matches!(method, PruningMethod::BrAspSet) {
+            return self.run_parallel_with_cdb(game, tt_conf, threads, cdb);
+        }
+        match tt_conf.kind_or_else(|| if aproximate_position_num(self.cols, self.rows) > (1<<28) { crate::tt::TTKind::Succinct } else { crate::tt::TTKind::HashMap }) {
+            TTKind::None => self.run_with_prot_tt_cdb(game, method, checkpoint, (), tt_conf.protect, cdb),
+            TTKind::HashMap => self.run_with_prot_tt_cdb(game, method, checkpoint, HashMap::new(), tt_conf.protect, cdb),
+            TTKind::Succinct => if let Some(file) = &tt_conf.file {
+                self.run_with_prot_tt_cdb(
+                    game, method, checkpoint,
+                    tt_conf.succinct_file(8, file, &format!("Chomp {}x{}", self.cols, self.rows)),
+                    tt_conf.protect, cdb)
+            } else {
+                self.run_with_prot_tt_cdb(
+                    game, method, checkpoint,
+                    TTSuccinct64::new(tt_conf.size_log2(8) + (32-4) /*GB*/, 2, 4, stafford13, Fifo),
+                    tt_conf.protect, cdb)
+            },
         }
     }
 
-    fn run_with_prot_tt_cdb<TT, CDB>(self, game: &chomp::Chomp, method: PruningMethod, tt: TT, protect_tt: bool, cdb: CDB) 
+    fn run_with_prot_tt_cdb<TT, CDB>(self, game: &chomp::Chomp, method: PruningMethod, checkpoint: Option<CheckpointFile>, tt: TT, protect_tt: bool, cdb: CDB) 
         where
          TT: NimbersProvider<<chomp::Chomp as Game>::Position> + NimbersStorer<<chomp::Chomp as Game>::Position> + igs::dbs::HasLen,
          CDB: NimbersProvider<<chomp::Chomp as Game>::Position>,
@@ -79,13 +94,13 @@ impl Conf {
                 format!("chomp_{}x{}_TT.bin", self.cols, self.rows),
                 |_, p| p.count_ones() as u16 >= min_fields_to_protect,
                 tt, 128);
-            self.run_with_tt_cdb(game, method, tt, cdb);
+            self.run_with_tt_cdb(game, method, checkpoint, tt, cdb);
         } else {
-            self.run_with_tt_cdb(game, method, tt, cdb);
+            self.run_with_tt_cdb(game, method, checkpoint, tt, cdb);
         }
     }
 
-    fn run_with_tt_cdb<TT, CDB>(self, game: &chomp::Chomp, method: PruningMethod, tt: TT, cdb: CDB) 
+    fn run_with_tt_cdb<TT, CDB>(self, game: &chomp::Chomp, method: PruningMethod, checkpoint: Option<CheckpointFile>, tt: TT, cdb: CDB) 
         where
          TT: NimbersProvider<<chomp::Chomp as Game>::Position> + NimbersStorer<<chomp::Chomp as Game>::Position> + igs::dbs::HasLen,
          CDB: NimbersProvider<<chomp::Chomp as Game>::Position>,
@@ -96,11 +111,32 @@ impl Conf {
             cdb,
             //move_sorter
             FewerBarsFirst{}, // TODO configurable
-            Without
+            stats()
         );
-        print_nimber_of_simple(&mut solver, method);
-        println!("TT size: {}", solver.transposition_table.len());  // TODO move to print_nimber_of_simple
-        println!("{}", solver.stats);   // TODO move to print_nimber_of_simple
+        print_nimber_of_simple(&mut solver, method, checkpoint);
+        report::tt_size_and_stats(solver.transposition_table.len(), &solver.stats);
+    }
+
+    fn run_parallel_with_cdb<CDB>(self, game: &chomp::Chomp, tt_conf: TTConf, threads: usize, cdb: CDB)
+        where CDB: NimbersProvider<<chomp::Chomp as Game>::Position> + Sync,
+    {
+        match tt_conf.kind.unwrap_or(TTKind::HashMap) {
+            TTKind::None => self.run_parallel_with_tt_cdb(game, threads, &(), cdb),
+            TTKind::HashMap => self.run_parallel_with_tt_cdb(game, threads, &ShardedTT::new(8, HashMap::new), cdb),
+            TTKind::Succinct => self.run_parallel_with_tt_cdb(game, threads,
+                 &ConcurrentTTSuccinct64::new(tt_conf.size_log2(8) + (32-4) /*GB*/, 2, 4, stafford13, Fifo), cdb),
+        }
+    }
+
+    fn run_parallel_with_tt_cdb<TT, CDB>(self, game: &chomp::Chomp, threads: usize, tt: &TT, cdb: CDB)
+        where
+         TT: ConcurrentNimbersStorer<<chomp::Chomp as Game>::Position> + igs::dbs::HasLen,
+         CDB: NimbersProvider<<chomp::Chomp as Game>::Position> + Sync,
+    {
+        report::threads(threads);
+        let mut solver = Solver::new(game, tt, cdb, FewerBarsFirst{}, stats());
+        print_nimber_of_simple_parallel(&mut solver, threads);
+        report::tt_size_and_stats(solver.transposition_table.len(), &solver.stats);
     }
 }
 
@@ -1,7 +1,7 @@
 use std::collections::HashMap;
 use clap::Args;
-use igs::{games::cram::{Cram, slices_provider::LimitedColumnsSliceProvider, SmallerComponentsFirst}, enddb::{EndDb, PrintStats, EndDbBuilderForDecomposableGame}, transposition_table::{NimbersProvider, TTSuccinct64, ProtectedTT, bit_mixer::stafford13, cluster_policy::Fifo, NimbersStorer}, game::Game, solver::Solver};
-use crate::{solver::{PruningMethod, print_nimber_of_decomposable, Without}, tt::{TTConf, TTKind}, constdb::ConstDBConf};
+use igs::{games::cram::{Cram, slices_provider::LimitedColumnsSliceProvider, SmallerComponentsFirst}, enddb::{EndDb, PrintStats, EndDbBuilderForDecomposableGame}, transposition_table::{ConcurrentNimbersStorer, ShardedTT, ConcurrentTTSuccinct64, NimbersProvider, TTSuccinct64, ProtectedTT, bit_mixer::stafford13, cluster_policy::Fifo, NimbersStorer}, game::Game, solver::{Solver, CheckpointFile}};
+use crate::{report, solver::{PruningMethod, print_nimber_of_decomposable, print_nimber_of_decomposable_parallel, print_misere_outcome_of_decomposable, stats}, tt::{TTConf, TTKind}, constdb::ConstDBConf};
 
 #[derive(Args, Clone, Copy)]
 pub struct Conf {
@@ -19,42 +19,61 @@ pub struct Conf {
 }
 
 impl Conf {
-    pub fn run(self, method: Option<PruningMethod>, tt_conf: TTConf, cdb: ConstDBConf) {
-        let method = method.unwrap_or(PruningMethod::BrAspSet);
-        println!("---=== Cram {}x{} {:?} ===---", self.cols, self.rows, method);
+    pub fn run_misere(self) {
+        report::title(&format!("Cram {}x{}", self.cols, self.rows), None);
+        let game = Cram::new(self.cols, self.rows);
+        let mut solver = Solver::new(&game, HashMap::<<Cram as Game>::Position, u8>::new(), (), (), stats());
+        print_misere_outcome_of_decomposable(&mut solver);
+        report::stats_only(&solver.stats);
+    }
+
+    pub fn run(self, method: PruningMethod, tt_conf: TTConf, cdb: ConstDBConf, threads: usize, checkpoint: Option<CheckpointFile>) {
+        let game_desc = format!("Cram {}x{}", self.cols, self.rows);
+        report::title(&game_desc, Some(&method));
+        let checkpoint = checkpoint.map(|c| c.with_game(game_desc));
         let game = Cram::new(self.cols, self.rows);
         if cdb.segments == 0 {
-            self.run_with_cdb(&game, method, tt_conf, ())
+            self.run_with_cdb(&game, method, checkpoint, tt_conf, threads, ())
         } else if self.edb_cols == 0 || self.edb_cols >= self.cols {    // no columns limit?
             let mut enddb = EndDb::build_with_lsmap_verifier(
                 &game,
                 PrintStats::default());
             for _ in 0..cdb.segments { enddb.build_slice_cached(&game, "igsolve_enddb").unwrap(); }
-            self.run_with_cdb(&game, method, tt_conf, enddb.done())
+            self.run_with_cdb(&game, method, checkpoint, tt_conf, threads, enddb.done())
         } else {
             let mut enddb = EndDb::build_with_lsmap_verifier(
                 LimitedColumnsSliceProvider::new(&game, self.edb_cols),
                 PrintStats::default());
             for _ in 0..cdb.segments { enddb.build_slice_cached(&game, format!("igsolve_enddb_{}cols", self.edb_cols)).unwrap(); }   
             // TODO edb_cols should be deeper in path
-            self.run_with_cdb(&game, method, tt_conf, enddb.done())
+            self.run_with_cdb(&game, method, checkpoint, tt_conf, threads, enddb.done())
         }
     }
 
-    fn run_with_cdb<CDB>(self, game: &Cram, method: PruningMethod, tt_conf: TTConf, cdb: CDB) 
-        where CDB: NimbersProvider<<Cram as Game>::Position>,
+    fn run_with_cdb<CDB>(self, game: &Cram, method: PruningMethod, checkpoint: Option<CheckpointFile>, tt_conf: TTConf, threads: usize, cdb: CDB) 
+        where CDB: NimbersProvider<<Cram as Game>::Position> + Sync,
     {
-        match tt_conf.kind.unwrap_or_else(|| if game.board_size() > 40 { crate::tt::TTKind::Succinct } else { crate::tt::TTKind::HashMap }) {
-            TTKind::None => self.run_with_prot_tt_cdb(game, method, (), tt_conf.protect, cdb),
-            TTKind::HashMap => self.run_with_prot_tt_cdb(game, method, HashMap::new(), tt_conf.protect, cdb),
-            TTKind::Succinct => self.run_with_prot_tt_cdb(
-                game, method,
-                 TTSuccinct64::new(tt_conf.size_log2(8) + (32-4) /*GB*/, 2, 4, stafford13, Fifo),
-                  tt_conf.protect, cdb),
+        if threads > 1 && matches!(method, PruningMethod::BrAspSet) {
+            return self.run_parallel_with_cdb(game, tt_conf, threads, cdb);
+        }
+        match tt_conf.kind_or_else(|| default_tt_kind(game)) {
+            TTKind::None => self.run_with_prot_tt_cdb(game, method, checkpoint, (), tt_conf.protect, cdb),
+            TTKind::HashMap => self.run_with_prot_tt_cdb(game, method, checkpoint, HashMap::new(), tt_conf.protect, cdb),
+            TTKind::Succinct => if let Some(file) = &tt_conf.file {
+                self.run_with_prot_tt_cdb(
+                    game, method, checkpoint,
+                    tt_conf.succinct_file(8, file, &format!("Cram {}x{}", self.cols, self.rows)),
+                    tt_conf.protect, cdb)
+            } else {
+                self.run_with_prot_tt_cdb(
+                    game, method, checkpoint,
+                    TTSuccinct64::new(tt_conf.size_log2(8) + (32-4) /*GB*/, 2, 4, stafford13, Fifo),
+                    tt_conf.protect, cdb)
+            },
         }
     }
 
-    fn run_with_prot_tt_cdb<TT, CDB>(self, game: &Cram, method: PruningMethod, tt: TT, protect_tt: bool, cdb: CDB) 
+    fn run_with_prot_tt_cdb<TT, CDB>(self, game: &Cram, method: PruningMethod, checkpoint: Option<CheckpointFile>, tt: TT, protect_tt: bool, cdb: CDB) 
         where
          TT: NimbersProvider<<Cram as Game>::Position> + NimbersStorer<<Cram as Game>::Position> + igs::dbs::HasLen,
          CDB: NimbersProvider<<Cram as Game>::Position>,
@@ -65,13 +84,13 @@ impl Conf {
                 format!("cram_{}x{}_TT.bin", self.cols, self.rows),
                 |_, p| p.count_ones() as u8 >= min_fields_to_protect,
                 tt, 128);
-            self.run_with_tt_cdb(game, method, tt, cdb);
+            self.run_with_tt_cdb(game, method, checkpoint, tt, cdb);
         } else {
-            self.run_with_tt_cdb(game, method, tt, cdb);
+            self.run_with_tt_cdb(game, method, checkpoint, tt, cdb);
         }
     }
 
-    fn run_with_tt_cdb<TT, CDB>(self, game: &Cram, method: PruningMethod, tt: TT, cdb: CDB) 
+    fn run_with_tt_cdb<TT, CDB>(self, game: &Cram, method: PruningMethod, checkpoint: Option<CheckpointFile>, tt: TT, cdb: CDB) 
         where
          TT: NimbersProvider<<Cram as Game>::Position> + NimbersStorer<<Cram as Game>::Position> + igs::dbs::HasLen,
          CDB: NimbersProvider<<Cram as Game>::Position>,
@@ -82,12 +101,43 @@ impl Conf {
             cdb,
             //move_sorter
             SmallerComponentsFirst{},
-            Without
+            stats()
         );
-        print_nimber_of_decomposable(&mut solver, method);
-        println!("TT size: {}", solver.transposition_table.len());  // TODO move to print_nimber_of_decomposable
-        println!("{}", solver.stats);   // TODO move to print_nimber_of_decomposable
+        print_nimber_of_decomposable(&mut solver, method, checkpoint);
+        report::tt_size_and_stats(solver.transposition_table.len(), &solver.stats);
+    }
+
+    fn run_parallel_with_cdb<CDB>(self, game: &Cram, tt_conf: TTConf, threads: usize, cdb: CDB)
+        where CDB: NimbersProvider<<Cram as Game>::Position> + Sync,
+    {
+        match tt_conf.kind_or_else(|| default_tt_kind(game)) {
+            TTKind::None => self.run_parallel_with_tt_cdb(game, threads, &(), cdb),
+            TTKind::HashMap => self.run_parallel_with_tt_cdb(game, threads, &ShardedTT::new(8, HashMap::new), cdb),
+            TTKind::Succinct => if let Some(file) = &tt_conf.file {
+                self.run_parallel_with_tt_cdb(game, threads,
+                    &tt_conf.concurrent_succinct_file(8, file, &format!("Cram {}x{}", self.cols, self.rows)), cdb)
+            } else {
+                self.run_parallel_with_tt_cdb(game, threads,
+                    &ConcurrentTTSuccinct64::new(tt_conf.size_log2(8) + (32-4) /*GB*/, 2, 4, stafford13, Fifo), cdb)
+            },
+        }
     }
+
+    fn run_parallel_with_tt_cdb<TT, CDB>(self, game: &Cram, threads: usize, tt: &TT, cdb: CDB)
+        where
+         TT: ConcurrentNimbersStorer<<Cram as Game>::Position> + igs::dbs::HasLen,
+         CDB: NimbersProvider<<Cram as Game>::Position> + Sync,
+    {
+        report::threads(threads);
+        let mut solver = Solver::new(game, tt, cdb, SmallerComponentsFirst{}, stats());
+        print_nimber_of_decomposable_parallel(&mut solver, threads);
+        report::tt_size_and_stats(solver.transposition_table.len(), &solver.stats);
+    }
+}
+
+/// Returns the kind of transposition table used by both sequential and parallel search if no kind is given.
+fn default_tt_kind(game: &Cram) -> TTKind {
+    if game.board_size() > 40 { TTKind::Succinct } else { TTKind::HashMap }
 }
 
 /*fn solve_cram<'a, TT, EDB, SORTER, S>(solver: &mut Solver<'a, Cram, TT, EDB, SORTER, S>, method: PruningMethod)
@@ -133,7 +183,7 @@ fn benchmark_tt<BitMixer: Fn(u64, u64) -> u64, Policy: ClusterPolicy>(tt_name: &
         enddb.done(),
         //move_sorter
         SmallerComponentsFirst{},
-        Without
+        stats()
     );
     solve_cram(&mut solver, PruningMethod::BrAspSet);
 }*/
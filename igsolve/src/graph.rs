@@ -0,0 +1,155 @@
+use std::{collections::HashMap, path::PathBuf, time::Instant};
+
+use clap::Args;
+
+use crate::{report, solver::{PruningMethod, stats, print_misere_outcome_of_simple, print_nimber_of_simple, print_nimber_of_simple_parallel}, tt::{TTConf, TTKind}, constdb::ConstDBConf};
+use igs::{games::{GraphGame, graph::FewerMovesFirst}, transposition_table::{ConcurrentNimbersStorer, ShardedTT, NimbersProvider, NimbersStorer}, game::Game, solver::{Solver, CheckpointFile, def::DefSimpleGameSolver, lvb::LVBSimpleGameSolver, br::BRSimpleGameSolver}};
+
+#[derive(Args, Clone)]
+pub struct Conf {
+    /// File with the graph of the game, in text (lines "node: successor successor ...", optional "root: node")
+    /// or binary format (see igs::games::GraphGame documentation);
+    /// graphs deeper than 1000 are solved in reverse topological order instead of search
+    file: PathBuf,
+
+    /// Print the nimbers of all nodes instead of the nimber of the root
+    #[arg(long, default_value_t = false)]
+    pub all: bool,
+}
+
+impl Conf {
+    /// Returns the game loaded from the file or exits the process if it cannot be loaded.
+    fn game(&self) -> GraphGame {
+        match GraphGame::load(&self.file) {
+            Ok(game) => game,
+            Err(e) => {
+                eprintln!("Cannot load graph from {}: {}", self.file.display(), e);
+                std::process::exit(1)
+            }
+        }
+    }
+
+    /// Includes the labels of the nodes (their numbers in the file) in the JSON document, if they differ from the node indices.
+    fn record_labels(game: &GraphGame) {
+        if let Some(labels) = game.labels() { report::record("node_labels", labels); }
+    }
+
+    /// Returns the depth of the deepest position to search, the root or (with `--all`) any node.
+    fn depth(&self, game: &GraphGame) -> u32 {
+        let depths = game.depths();
+        if self.all { depths.into_iter().max().unwrap_or(0) } else { depths[game.root() as usize] }
+    }
+
+    /// Calculates the (misère if `misere` is `true`) values of all nodes in reverse topological order, without search,
+    /// and prints these of the root or (with `--all`) all nodes.
+    fn run_iterative(&self, game: &GraphGame, depth: u32, misere: bool) {
+        if depth > GraphGame::MAX_SEARCH_DEPTH {
+            report::say(format_args!("Graph depth {depth} exceeds {} supported by search, calculating nodes in reverse topological order", GraphGame::MAX_SEARCH_DEPTH));
+            report::record("depth", &depth);
+        }
+        let now = Instant::now();
+        let values = if misere { game.misere_nimbers() } else { game.nimbers() };
+        let calc_time = now.elapsed();
+        let (name, key) = if misere { ("Misère Grundy value", "misere_grundy_value") } else { ("Nimber", "nimber") };
+        if self.all {
+            for (node, value) in values.iter().enumerate() { report::say(format_args!("{name} of node {}: {value}", game.label(node as u32))); }
+            report::record(if misere { "node_misere_grundy_values" } else { "node_nimbers" }, &values);
+            Self::record_labels(game);
+        } else {
+            let value = values[game.root() as usize];
+            report::say(format_args!("{name} of initial position: {value}"));
+            report::record(key, &value);
+            if misere {
+                report::say(format_args!("Initial position is {} under misère play", if value != 0 { "winning" } else { "losing" }));
+                report::record("winning", &(value != 0));
+            }
+        }
+        report::time(calc_time);
+    }
+
+    pub fn run_misere(self) {
+        let game = self.game();
+        report::title(&format!("Graph {} ({} nodes, root {})", self.file.display(), game.nodes(), game.label(game.root())), None);
+        let depth = self.depth(&game);
+        if self.all || depth > GraphGame::MAX_SEARCH_DEPTH { return self.run_iterative(&game, depth, true); }
+        let mut solver = Solver::new(&game, HashMap::<<GraphGame as Game>::Position, u8>::new(), (), (), stats());
+        print_misere_outcome_of_simple(&mut solver);
+        report::stats_only(&solver.stats);
+    }
+
+    pub fn run(self, method: PruningMethod, tt_conf: TTConf, _cdb: ConstDBConf, threads: usize, checkpoint: Option<CheckpointFile>) {
+        let game = self.game();
+        let game_desc = format!("Graph {} ({} nodes, root {})", self.file.display(), game.nodes(), game.label(game.root()));
+        report::title(&game_desc, Some(&method));
+        let checkpoint = checkpoint.map(|c| c.with_game(game_desc));
+        let depth = self.depth(&game);
+        if depth > GraphGame::MAX_SEARCH_DEPTH { return self.run_iterative(&game, depth, false); }
+        if self.all {
+            return self.run_all(&game, method, tt_conf);
+        }
+        if threads > 1 && matches!(method, PruningMethod::BrAspSet) {
+            return self.run_parallel(&game, tt_conf, threads);
+        }
+        match tt_conf.kind_or_else(|| TTKind::HashMap) {
+            TTKind::None => self.run_with_tt(&game, method, checkpoint, ()),
+            TTKind::HashMap => self.run_with_tt(&game, method, checkpoint, HashMap::new()),
+            TTKind::Succinct => unreachable!("succinct transposition table is rejected by Conf::validate"),
+        }
+    }
+
+    /// Prints the nimbers of all nodes, calculated by a single solver (which shares the transposition table between nodes).
+    fn run_all(self, game: &GraphGame, method: PruningMethod, tt_conf: TTConf) {
+        match tt_conf.kind_or_else(|| TTKind::HashMap) {
+            TTKind::None => self.run_all_with_tt(game, method, ()),
+            TTKind::HashMap => self.run_all_with_tt(game, method, HashMap::new()),
+            TTKind::Succinct => unreachable!("succinct transposition table is rejected by Conf::validate"),
+        }
+    }
+
+    fn run_all_with_tt<TT>(self, game: &GraphGame, method: PruningMethod, tt: TT)
+        where TT: NimbersProvider<<GraphGame as Game>::Position> + NimbersStorer<<GraphGame as Game>::Position> + igs::dbs::HasLen,
+    {
+        let mut solver = Solver::new(game, tt, (), FewerMovesFirst, stats());
+        let now = Instant::now();
+        let mut nimbers = Vec::with_capacity(game.nodes() as usize);
+        for node in 0..game.nodes() {
+            let nimber = match method {
+                PruningMethod::Def => solver.nimber_def(node),
+                PruningMethod::Lvb => solver.nimber_lvb(node),
+                PruningMethod::Br => solver.nimber_br(node),
+                PruningMethod::BrAspSet => solver.nimber_br_aspset(node),
+            };
+            report::say(format_args!("Nimber of node {}: {nimber}", game.label(node)));
+            nimbers.push(nimber);
+        }
+        report::record("node_nimbers", &nimbers);
+        Self::record_labels(game);
+        report::time(now.elapsed());
+        report::tt_size_and_stats(solver.transposition_table.len(), &solver.stats);
+    }
+
+    fn run_with_tt<TT>(self, game: &GraphGame, method: PruningMethod, checkpoint: Option<CheckpointFile>, tt: TT)
+        where TT: NimbersProvider<<GraphGame as Game>::Position> + NimbersStorer<<GraphGame as Game>::Position> + igs::dbs::HasLen,
+    {
+        let mut solver = Solver::new(game, tt, (), FewerMovesFirst, stats());
+        print_nimber_of_simple(&mut solver, method, checkpoint);
+        report::tt_size_and_stats(solver.transposition_table.len(), &solver.stats);
+    }
+
+    fn run_parallel(self, game: &GraphGame, tt_conf: TTConf, threads: usize) {
+        match tt_conf.kind.unwrap_or(TTKind::HashMap) {
+            TTKind::None => self.run_parallel_with_tt(game, threads, &()),
+            TTKind::HashMap => self.run_parallel_with_tt(game, threads, &ShardedTT::new(8, HashMap::new)),
+            TTKind::Succinct => unreachable!("succinct transposition table is rejected by Conf::validate"),
+        }
+    }
+
+    fn run_parallel_with_tt<TT>(self, game: &GraphGame, threads: usize, tt: &TT)
+        where TT: ConcurrentNimbersStorer<<GraphGame as Game>::Position> + igs::dbs::HasLen,
+    {
+        report::threads(threads);
+        let mut solver = Solver::new(game, tt, (), FewerMovesFirst, stats());
+        print_nimber_of_simple_parallel(&mut solver, threads);
+        report::tt_size_and_stats(solver.transposition_table.len(), &solver.stats);
+    }
+}
@@ -2,8 +2,8 @@ use std::collections::HashMap;
 
 use clap::Args;
 
-use crate::{solver::{PruningMethod, Without, print_nimber_of_decomposable}, tt::{TTConf, TTKind}, constdb::ConstDBConf};
-use igs::{games::GrundyGame, transposition_table::{NimbersProvider, NimbersStorer}, game::Game, solver::Solver};
+use crate::{report, solver::{PruningMethod, stats, print_misere_outcome_of_decomposable, print_nimber_of_decomposable, print_nimber_of_decomposable_parallel}, tt::{TTConf, TTKind}, constdb::ConstDBConf};
+use igs::{games::GrundyGame, transposition_table::{ConcurrentNimbersStorer, ShardedTT, NimbersProvider, NimbersStorer}, game::Game, solver::{Solver, CheckpointFile}};
 
 #[derive(Args, Clone, Copy)]
 pub struct Conf {
@@ -13,40 +13,36 @@ pub struct Conf {
 }
 
 impl Conf {
-    pub fn run(self, method: Option<PruningMethod>, tt_conf: TTConf, cdb: ConstDBConf) {
-        let method = method.unwrap_or(PruningMethod::Def);
-        println!("---=== Grundy's game {} {:?} ===---", self.position, method);
+    pub fn run_misere(self) {
+        report::title(&format!("Grundy's game {}", self.position), None);
         let game = GrundyGame(self.position);
-        if cdb.segments == 0 {
-            self.run_with_cdb(&game, method, tt_conf, ())
-        } else {
-            todo!("end-db is not yet supported for Grundy's game")
-        }
+        let mut solver = Solver::new(&game, HashMap::<<GrundyGame as Game>::Position, u8>::new(), (), (), stats());
+        print_misere_outcome_of_decomposable(&mut solver);
+        report::stats_only(&solver.stats);
     }
 
-    fn run_with_cdb<CDB>(self, game: &GrundyGame, method: PruningMethod, tt_conf: TTConf, cdb: CDB) 
-        where CDB: NimbersProvider<<GrundyGame as Game>::Position>,
-    {   // TODO copied from Cram, should be fixed
-        match tt_conf.kind.unwrap_or_else(|| crate::tt::TTKind::HashMap) {
-            TTKind::None => self.run_with_prot_tt_cdb(game, method, (), tt_conf.protect, cdb),
-            TTKind::HashMap => self.run_with_prot_tt_cdb(game, method, HashMap::new(), tt_conf.protect, cdb),
-            TTKind::Succinct => todo!("Succinct TT is not yet supported for Grundy's game"),
-        }
+    pub fn run(self, method: PruningMethod, tt_conf: TTConf, _cdb: ConstDBConf, threads: usize, checkpoint: Option<CheckpointFile>) {
+        let game_desc = format!("Grundy's game {}", self.position);
+        report::title(&game_desc, Some(&method));
+        let checkpoint = checkpoint.map(|c| c.with_game(game_desc));
+        let game = GrundyGame(self.position);
+        self.run_with_cdb(&game, method, checkpoint, tt_conf, threads, ())
     }
 
-    fn run_with_prot_tt_cdb<TT, CDB>(self, game: &GrundyGame, method: PruningMethod, tt: TT, protect_tt: bool, cdb: CDB) 
-        where
-         TT: NimbersProvider<<GrundyGame as Game>::Position> + NimbersStorer<<GrundyGame as Game>::Position> + igs::dbs::HasLen,
-         CDB: NimbersProvider<<GrundyGame as Game>::Position>,
-    {
-        if protect_tt {
-            todo!("TT protection is not yet supported for Grundy's game")
-        } else {
-            self.run_with_tt_cdb(game, method, tt, cdb);
+    fn run_with_cdb<CDB>(self, game: &GrundyGame, method: PruningMethod, checkpoint: Option<CheckpointFile>, tt_conf: TTConf, threads: usize, cdb: CDB) 
+        where CDB: NimbersProvider<<GrundyGame as Game>::Position> + Sync,
+    {   // TODO copied from Cram, should be fixed
+        if threads > 1 && matches!(method, PruningMethod::BrAspSet) {
+            return self.run_parallel_with_cdb(game, tt_conf, threads, cdb);
+        }
+        match tt_conf.kind_or_else(|| crate::tt::TTKind::HashMap) {
+            TTKind::None => self.run_with_tt_cdb(game, method, checkpoint, (), cdb),
+            TTKind::HashMap => self.run_with_tt_cdb(game, method, checkpoint, HashMap::new(), cdb),
+            TTKind::Succinct => unreachable!("succinct transposition table is rejected by Conf::validate"),
         }
     }
 
-    fn run_with_tt_cdb<TT, CDB>(self, game: &GrundyGame, method: PruningMethod, tt: TT, cdb: CDB) 
+    fn run_with_tt_cdb<TT, CDB>(self, game: &GrundyGame, method: PruningMethod, checkpoint: Option<CheckpointFile>, tt: TT, cdb: CDB) 
         where
          TT: NimbersProvider<<GrundyGame as Game>::Position> + NimbersStorer<<GrundyGame as Game>::Position> + igs::dbs::HasLen,
          CDB: NimbersProvider<<GrundyGame as Game>::Position>,
@@ -57,10 +53,30 @@ impl Conf {
             cdb,
             //move_sorter
             (),
-            Without
+            stats()
         );
-        print_nimber_of_decomposable(&mut solver, method);
-        println!("TT size: {}", solver.transposition_table.len());  // TODO move to print_nimber_of_decomposable
-        println!("{}", solver.stats);   // TODO move to print_nimber_of_decomposable
+        print_nimber_of_decomposable(&mut solver, method, checkpoint);
+        report::tt_size_and_stats(solver.transposition_table.len(), &solver.stats);
+    }
+
+    fn run_parallel_with_cdb<CDB>(self, game: &GrundyGame, tt_conf: TTConf, threads: usize, cdb: CDB)
+        where CDB: NimbersProvider<<GrundyGame as Game>::Position> + Sync,
+    {
+        match tt_conf.kind.unwrap_or(TTKind::HashMap) {
+            TTKind::None => self.run_parallel_with_tt_cdb(game, threads, &(), cdb),
+            TTKind::HashMap => self.run_parallel_with_tt_cdb(game, threads, &ShardedTT::new(8, HashMap::new), cdb),
+            TTKind::Succinct => unreachable!("succinct transposition table is rejected by Conf::validate"),
+        }
+    }
+
+    fn run_parallel_with_tt_cdb<TT, CDB>(self, game: &GrundyGame, threads: usize, tt: &TT, cdb: CDB)
+        where
+         TT: ConcurrentNimbersStorer<<GrundyGame as Game>::Position> + igs::dbs::HasLen,
+         CDB: NimbersProvider<<GrundyGame as Game>::Position> + Sync,
+    {
+        report::threads(threads);
+        let mut solver = Solver::new(game, tt, cdb, (), stats());
+        print_nimber_of_decomposable_parallel(&mut solver, threads);
+        report::tt_size_and_stats(solver.transposition_table.len(), &solver.stats);
     }
 }
\ No newline at end of file
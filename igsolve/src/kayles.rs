@@ -0,0 +1,155 @@
+use std::{collections::HashMap, path::PathBuf};
+use clap::{Args, ValueEnum};
+use igs::{games::kayles::{Kayles, KaylesRule, SmallerComponentsFirst}, transposition_table::{ConcurrentNimbersStorer, ShardedTT, ConcurrentTTSuccinct64, NimbersProvider, TTSuccinct64, bit_mixer::stafford13, cluster_policy::Fifo, NimbersStorer}, game::Game, solver::{Solver, CheckpointFile}};
+use crate::{report, solver::{PruningMethod, print_nimber_of_decomposable, print_nimber_of_decomposable_parallel, print_misere_outcome_of_decomposable, stats}, tt::{TTConf, TTKind}, constdb::ConstDBConf};
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum GraphFamily {
+    /// Path with N vertices
+    Path,
+    /// Cycle with N vertices
+    Cycle,
+    /// Complete graph with N vertices
+    Complete,
+    /// Grid with N columns and K rows
+    Grid,
+    /// Generalized Petersen graph G(N, K), the Petersen graph is G(5, 2)
+    Petersen,
+}
+
+#[derive(Args, Clone)]
+pub struct Conf {
+    /// Family of the graph to play on
+    #[arg(value_enum, required_unless_present = "file")]
+    graph: Option<GraphFamily>,
+
+    /// The first parameter of the graph (number of vertices, columns of the grid or N of Petersen graph)
+    #[arg(required_unless_present = "file")]
+    n: Option<u8>,
+
+    /// File with the graph to play on (instead of a family), with lines "a b" (edge between vertices a and b)
+    /// or "v" (isolated vertex), vertices numbered from 0 to 63
+    #[arg(long, conflicts_with_all = ["graph", "n", "k"])]
+    file: Option<PathBuf>,
+
+    /// The second parameter of the graph (rows of the grid (default N) or K of Petersen graph (default 2))
+    #[arg(short='k')]
+    k: Option<u8>,
+
+    /// Play Dawson's Kayles (a move removes two adjacent vertices) instead of Node-Kayles (a move removes a vertex and its neighbours)
+    #[arg(long, default_value_t = false)]
+    dawson: bool,
+
+    /// Use transposition table keyed by the canonical labelling (found by nauty) of components,
+    /// so isomorphic components share entries
+    #[cfg(feature = "nauty")]
+    #[arg(long, default_value_t = false)]
+    pub canonical: bool,
+}
+
+impl Conf {
+    fn rule(&self) -> KaylesRule {
+        if self.dawson { KaylesRule::Dawson } else { KaylesRule::Node }
+    }
+
+    /// Returns the game read from the file or built from the graph family,
+    /// or exits the process if the file cannot be loaded or the parameters of the family are invalid.
+    fn game(&self) -> Kayles {
+        if let Some(file) = &self.file {
+            return Kayles::load(self.rule(), file).unwrap_or_else(|e| {
+                eprintln!("Cannot load graph from {}: {}", file.display(), e);
+                std::process::exit(1)
+            });
+        }
+        let (graph, n) = (self.graph.unwrap(), self.n.unwrap());
+        match graph {
+            GraphFamily::Path => Kayles::path(self.rule(), n),
+            GraphFamily::Cycle => Kayles::cycle(self.rule(), n),
+            GraphFamily::Complete => Kayles::complete(self.rule(), n),
+            GraphFamily::Grid => Kayles::grid(self.rule(), n, self.k.unwrap_or(n)),
+            GraphFamily::Petersen => Kayles::generalized_petersen(self.rule(), n, self.k.unwrap_or(2)),
+        }.unwrap_or_else(|e| crate::exit_invalid_value(format_args!("cannot solve {}: {}", self.description(), e)))
+    }
+
+    /// Returns description of the game, for example "Node-Kayles Grid 4x3".
+    fn description(&self) -> String {
+        if let Some(file) = &self.file {
+            return format!("{} Graph {}", self.rule(), file.display());
+        }
+        let (graph, n) = (self.graph.unwrap(), self.n.unwrap());
+        match graph {
+            GraphFamily::Grid => format!("{} {:?} {}x{}", self.rule(), graph, n, self.k.unwrap_or(n)),
+            GraphFamily::Petersen => format!("{} {:?} G({}, {})", self.rule(), graph, n, self.k.unwrap_or(2)),
+            _ => format!("{} {:?} {}", self.rule(), graph, n)
+        }
+    }
+
+    pub fn run_misere(self) {
+        let game = self.game();
+        report::title(&self.description(), None);
+        let mut solver = Solver::new(&game, HashMap::<<Kayles as Game>::Position, u8>::new(), (), (), stats());
+        print_misere_outcome_of_decomposable(&mut solver);
+        report::stats_only(&solver.stats);
+    }
+
+    pub fn run(self, method: PruningMethod, tt_conf: TTConf, _cdb: ConstDBConf, threads: usize, checkpoint: Option<CheckpointFile>) {
+        let game = self.game();
+        let game_desc = self.description();
+        report::title(&game_desc, Some(&method));
+        let checkpoint = checkpoint.map(|c| c.with_game(game_desc));
+        self.run_with_cdb(&game, method, checkpoint, tt_conf, threads, ())
+    }
+
+    fn run_with_cdb<CDB>(&self, game: &Kayles, method: PruningMethod, checkpoint: Option<CheckpointFile>, tt_conf: TTConf, threads: usize, cdb: CDB)
+        where CDB: NimbersProvider<<Kayles as Game>::Position> + Sync,
+    {
+        if threads > 1 && matches!(method, PruningMethod::BrAspSet) {
+            return self.run_parallel_with_cdb(game, tt_conf, threads, cdb);
+        }
+        #[cfg(feature = "nauty")] if self.canonical {
+            return self.run_with_tt_cdb(game, method, checkpoint, igs::games::kayles::KaylesCanonTT::new(game), cdb);
+        }
+        match tt_conf.kind_or_else(|| if game.vertices() > 40 { TTKind::Succinct } else { TTKind::HashMap }) {
+            TTKind::None => self.run_with_tt_cdb(game, method, checkpoint, (), cdb),
+            TTKind::HashMap => self.run_with_tt_cdb(game, method, checkpoint, HashMap::new(), cdb),
+            TTKind::Succinct => if let Some(file) = &tt_conf.file {
+                self.run_with_tt_cdb(game, method, checkpoint, tt_conf.succinct_file(8, file, &self.description()), cdb)
+            } else {
+                self.run_with_tt_cdb(game, method, checkpoint,
+                    TTSuccinct64::new(tt_conf.size_log2(8) + (32-4) /*GB*/, 2, 4, stafford13, Fifo), cdb)
+            },
+        }
+    }
+
+    fn run_with_tt_cdb<TT, CDB>(&self, game: &Kayles, method: PruningMethod, checkpoint: Option<CheckpointFile>, tt: TT, cdb: CDB)
+        where
+         TT: NimbersProvider<<Kayles as Game>::Position> + NimbersStorer<<Kayles as Game>::Position> + igs::dbs::HasLen,
+         CDB: NimbersProvider<<Kayles as Game>::Position>,
+    {
+        let mut solver = Solver::new(game, tt, cdb, SmallerComponentsFirst, stats());
+        print_nimber_of_decomposable(&mut solver, method, checkpoint);
+        report::tt_size_and_stats(solver.transposition_table.len(), &solver.stats);
+    }
+
+    fn run_parallel_with_cdb<CDB>(&self, game: &Kayles, tt_conf: TTConf, threads: usize, cdb: CDB)
+        where CDB: NimbersProvider<<Kayles as Game>::Position> + Sync,
+    {
+        match tt_conf.kind.unwrap_or(TTKind::HashMap) {
+            TTKind::None => self.run_parallel_with_tt_cdb(game, threads, &(), cdb),
+            TTKind::HashMap => self.run_parallel_with_tt_cdb(game, threads, &ShardedTT::new(8, HashMap::new), cdb),
+            TTKind::Succinct => self.run_parallel_with_tt_cdb(game, threads,
+                 &ConcurrentTTSuccinct64::new(tt_conf.size_log2(8) + (32-4) /*GB*/, 2, 4, stafford13, Fifo), cdb),
+        }
+    }
+
+    fn run_parallel_with_tt_cdb<TT, CDB>(&self, game: &Kayles, threads: usize, tt: &TT, cdb: CDB)
+        where
+         TT: ConcurrentNimbersStorer<<Kayles as Game>::Position> + igs::dbs::HasLen,
+         CDB: NimbersProvider<<Kayles as Game>::Position> + Sync,
+    {
+        report::threads(threads);
+        let mut solver = Solver::new(game, tt, cdb, SmallerComponentsFirst, stats());
+        print_nimber_of_decomposable_parallel(&mut solver, threads);
+        report::tt_size_and_stats(solver.transposition_table.len(), &solver.stats);
+    }
+}
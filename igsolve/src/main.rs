@@ -1,11 +1,13 @@
 #![doc = include_str!("../README.md")]
 
-use clap::{Parser, Subcommand};
+use std::{path::PathBuf, time::Duration};
+use clap::{CommandFactory, Parser, Subcommand, error::ErrorKind};
+use igs::solver::CheckpointFile;
 mod solver;
 use solver::PruningMethod;
 
 mod tt;
-use tt::TTConf;
+use tt::{TTConf, TTKind};
 
 mod constdb;
 use constdb::ConstDBConf;
@@ -14,6 +16,11 @@ mod cram;
 mod chomp;
 mod chomp_skyline;
 mod grundy_game;
+mod graph;
+mod kayles;
+mod take_and_break;
+
+mod report;
 
 
 //#[allow(non_camel_case_types)]
@@ -28,6 +35,54 @@ pub enum GameConf {
     ChompSL(chomp_skyline::Conf),
     /// solve Grundy's game
     Grundy(grundy_game::Conf),
+    /// solve game played on directed acyclic graph read from file
+    Graph(graph::Conf),
+    /// solve Node-Kayles or Dawson's Kayles played on a graph
+    Kayles(kayles::Conf),
+    /// solve take-and-break game (like octal or hexadecimal game) played on a single heap
+    TakeAndBreak(take_and_break::Conf),
+}
+
+impl GameConf {
+    /// Returns the pruning method used for the game if no method is given.
+    fn default_method(&self) -> PruningMethod {
+        match self {
+            GameConf::Cram(_) | GameConf::Kayles(_) => PruningMethod::BrAspSet,
+            _ => PruningMethod::Def
+        }
+    }
+
+    /// Returns the name of the game used in error messages.
+    fn name(&self) -> &'static str {
+        match self {
+            GameConf::Cram(_) => "Cram",
+            GameConf::Chomp(_) | GameConf::ChompSL(_) => "Chomp",
+            GameConf::Grundy(_) => "Grundy's game",
+            GameConf::Graph(_) => "graph game",
+            GameConf::Kayles(_) => "Kayles",
+            GameConf::TakeAndBreak(_) => "take-and-break games",
+        }
+    }
+
+    /// Returns whether the end database can be built for the game.
+    fn supports_enddb(&self) -> bool {
+        matches!(self, GameConf::Cram(_))
+    }
+
+    /// Returns whether the succinct transposition table can be used for the game.
+    fn supports_succinct_tt(&self) -> bool {
+        !matches!(self, GameConf::Grundy(_) | GameConf::Graph(_) | GameConf::TakeAndBreak(_))
+    }
+
+    /// Returns whether the (sequential) search for the game can protect the transposition table.
+    fn supports_tt_protection(&self) -> bool {
+        matches!(self, GameConf::Cram(_) | GameConf::Chomp(_) | GameConf::ChompSL(_))
+    }
+
+    /// Returns whether the parallel search for the game can keep the transposition table in a file.
+    fn supports_parallel_tt_file(&self) -> bool {
+        matches!(self, GameConf::Cram(_))
+    }
 }
 
 #[derive(Parser)]
@@ -47,14 +102,130 @@ pub struct Conf {
     /// Pruning method. The default value depends on the game being solved
     #[arg(short='m', long, value_enum)]
     pub method: Option<PruningMethod>,
+
+    /// Number of threads that analyze the moves of the initial position in parallel (used only by br-asp-set method)
+    #[arg(short='t', long, default_value_t = 1)]
+    pub threads: usize,
+
+    /// File to periodically save the state of the top-level search loop to (supported only by single-threaded br-asp-set method).
+    /// If the file exists, the search is resumed from the state saved, provided that it has been saved for the same game.
+    /// The file is removed when the search is finished
+    #[arg(long)]
+    pub checkpoint: Option<PathBuf>,
+
+    /// Interval (in seconds) between saves of the checkpoint
+    #[arg(long="checkpoint_interval", default_value_t = 60)]
+    pub checkpoint_interval: u64,
+
+    /// Solve the game under misère play convention (the player who makes the last move loses).
+    /// Only the plain search by definition (as the def method) is supported, always single-threaded and with a hash map transposition table;
+    /// the pruning method, transposition table, end database, threads and checkpoint options are ignored
+    #[arg(long)]
+    pub misere: bool,
+
+    /// Print the results as a single JSON document instead of the text
+    #[arg(long)]
+    pub json: bool,
+
+    /// Collect and print the statistics of the search (numbers of visited positions, transposition table hits, etc.),
+    /// which slightly slows down the search
+    #[arg(long)]
+    pub stats: bool,
+}
+
+impl Conf {
+    /// Exits with an error message if the options cannot be used together (or with the game).
+    fn validate(&self, method: &PruningMethod) {
+        if let Some(conflict) = self.conflict(method) {
+            Conf::command().error(ErrorKind::ArgumentConflict, conflict).exit()
+        }
+    }
+
+    /// Returns the description of the first conflict between the options, if any.
+    fn conflict(&self, method: &PruningMethod) -> Option<String> {
+        if self.misere { return None; }  // all the options checked are ignored
+        let game = self.game.name();
+        let parallel = self.threads > 1 && matches!(method, PruningMethod::BrAspSet);
+        let graph_all = matches!(&self.game, GameConf::Graph(graph_conf) if graph_conf.all);
+        if self.tt.file.is_some() && !matches!(self.tt.kind, Some(TTKind::Succinct) | None) {
+            return Some("--tt_file can be used only with succinct transposition table".to_owned());
+        }
+        if self.cdb.segments != 0 && !self.game.supports_enddb() {
+            let hint = if matches!(self.game, GameConf::TakeAndBreak(_)) { ", use --edb_heap" } else { "" };
+            return Some(format!("end database (--edb_segments) is not supported for {game}{hint}"));
+        }
+        if (self.tt.file.is_some() || matches!(self.tt.kind, Some(TTKind::Succinct))) && !self.game.supports_succinct_tt() {
+            return Some(format!("succinct transposition table is not supported for {game}"));
+        }
+        if self.tt.protect && parallel {
+            return Some("--tt_protection is not supported by parallel search".to_owned());
+        }
+        if self.tt.protect && !self.game.supports_tt_protection() {
+            return Some(format!("--tt_protection is not supported for {game}"));
+        }
+        if self.tt.file.is_some() && parallel && !self.game.supports_parallel_tt_file() {
+            return Some(format!("--tt_file is not supported by parallel search for {game}"));
+        }
+        if graph_all && parallel {
+            return Some("graph --all is not supported by parallel search".to_owned());
+        }
+        #[cfg(feature = "nauty")]
+        if matches!(&self.game, GameConf::Kayles(kayles_conf) if kayles_conf.canonical) {
+            if self.tt.kind.is_some() || self.tt.file.is_some() {
+                return Some("kayles --canonical cannot be used with --tt or --tt_file".to_owned());
+            }
+            if parallel {
+                return Some("kayles --canonical is not supported by parallel search".to_owned());
+            }
+        }
+        if self.checkpoint.is_some() {
+            if !matches!(method, PruningMethod::BrAspSet) {
+                return Some("--checkpoint is supported only by br-asp-set method".to_owned());
+            }
+            if self.threads > 1 {
+                return Some("--checkpoint is not supported by parallel search (--threads greater than 1)".to_owned());
+            }
+            if graph_all {
+                return Some("--checkpoint cannot be used with graph --all".to_owned());
+            }
+        }
+        None
+    }
+}
+
+/// Exits with the error message about the invalid value of some argument.
+pub fn exit_invalid_value(msg: impl std::fmt::Display) -> ! {
+    Conf::command().error(ErrorKind::ValueValidation, msg).exit()
 }
 
 fn main() {
     let conf: Conf = Conf::parse();
+    let method = conf.method.clone().unwrap_or_else(|| conf.game.default_method());
+    conf.validate(&method);
+    if conf.json { report::enable_json(); }
+    if conf.stats { report::enable_stats(); }
+    if conf.misere {
+        match conf.game {
+            GameConf::Cram(cram_conf) => cram_conf.run_misere(),
+            GameConf::Chomp(chomp_conf) => chomp_conf.run_misere(),
+            GameConf::ChompSL(chomp_conf) => chomp_conf.run_misere(),
+            GameConf::Grundy(grundy_conf) => grundy_conf.run_misere(),
+            GameConf::Graph(graph_conf) => graph_conf.run_misere(),
+            GameConf::Kayles(kayles_conf) => kayles_conf.run_misere(),
+            GameConf::TakeAndBreak(tab_conf) => tab_conf.run_misere(),
+        }
+        report::print_json();
+        return;
+    }
+    let checkpoint = conf.checkpoint.map(|path| CheckpointFile::new(path, Duration::from_secs(conf.checkpoint_interval)));
     match conf.game {
-        GameConf::Cram(cram_conf) => cram_conf.run(conf.method, conf.tt, conf.cdb),
-        GameConf::Chomp(chomp_conf) => chomp_conf.run(conf.method, conf.tt, conf.cdb),
-        GameConf::ChompSL(chomp_conf) => chomp_conf.run(conf.method, conf.tt, conf.cdb),
-        GameConf::Grundy(grundy_conf) => grundy_conf.run(conf.method, conf.tt, conf.cdb),
+        GameConf::Cram(cram_conf) => cram_conf.run(method, conf.tt, conf.cdb, conf.threads, checkpoint),
+        GameConf::Chomp(chomp_conf) => chomp_conf.run(method, conf.tt, conf.cdb, conf.threads, checkpoint),
+        GameConf::ChompSL(chomp_conf) => chomp_conf.run(method, conf.tt, conf.cdb, conf.threads, checkpoint),
+        GameConf::Grundy(grundy_conf) => grundy_conf.run(method, conf.tt, conf.cdb, conf.threads, checkpoint),
+        GameConf::Graph(graph_conf) => graph_conf.run(method, conf.tt, conf.cdb, conf.threads, checkpoint),
+        GameConf::Kayles(kayles_conf) => kayles_conf.run(method, conf.tt, conf.cdb, conf.threads, checkpoint),
+        GameConf::TakeAndBreak(tab_conf) => tab_conf.run(method, conf.tt, conf.cdb, conf.threads, checkpoint),
     }
+    report::print_json();
 }
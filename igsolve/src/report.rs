@@ -0,0 +1,114 @@
+//! Reporting the results either as human-readable text (printed as soon as they are known) or as a single JSON document.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{LazyLock, Mutex};
+
+use igs::stats::{EventStats, EventType, PrintProgress, ProgressReporter, SearchPhase};
+
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use crate::solver::PruningMethod;
+
+/// Whether the results are reported as JSON.
+static JSON: AtomicBool = AtomicBool::new(false);
+
+/// Whether the search statistics are collected.
+static STATS: AtomicBool = AtomicBool::new(false);
+
+/// Results collected for the JSON document.
+static RESULTS: LazyLock<Mutex<Map<String, Value>>> = LazyLock::new(Default::default);
+
+/// Makes the results reported as a JSON document (printed by [`print_json`]) instead of the text.
+pub fn enable_json() { JSON.store(true, Ordering::Relaxed); }
+
+/// Returns whether the results are reported as a JSON document.
+#[inline] pub fn json() -> bool { JSON.load(Ordering::Relaxed) }
+
+/// Makes the search statistics collected (see [`crate::solver::stats`]) and reported.
+pub fn enable_stats() { STATS.store(true, Ordering::Relaxed); }
+
+/// Returns whether the search statistics are collected.
+#[inline] pub fn stats_enabled() -> bool { STATS.load(Ordering::Relaxed) }
+
+/// Prints the line given in the format of [`println`], unless the results are reported as JSON.
+pub fn say(line: std::fmt::Arguments) {
+    if !json() { println!("{line}") }
+}
+
+/// Includes the field `key` of the given `value` in the JSON document.
+pub fn record<V: Serialize + ?Sized>(key: &str, value: &V) {
+    RESULTS.lock().unwrap().insert(key.to_owned(), serde_json::to_value(value).unwrap());
+}
+
+/// Prints the JSON document with all the results recorded, if the results are reported as JSON.
+pub fn print_json() {
+    if json() { println!("{}", Value::Object(RESULTS.lock().unwrap().clone())); }
+}
+
+/// Reports the description of the `game` solved by the given `method` (under misère play convention if `method` is `None`).
+pub fn title(game: &str, method: Option<&PruningMethod>) {
+    match method {
+        Some(method) => say(format_args!("---=== {game} {method:?} ===---")),
+        None => say(format_args!("---=== {game} misère ===---"))
+    }
+    record("game", game);
+    record("misere", &method.is_none());
+    if let Some(method) = method { record("method", &format!("{method:?}")); }
+}
+
+/// Reports the number of threads used by the search.
+pub fn threads(threads: usize) {
+    say(format_args!("Threads: {threads}"));
+    record("threads", &threads);
+}
+
+/// Reports the number of entries in the transposition table and the search statistics (if they are collected).
+pub fn tt_size_and_stats(tt_size: usize, stats: &Option<EventStats>) {
+    say(format_args!("TT size: {tt_size}"));
+    record("tt_size", &tt_size);
+    stats_only(stats);
+}
+
+/// Reports the search statistics (if they are collected).
+pub fn stats_only(stats: &Option<EventStats>) {
+    match stats {
+        Some(stats) => {
+            say(format_args!("{stats}"));
+            record("stats", &stats_json(stats));
+        }
+        None => say(format_args!(""))
+    }
+}
+
+/// Returns the JSON object with the numbers of events of each type in each search phase (and in total),
+/// and the number of positions visited.
+fn stats_json(stats: &EventStats) -> Value {
+    const EVENTS: [(EventType, &str); 8] = [
+        (EventType::Exact, "exact"), (EventType::Unknown, "unknown"),
+        (EventType::TTCut, "tt_cut"), (EventType::ConstDbCut, "const_db_cut"),
+        (EventType::TTSkip, "tt_skip"), (EventType::ConstDbSkip, "const_db_skip"),
+        (EventType::TTRead, "tt_read"), (EventType::ConstDbRead, "const_db_read")];
+    let mut result = Map::new();
+    for (event, key) in EVENTS {
+        let [pre, etc, recursive] = [SearchPhase::Pre, SearchPhase::ETC, SearchPhase::Recursive].map(|phase| stats.number_of_events(phase, event));
+        result.insert(key.to_owned(), json!({ "pre": pre, "etc": etc, "recursive": recursive, "total": pre + etc + recursive }));
+    }
+    result.insert("nodes_visited".to_owned(), json!(stats.nodes_visited()));
+    Value::Object(result)
+}
+
+/// Reports the calculation time.
+pub fn time(time: std::time::Duration) {
+    say(format_args!("Calculation time: {time:.2?}"));
+    record("time_secs", &time.as_secs_f64());
+}
+
+/// Prints search progress to std-out, unless the results are reported as JSON.
+#[derive(Copy, Clone)]
+pub struct Progress;
+
+impl ProgressReporter for Progress {
+    fn begin(&mut self, max: u16) { if !json() { PrintProgress.begin(max) } }
+    fn end(&mut self) { if !json() { PrintProgress.end() } }
+    fn progress(&mut self, current: u16) { if !json() { PrintProgress.progress(current) } }
+}
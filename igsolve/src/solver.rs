@@ -1,7 +1,8 @@
-use std::{fmt::{Display, Formatter}, time::Instant};
+use std::time::Instant;
 
 use clap::{ValueEnum};
-use igs::{stats::{StatsCollector, PrintProgress}, game::{DecomposableGame, SimpleGame}, solver::{def::{DefDecomposableGameSolver, DefSimpleGameSolver}, lvb::{LVBDecomposableGameSolver, LVBSimpleGameSolver}, br::{BRDecomposableGameSolver, BRSimpleGameSolver}}};
+use crate::report::{self, Progress, say, record};
+use igs::{stats::EventStats, game::{DecomposableGame, MisereDecomposableGame, SimpleGame}, solver::{def::{DefDecomposableGameSolver, DefSimpleGameSolver}, lvb::{LVBDecomposableGameSolver, LVBSimpleGameSolver}, br::{BRDecomposableGameSolver, BRSimpleGameSolver, BRParallelDecomposableGameSolver, BRParallelSimpleGameSolver}, misere::{MisereDecomposableGameSolver, MisereSimpleGameSolver}, CheckpointFile}};
 
 #[derive(ValueEnum, Clone, Debug)]
 pub enum PruningMethod {
@@ -11,40 +12,109 @@ pub enum PruningMethod {
     BrAspSet
 }
 
-pub struct Without;
-impl StatsCollector for Without {}
-impl Display for Without {
-    fn fmt(&self, _: &mut Formatter<'_>) -> std::fmt::Result { Ok(()) }
+/// Returns the collector of the search statistics, which is `None` unless they are enabled by `--stats`.
+pub fn stats() -> Option<EventStats> {
+    report::stats_enabled().then(EventStats::default)
 }
 
-pub fn print_nimber_of_simple<'a, G, S>(solver: &mut S, method: PruningMethod)
+pub fn print_nimber_of_simple<'a, G, S>(solver: &mut S, method: PruningMethod, checkpoint: Option<CheckpointFile>)
 where G: SimpleGame,
       S: DefSimpleGameSolver<G> + LVBSimpleGameSolver<G> + BRSimpleGameSolver<G>
 {
     let now = Instant::now();
     let nimber = match method {
         PruningMethod::Def => solver.nimber_of_initial_def(),
-        PruningMethod::Lvb => solver.nimber_of_initial_lvb_report_progress(PrintProgress),
+        PruningMethod::Lvb => solver.nimber_of_initial_lvb_report_progress(Progress),
         PruningMethod::Br => solver.nimber_of_initial_br(),
-        PruningMethod::BrAspSet => solver.nimber_of_initial_br_aspset_report_progress(PrintProgress),
+        PruningMethod::BrAspSet => if let Some(checkpoint) = checkpoint {
+            solver.nimber_of_initial_br_aspset_checkpoint(Progress, checkpoint).unwrap_or_else(|e| {
+                eprintln!("Cannot use checkpoint: {}", e);
+                std::process::exit(1)
+            })
+        } else {
+            solver.nimber_of_initial_br_aspset_report_progress(Progress)
+        },
     };
     let calc_time = now.elapsed();
-    println!("Nimber of initial position: {nimber}");
-    println!("Calculation time: {calc_time:.2?}");
+    say(format_args!("Nimber of initial position: {nimber}"));
+    record("nimber", &nimber);
+    report::time(calc_time);
 }
 
-pub fn print_nimber_of_decomposable<'a, G, S>(solver: &mut S, method: PruningMethod)
+pub fn print_nimber_of_decomposable<'a, G, S>(solver: &mut S, method: PruningMethod, checkpoint: Option<CheckpointFile>)
 where G: DecomposableGame,
       S: DefDecomposableGameSolver<G> + LVBDecomposableGameSolver<G> + BRDecomposableGameSolver<G>
 {
     let now = Instant::now();
     let nimber = match method {
         PruningMethod::Def => solver.nimber_of_initial_def(),
-        PruningMethod::Lvb => solver.nimber_of_initial_lvb_report_progress(PrintProgress),
+        PruningMethod::Lvb => solver.nimber_of_initial_lvb_report_progress(Progress),
         PruningMethod::Br => solver.nimber_of_initial_br(),
-        PruningMethod::BrAspSet => solver.nimber_of_initial_br_aspset_report_progress(PrintProgress),
+        PruningMethod::BrAspSet => if let Some(checkpoint) = checkpoint {
+            solver.nimber_of_initial_br_aspset_checkpoint(Progress, checkpoint).unwrap_or_else(|e| {
+                eprintln!("Cannot use checkpoint: {}", e);
+                std::process::exit(1)
+            })
+        } else {
+            solver.nimber_of_initial_br_aspset_report_progress(Progress)
+        },
     };
     let calc_time = now.elapsed();
-    println!("Nimber of initial position: {nimber}");
-    println!("Calculation time: {calc_time:.2?}");
-}
\ No newline at end of file
+    say(format_args!("Nimber of initial position: {nimber}"));
+    record("nimber", &nimber);
+    report::time(calc_time);
+}
+
+pub fn print_nimber_of_simple_parallel<G, S>(solver: &mut S, threads: usize)
+where G: SimpleGame,
+      S: BRParallelSimpleGameSolver<G>
+{
+    let now = Instant::now();
+    let nimber = solver.nimber_of_initial_br_aspset_parallel_report_progress(threads, Progress);
+    let calc_time = now.elapsed();
+    say(format_args!("Nimber of initial position: {nimber}"));
+    record("nimber", &nimber);
+    report::time(calc_time);
+}
+
+pub fn print_nimber_of_decomposable_parallel<G, S>(solver: &mut S, threads: usize)
+where G: DecomposableGame,
+      S: BRParallelDecomposableGameSolver<G>
+{
+    let now = Instant::now();
+    let nimber = solver.nimber_of_initial_br_aspset_parallel_report_progress(threads, Progress);
+    let calc_time = now.elapsed();
+    say(format_args!("Nimber of initial position: {nimber}"));
+    record("nimber", &nimber);
+    report::time(calc_time);
+}
+
+fn outcome_str(is_winning: bool) -> &'static str {
+    if is_winning { "winning" } else { "losing" }
+}
+
+pub fn print_misere_outcome_of_simple<G, S>(solver: &mut S)
+where G: SimpleGame,
+      S: MisereSimpleGameSolver<G>
+{
+    let now = Instant::now();
+    let nimber = solver.misere_nimber_of_initial_def();
+    let calc_time = now.elapsed();
+    say(format_args!("Misère Grundy value of initial position: {nimber}"));
+    say(format_args!("Initial position is {} under misère play", outcome_str(nimber != 0)));
+    record("misere_grundy_value", &nimber);
+    record("winning", &(nimber != 0));
+    report::time(calc_time);
+}
+
+pub fn print_misere_outcome_of_decomposable<G, S>(solver: &mut S)
+where G: MisereDecomposableGame,
+      S: MisereDecomposableGameSolver<G>
+{
+    let now = Instant::now();
+    let is_winning = solver.is_initial_misere_winning();
+    let calc_time = now.elapsed();
+    say(format_args!("Initial position is {} under misère play", outcome_str(is_winning)));
+    record("winning", &is_winning);
+    report::time(calc_time);
+}
@@ -0,0 +1,104 @@
+use std::collections::HashMap;
+
+use clap::Args;
+
+use crate::{report, solver::{PruningMethod, stats, print_misere_outcome_of_decomposable, print_nimber_of_decomposable, print_nimber_of_decomposable_parallel}, tt::{TTConf, TTKind}, constdb::ConstDBConf};
+use igs::{games::TakeAndBreak, transposition_table::{ConcurrentNimbersStorer, ShardedTT, NimbersProvider, NimbersStorer}, game::Game, solver::{Solver, CheckpointFile}};
+
+#[derive(Args, Clone)]
+pub struct Conf {
+    /// Octal, hexadecimal (like 0.137, 4.07 or 0.F) code of the game
+    #[arg()]
+    code: String,
+
+    /// initial position (heap size)
+    #[arg(short='p', long)]
+    position: u16,
+
+    /// Size of the largest heap whose nimber is included in the end database (calculated before the search)
+    #[arg(long="edb_heap", default_value_t = 0)]
+    edb_heap: u16,
+}
+
+impl Conf {
+    /// Returns the game or exits the process if the code or the position is invalid.
+    fn game(&self) -> TakeAndBreak {
+        TakeAndBreak::from_code(&self.code, self.position).unwrap_or_else(|e|
+            crate::exit_invalid_value(format_args!("cannot solve {} heap {}: {}", self.code, self.position, e)))
+    }
+
+    pub fn run_misere(self) {
+        let game = self.game();
+        report::title(&format!("{} heap {}", game.code(), self.position), None);
+        let mut solver = Solver::new(&game, HashMap::<<TakeAndBreak as Game>::Position, u8>::new(), (), (), stats());
+        print_misere_outcome_of_decomposable(&mut solver);
+        report::stats_only(&solver.stats);
+    }
+
+    pub fn run(self, method: PruningMethod, tt_conf: TTConf, _cdb: ConstDBConf, threads: usize, checkpoint: Option<CheckpointFile>) {
+        let game = self.game();
+        let game_desc = format!("{} heap {}", game.code(), self.position);
+        report::title(&game_desc, Some(&method));
+        let checkpoint = checkpoint.map(|c| c.with_game(game_desc));
+        if self.edb_heap == 0 {
+            self.run_with_cdb(&game, method, checkpoint, tt_conf, threads, ())
+        } else {
+            let heap_nimbers = game.heap_nimbers(self.edb_heap);
+            let edb_heap = heap_nimbers.max_heap().unwrap_or(0);
+            report::say(format_args!("End database: nimbers of heaps up to {edb_heap}"));
+            report::record("end_db_max_heap", &edb_heap);
+            self.run_with_cdb(&game, method, checkpoint, tt_conf, threads, heap_nimbers)
+        }
+    }
+
+    fn run_with_cdb<CDB>(self, game: &TakeAndBreak, method: PruningMethod, checkpoint: Option<CheckpointFile>, tt_conf: TTConf, threads: usize, cdb: CDB) 
+        where CDB: NimbersProvider<<TakeAndBreak as Game>::Position> + Sync,
+    {
+        if threads > 1 && matches!(method, PruningMethod::BrAspSet) {
+            return self.run_parallel_with_cdb(game, tt_conf, threads, cdb);
+        }
+        match tt_conf.kind_or_else(|| crate::tt::TTKind::HashMap) {
+            TTKind::None => self.run_with_tt_cdb(game, method, checkpoint, (), cdb),
+            TTKind::HashMap => self.run_with_tt_cdb(game, method, checkpoint, HashMap::new(), cdb),
+            TTKind::Succinct => unreachable!("succinct transposition table is rejected by Conf::validate"),
+        }
+    }
+
+    fn run_with_tt_cdb<TT, CDB>(self, game: &TakeAndBreak, method: PruningMethod, checkpoint: Option<CheckpointFile>, tt: TT, cdb: CDB) 
+        where
+         TT: NimbersProvider<<TakeAndBreak as Game>::Position> + NimbersStorer<<TakeAndBreak as Game>::Position> + igs::dbs::HasLen,
+         CDB: NimbersProvider<<TakeAndBreak as Game>::Position>,
+    {
+        let mut solver = Solver::new(
+            game,
+            tt,
+            cdb,
+            //move_sorter
+            (),
+            stats()
+        );
+        print_nimber_of_decomposable(&mut solver, method, checkpoint);
+        report::tt_size_and_stats(solver.transposition_table.len(), &solver.stats);
+    }
+
+    fn run_parallel_with_cdb<CDB>(self, game: &TakeAndBreak, tt_conf: TTConf, threads: usize, cdb: CDB)
+        where CDB: NimbersProvider<<TakeAndBreak as Game>::Position> + Sync,
+    {
+        match tt_conf.kind.unwrap_or(TTKind::HashMap) {
+            TTKind::None => self.run_parallel_with_tt_cdb(game, threads, &(), cdb),
+            TTKind::HashMap => self.run_parallel_with_tt_cdb(game, threads, &ShardedTT::new(8, HashMap::new), cdb),
+            TTKind::Succinct => unreachable!("succinct transposition table is rejected by Conf::validate"),
+        }
+    }
+
+    fn run_parallel_with_tt_cdb<TT, CDB>(self, game: &TakeAndBreak, threads: usize, tt: &TT, cdb: CDB)
+        where
+         TT: ConcurrentNimbersStorer<<TakeAndBreak as Game>::Position> + igs::dbs::HasLen,
+         CDB: NimbersProvider<<TakeAndBreak as Game>::Position> + Sync,
+    {
+        report::threads(threads);
+        let mut solver = Solver::new(game, tt, cdb, (), stats());
+        print_nimber_of_decomposable_parallel(&mut solver, threads);
+        report::tt_size_and_stats(solver.transposition_table.len(), &solver.stats);
+    }
+}
\ No newline at end of file
@@ -1,4 +1,6 @@
+use std::path::{Path, PathBuf};
 use clap::ValueEnum;
+use igs::transposition_table::{TTSuccinct64, ConcurrentTTSuccinct64, TTFile, TTFileConf, bit_mixer::Stafford13, cluster_policy::Fifo};
 
 #[derive(ValueEnum, Clone, Copy, Debug)]
 pub enum TTKind {
@@ -24,12 +26,56 @@ pub struct TTConf {
     /// Whether to save the most valuable part of the transposition table to disk (so that calculations can be resumed)
     #[arg(long="tt_protection", default_value_t = false)]
     pub protect: bool,
+
+    /// File in which succinct transposition table is kept (memory-mapped).
+    /// If the file exists, the calculations are resumed with its content.
+    /// Implies succinct transposition table
+    #[arg(long="tt_file")]
+    pub file: Option<PathBuf>,
 }
 
 impl TTConf {
     pub fn size_log2(&self, default_size: usize) -> u8 {
         self.size.unwrap_or(default_size).checked_ilog2().unwrap_or(0) as u8
     }
+
+    /// Returns kind of transposition table to use: succinct if the file is given, `default` if the kind is not given.
+    /// The file given along with another kind is rejected by the validation of the arguments.
+    pub fn kind_or_else<F: FnOnce() -> TTKind>(&self, default: F) -> TTKind {
+        match (self.kind, &self.file) {
+            (Some(kind), _) => kind,
+            (None, Some(_)) => TTKind::Succinct,
+            (None, None) => default()
+        }
+    }
+
+    /// Returns succinct transposition table of size given in GB (or `default_size`),
+    /// kept in the memory-mapped file with the given `path`.
+    /// The file header records `game`, which should describe the game and its initial position.
+    /// Exits the process if the file cannot be used.
+    pub fn succinct_file(&self, default_size: usize, path: &Path, game: &str) -> TTSuccinct64<fn(u64, u64) -> u64, Fifo, TTFile> {
+        let file = TTFileConf { path, game };
+        exit_on_file_error(path, TTSuccinct64::with_file(&file, self.size_log2(default_size) + (32-4) /*GB*/, 2, 4, Stafford13, Fifo))
+    }
+
+    /// Returns concurrent succinct transposition table of size given in GB (or `default_size`),
+    /// kept in the memory-mapped file with the given `path`, which is compatible with the file of [`Self::succinct_file`].
+    /// Exits the process if the file cannot be used.
+    pub fn concurrent_succinct_file(&self, default_size: usize, path: &Path, game: &str) -> ConcurrentTTSuccinct64<fn(u64, u64) -> u64, Fifo, TTFile> {
+        let file = TTFileConf { path, game };
+        exit_on_file_error(path, ConcurrentTTSuccinct64::with_file(&file, self.size_log2(default_size) + (32-4) /*GB*/, 2, 4, Stafford13, Fifo))
+    }
+}
+
+/// Returns the transposition table kept in the file with the given `path` or exits the process if the file cannot be used.
+fn exit_on_file_error<TT>(path: &Path, tt: std::io::Result<TT>) -> TT {
+    match tt {
+        Ok(tt) => tt,
+        Err(e) => {
+            eprintln!("Cannot use transposition table file {}: {}", path.display(), e);
+            std::process::exit(1)
+        }
+    }
 }
 
 /*let mut freq = [0u64; 9*5+1];
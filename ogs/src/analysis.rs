@@ -0,0 +1,100 @@
+use crate::{Game, Nimber};
+
+/// Move from a single heap.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Move {
+    /// Index of the heap (in the position) the move is made from.
+    pub heap: usize,
+    /// Number of tokens taken.
+    pub take: usize,
+    /// Sizes of the heaps left (in non-decreasing order), empty if the whole heap is taken.
+    pub leaves: Vec<usize>
+}
+
+impl Game {
+    /// Returns all different moves from the heap of size `n`,
+    /// each given as the number of tokens taken and the (non-decreasing) sizes of the heaps left.
+    pub fn moves(&self, n: usize) -> Vec<(usize, Vec<usize>)> {
+        if n >= self.unrolled() { return self.unrolled_to(n).moves(n); }
+        let mut result = Vec::new();
+        if self.can_take_all(n) { result.push((n, Vec::new())); }
+        for t in self.taking.iter().copied().take_while(|t| *t < n) {
+            result.push((t, vec![n-t]));
+        }
+        for (i, j) in self.breaking_moves(n).chain(self.restricted_breaking_moves(n)) {
+            result.push((n - i - j, vec![i, j]));
+        }
+        for heaps in self.breaking_more_moves(n) {
+            result.push((n - heaps.iter().sum::<usize>(), heaps));
+        }
+        result.sort_unstable();
+        result.dedup();
+        result
+    }
+}
+
+/// Returns the nimber of the position consisting of the `heaps` of given sizes,
+/// or `None` if the `nimbers` of the heaps are not known (i.e. some heap has at least `nimbers.len()` tokens).
+pub fn position_nimber(nimbers: &[Nimber], heaps: &[usize]) -> Option<Nimber> {
+    heaps.iter().try_fold(0, |acc, h| nimbers.get(*h).map(|n| acc ^ n))
+}
+
+/// Returns all winning moves (i.e. the moves to the positions of nimber 0) from the position consisting of the `heaps` of given sizes,
+/// or `None` if the `nimbers` of the heaps (of the `game`) are not known.
+/// Only the first of the heaps of equal sizes is considered, since the moves from the others are equivalent.
+pub fn winning_moves(game: &Game, nimbers: &[Nimber], heaps: &[usize]) -> Option<Vec<Move>> {
+    let nimber = position_nimber(nimbers, heaps)?;
+    let mut result = Vec::new();
+    if nimber == 0 { return Some(result); }
+    for (heap, n) in heaps.iter().copied().enumerate() {
+        if heaps[..heap].contains(&n) { continue; }
+        let target = nimber ^ nimbers[n];   // the nimber the heap should be replaced with
+        for (take, leaves) in game.moves(n) {
+            if leaves.iter().fold(0, |acc, h| acc ^ nimbers[*h]) == target {
+                result.push(Move { heap, take, leaves });
+            }
+        }
+    }
+    Some(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+    use crate::{NaiveSolver, Solver};
+
+    #[test]
+    fn test_moves() {
+        let kayles = Game::from_str("0.77").unwrap();
+        assert_eq!(kayles.moves(1), [(1, vec![])]);
+        assert_eq!(kayles.moves(4), [(1, vec![1, 2]), (1, vec![3]), (2, vec![1, 1]), (2, vec![2])]);
+        let g = Game::from_str("0.08").unwrap();
+        assert_eq!(g.moves(5), [(2, vec![1, 1, 1])]);
+        let grundy = Game::from_str("grundy").unwrap();
+        assert_eq!(grundy.moves(6), [(0, vec![1, 5]), (0, vec![2, 4])]);
+    }
+
+    #[test]
+    fn test_winning_moves() {
+        let kayles = Game::from_str("0.77").unwrap();
+        let nimbers: Vec<Nimber> = NaiveSolver::<()>::new(kayles.clone()).take(40).collect();
+        assert_eq!(position_nimber(&nimbers, &[1, 2, 3]), Some(1 ^ 2 ^ 3));
+        assert_eq!(position_nimber(&nimbers, &[1, 40]), None);
+        assert_eq!(winning_moves(&kayles, &nimbers, &[2, 2]), Some(vec![]));
+        assert_eq!(winning_moves(&kayles, &nimbers, &[40]), None);
+        let position = [12, 7, 30, 7];
+        let moves = winning_moves(&kayles, &nimbers, &position).unwrap();
+        assert!(!moves.is_empty());
+        for heap in 0..3 {  // the last heap is equal to the second
+            for (take, leaves) in kayles.moves(position[heap]) {
+                let mut heaps = position.to_vec();
+                heaps.remove(heap);
+                heaps.extend(&leaves);
+                let m = Move { heap, take, leaves };
+                assert_eq!(position_nimber(&nimbers, &heaps) == Some(0), moves.contains(&m), "{m:?}");
+            }
+        }
+        assert!(moves.iter().all(|m| m.heap != 3));
+    }
+}
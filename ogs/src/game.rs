@@ -1,88 +1,460 @@
-use crate::{BitSet, SolverEvent};
+use crate::{BitSet, Nimber, SolverEvent};
+use crate::set::option_words;
 
-use std::{iter::FusedIterator, str::FromStr};
+use std::{borrow::Cow, iter::FusedIterator, str::FromStr};
 
+/// Rules of an octal (or hexadecimal) game.
+///
+/// If the code of the game is infinite (see [`Game::repeating`]), the lists of moves include only the moves
+/// that remove less tokens than [`Game::unrolled`], and are extended by [`Game::unroll`] as needed.
 #[derive(Default, Clone)]
 pub struct Game {
-    pub taking_all: [u64; 4],   // set
-    pub taking: Vec<u8>,
-    pub breaking: Vec<u8>,
-    //breaking_even: Vec<u8>,
-    //breaking_odd: Vec<u8>
+    pub taking_all: Vec<u64>,   // set
+    pub taking: Vec<usize>,
+    pub breaking: Vec<usize>,
+    /// `breaking_more[i]` lists (in increasing order) the numbers of tokens
+    /// whose removal can leave `i+3` non-empty heaps (like in hexadecimal games for `i=0`).
+    pub breaking_more: Vec<Vec<usize>>,
+    /// Numbers of tokens (in increasing order) whose removal can leave two non-empty heaps of different sizes.
+    pub breaking_unequal: Vec<u8>,
+    /// Numbers of tokens (in increasing order) whose removal can leave two non-empty heaps of even sizes.
+    pub breaking_even: Vec<u8>,
+    /// Numbers of tokens (in increasing order) whose removal can leave two non-empty heaps of odd sizes.
+    pub breaking_odd: Vec<u8>,
+    /// Code digits repeated periodically, for each number of tokens from `repeating_from` on.
+    /// Empty if the code is finite.
+    pub repeating: Vec<u32>,
+    /// Number of tokens whose removal is described by the first of the `repeating` digits.
+    pub repeating_from: usize,
+    /// The moves described by the `repeating` digits that remove less than `unrolled` tokens are included in the lists of moves.
+    unrolled: usize
+}
+
+/// Restriction on the sizes of two heaps left by a breaking move.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SplitRule {
+    /// The heaps must have different sizes (like in Grundy's game).
+    Unequal,
+    /// Both heaps must have even sizes.
+    Even,
+    /// Both heaps must have odd sizes.
+    Odd
+}
+
+impl SplitRule {
+    /// Returns the letter that denotes the rule in the game code.
+    pub fn letter(self) -> u8 {
+        match self { SplitRule::Unequal => b'u', SplitRule::Even => b'e', SplitRule::Odd => b'o' }
+    }
+
+    /// Returns the rule denoted by the given `letter` or `None` if there is no such rule.
+    pub fn from_letter(letter: u8) -> Option<Self> {
+        match letter {
+            b'u' | b'U' => Some(SplitRule::Unequal),
+            b'e' | b'E' => Some(SplitRule::Even),
+            b'o' | b'O' => Some(SplitRule::Odd),
+            _ => None
+        }
+    }
+
+    /// Returns whether the rule allows to leave two heaps of sizes `a` and `b`.
+    #[inline] pub fn allows(self, a: usize, b: usize) -> bool {
+        match self {
+            SplitRule::Unequal => a != b,
+            SplitRule::Even => a & 1 == 0 && b & 1 == 0,
+            SplitRule::Odd => a & 1 == 1 && b & 1 == 1
+        }
+    }
+
+    /// Returns the (first, last, step) range of the sizes of the smaller heap in the allowed splits of `n` tokens.
+    #[inline] fn smaller_heaps(self, n: usize) -> (usize, usize, usize) {
+        match self {
+            SplitRule::Unequal => (1, n.saturating_sub(1) / 2, 1),
+            SplitRule::Even => (2, if n & 1 == 0 { n / 2 } else { 0 }, 2),
+            SplitRule::Odd => (1, if n & 1 == 0 { n / 2 } else { 0 }, 2)
+        }
+    }
+
+    /// Returns iterator over the allowed splits of `n` tokens into two non-empty heaps, the smaller heap first.
+    #[inline] pub fn splits(self, n: usize) -> impl Iterator<Item = (usize, usize)> {
+        let (first, last, step) = self.smaller_heaps(n);
+        (first..=last).step_by(step).map(move |i| (i, n - i))
+    }
+
+    /// Returns the number of the allowed splits of `n` tokens into two non-empty heaps.
+    #[inline] pub fn number_of_splits(self, n: usize) -> usize {
+        let (first, last, step) = self.smaller_heaps(n);
+        if first > last { 0 } else { (last - first) / step + 1 }
+    }
 }
 
 impl Game {
-    #[inline] fn parse_octal(c: u8) -> Option<u8> {
-        (b'0' <= c && c <= b'8').then(|| c - b'0')
+    /// Parses a single code digit: `0-9`, `A-F` (or `a-f`) or decimal number in square brackets, like `[16]`.
+    fn parse_digit(s: &mut &[u8]) -> Option<u32> {
+        let (c, rest) = s.split_first()?;
+        *s = rest;
+        match c {
+            b'0'..=b'9' => Some((c - b'0') as u32),
+            b'a'..=b'f' => Some((c - b'a' + 10) as u32),
+            b'A'..=b'F' => Some((c - b'A' + 10) as u32),
+            b'[' => {
+                let end = rest.iter().position(|c| *c == b']')?;
+                *s = &rest[end+1..];
+                std::str::from_utf8(&rest[..end]).ok()?.parse().ok()
+            },
+            _ => None
+        }
+    }
+
+    /// Adds the moves described by code `digit` that remove `position` tokens.
+    fn add_digit(&mut self, position: usize, digit: u32) {
+        if digit & 1 != 0 {
+            if self.taking_all.len() <= position / 64 { self.taking_all.resize(position / 64 + 1, 0); }
+            self.taking_all.set_bit(position)
+        }
+        if digit & 2 != 0 { self.taking.push(position) }
+        if digit & 4 != 0 { self.breaking.push(position) }
+        for heaps in 3..32 {
+            if digit & (1 << heaps) != 0 {
+                if self.breaking_more.len() <= heaps - 3 { self.breaking_more.resize(heaps - 2, Vec::new()); }
+                self.breaking_more[heaps - 3].push(position);
+            }
+        }
+    }
+
+    /// Parses comma-separated list of decimal numbers (of tokens) that ends with `+` or at the end of `s`.
+    fn parse_takes(s: &mut &[u8]) -> Option<Vec<u8>> {
+        let end = s.iter().position(|c| *c == b'+').unwrap_or(s.len());
+        let mut result = std::str::from_utf8(&s[..end]).ok()?
+            .split(',').map(|t| t.parse().ok()).collect::<Option<Vec<u8>>>()?;
+        *s = &s[end..];
+        result.sort_unstable();
+        result.dedup();
+        Some(result)
+    }
+
+    /// Parses comma-separated list of positive decimal numbers (of tokens) given in parentheses, and returns it sorted.
+    fn parse_set(s: &[u8]) -> Option<Vec<usize>> {
+        let s = std::str::from_utf8(s.strip_prefix(b"(")?.strip_suffix(b")")?).ok()?;
+        if s.trim().is_empty() { return Some(Vec::new()); }
+        let mut result = s.split(',').map(|t| t.trim().parse().ok().filter(|t| *t != 0)).collect::<Option<Vec<usize>>>()?;
+        result.sort_unstable();
+        result.dedup();
+        Some(result)
+    }
+
+    /// Parses the subtraction game given as `S(...)` or `S-all-but(...)` (see [`Game::from_ascii`]).
+    fn parse_subtraction(s: &[u8]) -> Option<Game> {
+        let mut result = Self::default();
+        if let Some(set) = s.strip_prefix(b"S-all-but").or_else(|| s.strip_prefix(b"s-all-but")) {
+            let set = Self::parse_set(set)?;
+            let end = set.last().map_or(1, |t| t + 1);
+            for t in 1..end {
+                if set.binary_search(&t).is_err() { result.add_digit(t, 3); }
+            }
+            result.repeating = vec![3];
+            result.repeating_from = end;
+        } else {
+            for t in Self::parse_set(s.strip_prefix(b"S").or_else(|| s.strip_prefix(b"s"))?)? {
+                result.add_digit(t, 3);
+            }
+        }
+        result.unrolled = result.repeating_from;
+        Some(result)
     }
 
+    /// Parses the code of the game.
+    /// 
+    /// The code consists of an optional first digit (`0`, `4`, `8`, `C`, ...) followed by a separator (`.`, `,` or `d`),
+    /// and digits, one for each number of tokens taken.
+    /// Bit `2^k` of a digit allows to leave `k` non-empty heaps after taking the number of tokens.
+    /// Hexadecimal digits are supported, and larger digits can be given as decimal numbers in square brackets, like `[16]`.
+    /// 
+    /// The last digits can be given in parentheses, like in `0.1(6)`, or as `\overline{...}`, like in `0.1\overline{6}`,
+    /// to repeat them periodically infinitely many times (see [`Game::repeating`]).
+    /// 
+    /// The digits can be followed by restricted breaking moves, each given as `+`, a letter of [`SplitRule`]
+    /// (`u` for unequal, `e` for even or `o` for odd heaps) and a comma-separated list of the numbers of tokens taken.
+    /// For example, Grundy's game (split any heap into two unequal heaps) is `0.+u0` and can be also given as `grundy`.
+    /// 
+    /// Subtraction games can be also given by their subtraction sets:
+    /// `S(1,3,4)` allows to remove 1, 3 or 4 tokens from a heap, and is equivalent to `0.3033`,
+    /// while `S-all-but(2,5)` allows to remove any number of tokens except 2 and 5, and is equivalent to `0.30330(3)`.
     pub fn from_ascii(mut s: &[u8]) -> Option<Game> {
+        if s.eq_ignore_ascii_case(b"grundy") { return Self::from_ascii(b"0.+u0"); }
+        if s.starts_with(b"S") || s.starts_with(b"s") { return Self::parse_subtraction(s); }
         let mut result = Self::default();
-        if s.starts_with(b"4.") || s.starts_with(b"4,") || s.starts_with(b"4d") {
-            result.breaking.push(0);
-            s = &s[2..];
-        } else if s.starts_with(b"0.") || s.starts_with(b"0,") || s.starts_with(b"0d") {
-            s = &s[2..];
-        } else if s.starts_with(b".") || s.starts_with(b",") || s.starts_with(b"d") {
+        if s.starts_with(b".") || s.starts_with(b",") || s.starts_with(b"d") {
+            s = &s[1..];
+        } else {
+            let mut rest = s;
+            if let Some(digit) = Self::parse_digit(&mut rest) {
+                if rest.starts_with(b".") || rest.starts_with(b",") || rest.starts_with(b"d") {
+                    if digit & 3 != 0 { return None; }
+                    result.add_digit(0, digit);
+                    s = &rest[1..];
+                }
+            }
+        }
+        let mut position = 0;
+        while !s.is_empty() && !matches!(s[0], b'+' | b'(' | b'\\') {
+            position += 1;
+            let digit = Self::parse_digit(&mut s)?;
+            result.add_digit(position, digit);
+        }
+        result.repeating_from = position + 1;
+        if let Some(rest) = s.strip_prefix(b"(").or_else(|| s.strip_prefix(b"\\overline{")) {
+            let end = if s[0] == b'(' { b')' } else { b'}' };
+            s = rest;
+            while s.first() != Some(&end) {
+                result.repeating.push(Self::parse_digit(&mut s)?);
+            }
             s = &s[1..];
+            if result.repeating.is_empty() { return None; }
+            if result.repeating.iter().all(|d| *d == 0) { result.repeating.clear(); }
+        }
+        result.unrolled = result.repeating_from;
+        while let Some((b'+', rest)) = s.split_first() {
+            let (letter, rest) = rest.split_first()?;
+            s = rest;
+            let takes = Self::parse_takes(&mut s)?;
+            result.restricted_breaking_mut(SplitRule::from_letter(*letter)?).extend(takes);
         }
-        let mut position = 0u8;
-        for c in s {
-            position = position.checked_add(1)?;
-            let oct = Self::parse_octal(*c)?;
-            if oct & 1 != 0 { result.taking_all.add_nimber(position as u16) }
-            if oct & 2 != 0 { result.taking.push(position) }
-            if oct & 4 != 0 { result.breaking.push(position) }
+        if !s.is_empty() { return None; }
+        for rule in [SplitRule::Unequal, SplitRule::Even, SplitRule::Odd] {
+            let breaking = result.restricted_breaking_mut(rule);
+            breaking.sort_unstable();
+            breaking.dedup();
         }
         Some(result)
     }
 
+    /// Returns numbers of tokens (in increasing order) whose removal can leave two non-empty heaps allowed by the given `rule`.
+    #[inline] pub fn restricted_breaking(&self, rule: SplitRule) -> &[u8] {
+        match rule {
+            SplitRule::Unequal => &self.breaking_unequal,
+            SplitRule::Even => &self.breaking_even,
+            SplitRule::Odd => &self.breaking_odd
+        }
+    }
+
+    #[inline] fn restricted_breaking_mut(&mut self, rule: SplitRule) -> &mut Vec<u8> {
+        match rule {
+            SplitRule::Unequal => &mut self.breaking_unequal,
+            SplitRule::Even => &mut self.breaking_even,
+            SplitRule::Odd => &mut self.breaking_odd
+        }
+    }
+
+    /// Returns the rules of the restricted breaking moves together with the numbers of tokens they concern,
+    /// skipping the rules that concern no numbers.
+    pub fn restricted_breakings(&self) -> impl Iterator<Item = (SplitRule, &[u8])> {
+        [SplitRule::Unequal, SplitRule::Even, SplitRule::Odd].into_iter()
+            .map(|rule| (rule, self.restricted_breaking(rule)))
+            .filter(|(_, breaking)| !breaking.is_empty())
+    }
+
+    /// Returns whether the game has any breaking moves restricted by [`SplitRule`].
+    #[inline] pub fn has_restricted_breaking(&self) -> bool {
+        self.restricted_breakings().next().is_some()
+    }
+
+    /// Returns whether the code of the game is finite, i.e. has no [`Game::repeating`] digits.
+    #[inline] pub fn is_code_finite(&self) -> bool {
+        self.repeating.is_empty()
+    }
+
+    /// Returns the number of tokens such that the lists of moves include all the moves that remove less tokens.
+    /// Returns `usize::MAX` if the code of the game is finite.
+    #[inline] pub fn unrolled(&self) -> usize {
+        if self.is_code_finite() { usize::MAX } else { self.unrolled }
+    }
+
+    /// Extends the lists of moves by the moves described by the [`Game::repeating`] digits that remove up to `n` tokens.
+    /// 
+    /// The solvers call this method before calculating the nimber of each heap,
+    /// so the rules apply to arbitrarily large heaps.
+    #[inline] pub fn unroll(&mut self, n: usize) {
+        if n < self.unrolled() { return; }
+        for position in self.unrolled..=n {
+            let digit = self.repeating[(position - self.repeating_from) % self.repeating.len()];
+            self.add_digit(position, digit);
+        }
+        self.unrolled = n + 1;
+    }
+
+    /// Returns the game whose lists include all the moves that remove up to `n` tokens,
+    /// either `self` or its unrolled copy.
+    pub fn unrolled_to(&self, n: usize) -> Cow<'_, Game> {
+        if n < self.unrolled() { return Cow::Borrowed(self); }
+        let mut result = self.clone();
+        result.unroll(n);
+        Cow::Owned(result)
+    }
+
     #[inline] pub fn can_take_all(&self, n: usize) -> bool {
         self.taking_all.try_get_bit(n).unwrap_or(false)
     }
 
-    pub(crate) fn consider_taking<S: SolverEvent>(&self, nimbers: &[u16], option_nimbers: &mut [u64; 1<<(16-6)], stats: &mut S) {
+    pub(crate) fn consider_taking<S: SolverEvent>(&self, nimbers: &[Nimber], option_nimbers: &mut [u64], stats: &mut S) {
         let n = nimbers.len();
         if self.can_take_all(n) { option_nimbers.add_nimber(0) }
         for t in &self.taking {
-            let t = *t as usize;
+            let t = *t;
             if t >= n { break }
             option_nimbers.add_nimber(nimbers[n-t]);
             stats.take_option();
         }
     }
 
-    #[inline] pub fn breaking_moves(&self, n: usize) -> BreakingMoveIterator<std::iter::Copied<std::slice::Iter<'_, u8>>> {
+    /// Adds to `option_nimbers` the nimbers of the options of the heap of size `nimbers.len()`
+    /// that arise from breaking moves that leave more than two heaps.
+    pub(crate) fn consider_breaking_more<S: SolverEvent>(&self, nimbers: &[Nimber], option_nimbers: &mut [u64], stats: &mut S) {
+        let n = nimbers.len();
+        for (i, breaking) in self.breaking_more.iter().enumerate() {
+            let heaps = i + 3;
+            for b in breaking {
+                let b = *b;
+                if b + heaps > n { break }
+                for_each_split(nimbers, n - b, heaps, 1, 0, &mut |nimber| {
+                    option_nimbers.add_nimber(nimber);
+                    stats.break_option();
+                });
+            }
+        }
+    }
+
+    /// Adds to `option_nimbers` the nimbers of the options of the heap of size `nimbers.len()`
+    /// that arise from restricted breaking moves.
+    pub(crate) fn consider_breaking_restricted<S: SolverEvent>(&self, nimbers: &[Nimber], option_nimbers: &mut [u64], stats: &mut S) {
+        let n = nimbers.len();
+        for (rule, breaking) in self.restricted_breakings() {
+            for b in breaking {
+                let b = *b as usize;
+                if b + 2 > n { break }
+                for (i, j) in rule.splits(n - b) {
+                    option_nimbers.add_nimber(nimbers[i] ^ nimbers[j]);
+                    stats.break_option();
+                }
+            }
+        }
+    }
+
+    /// Returns iterator over the (not restricted) breaking moves from the heap of size `n`, which leave two non-empty heaps.
+    /// Each move is given as the sizes of the two heaps left, the smaller first.
+    /// 
+    /// If the code of the game is infinite, it must be unrolled (see [`Game::unroll`]) to include the moves that remove up to `n` tokens.
+    #[inline] pub fn breaking_moves(&self, n: usize) -> BreakingMoveIterator<std::iter::Copied<std::slice::Iter<'_, usize>>> {
         //BreakingMoveIterator::for_iter(n, self.breaking.iter().copied())
         BreakingMoveIterator::for_slice(n, self.breaking.as_slice())
     }
 
-    /// Returns rules as a sequence of octal numbers.
-    pub fn rules(&self) -> [u8; 256] {
-        let mut result = [0; 256];
-        for a in 0..256 {
-            if self.taking_all.get_bit(a) {
-                result[a] |= 1;
-            }
+    /// Returns iterator over the moves from the heap of size `n` that leave more than two heaps.
+    /// Each move is given as a sequence of (non-decreasing) sizes of the heaps left.
+    pub fn breaking_more_moves(&self, n: usize) -> impl Iterator<Item = Vec<usize>> + '_ {
+        self.breaking_more.iter().enumerate().flat_map(move |(i, breaking)|
+            breaking.iter().copied().take_while(move |b| b + i + 3 <= n)
+                .flat_map(move |b| SplitIterator::new(n - b, i + 3))
+        )
+    }
+
+    /// Returns iterator over the restricted breaking moves from the heap of size `n`.
+    /// Each move is given as the sizes of the two heaps left, the smaller first.
+    pub fn restricted_breaking_moves(&self, n: usize) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.restricted_breakings().flat_map(move |(rule, breaking)|
+            breaking.iter().map(|b| *b as usize).take_while(move |b| b + 2 <= n)
+                .flat_map(move |b| rule.splits(n - b))
+        )
+    }
+
+    /// Returns the nimbers (in increasing order, without repetitions) of all options of the heap of size `nimbers.len()`,
+    /// given the `nimbers` of all smaller heaps.
+    ///
+    /// The options are enumerated move by move, without any of the optimizations of the solvers,
+    /// so the method is slow but useful to independently verify the nimbers calculated.
+    pub fn option_nimbers(&self, nimbers: &[Nimber]) -> Vec<Nimber> {
+        let n = nimbers.len();
+        if n >= self.unrolled() { return self.unrolled_to(n).option_nimbers(nimbers); }
+        let mut options = vec![0u64; option_words(nimbers.iter().copied().max().unwrap_or(0))];
+        if self.can_take_all(n) { options.add_nimber(0); }
+        for t in self.taking.iter().copied().take_while(|t| *t < n) {
+            options.add_nimber(nimbers[n-t]);
+        }
+        for (i, j) in self.breaking_moves(n).chain(self.restricted_breaking_moves(n)) {
+            options.add_nimber(nimbers[i] ^ nimbers[j]);
+        }
+        for heaps in self.breaking_more_moves(n) {
+            options.add_nimber(heaps.into_iter().fold(0, |acc, h| acc ^ nimbers[h]));
+        }
+        options.iter().enumerate().filter(|(_, bits)| **bits != 0)
+            .flat_map(|(word, bits)| (0..64).filter(move |bit| bits & (1 << bit) != 0).map(move |bit| (word * 64 + bit) as Nimber))
+            .collect()
+    }
+
+    /// Returns the maximum number of heaps that can be left by a single move.
+    pub fn max_heaps(&self) -> usize {
+        let listed = if let Some(i) = self.breaking_more.iter().rposition(|b| !b.is_empty()) { i + 3 }
+            else if !self.breaking.is_empty() || self.has_restricted_breaking() { 2 }
+            else if !self.taking.is_empty() { 1 }
+            else { 0 };
+        // bit 2^k of a digit allows to leave k heaps
+        listed.max(self.repeating.iter().fold(0, |acc, d| acc | d).checked_ilog2().map_or(0, |k| k as usize))
+    }
+
+    /// Returns the number of (not repeated) code digits, including the first one, for `0` tokens.
+    fn code_len(&self) -> usize {
+        if !self.is_code_finite() { return self.repeating_from; }
+        let last_taking_all = self.taking_all.iter().rposition(|w| *w != 0).map_or(0, |i| i * 64 + 63 - self.taking_all[i].leading_zeros() as usize);
+        [self.taking.last(), self.breaking.last()].into_iter().chain(self.breaking_more.iter().map(|b| b.last()))
+            .flatten().copied().fold(last_taking_all, usize::max) + 1
+    }
+
+    /// Returns rules as a sequence of code digits, without the [`Game::repeating`] ones.
+    /// The first digit concerns `0` tokens and the last one is non-zero (unless the code is infinite or there are no other digits).
+    pub fn rules(&self) -> Vec<u32> {
+        let len = self.code_len();
+        let mut result = vec![0; len];
+        for (a, digit) in result.iter_mut().enumerate() {
+            if self.can_take_all(a) { *digit |= 1; }
+        }
+        for t in self.taking.iter().take_while(|t| **t < len) { result[*t] |= 2; }
+        for b in self.breaking.iter().take_while(|b| **b < len) { result[*b] |= 4; }
+        for (i, breaking) in self.breaking_more.iter().enumerate() {
+            for b in breaking.iter().take_while(|b| **b < len) { result[*b] |= 1 << (i + 3); }
         }
-        for t in &self.taking { result[*t as usize] |= 2; }
-        for b in &self.breaking { result[*b as usize] |= 4; }
         result
     }
 
+    /// Appends the given code `digit` to `result`.
+    fn push_digit(result: &mut Vec<u8>, digit: u32) {
+        if digit < 16 {
+            result.push(char::from_digit(digit, 16).unwrap().to_ascii_uppercase() as u8);
+        } else {
+            result.extend_from_slice(format!("[{digit}]").as_bytes());
+        }
+    }
+
     /// Returns rules as an ascii string, using given decimal separator (for example `b'.'`).
     pub fn to_ascii(&self, separator: u8) -> Vec<u8> {
         let rules = self.rules();
-        let mut number_of_rules = 0;
-        for (i, r) in rules.iter().enumerate() {
-            if *r != 0 { number_of_rules = i; }
-        }
-        number_of_rules += 1;
-        let mut result = Vec::with_capacity(number_of_rules);
-        result.push(rules[0] + b'0');
+        let mut result = Vec::with_capacity(rules.len() + 1);
+        Self::push_digit(&mut result, rules[0]);
         result.push(separator);
-        for r in 1..number_of_rules {
-            result.push(rules[r] + b'0');
+        for r in &rules[1..] {
+            Self::push_digit(&mut result, *r);
+        }
+        if !self.is_code_finite() {
+            result.push(b'(');
+            for r in &self.repeating { Self::push_digit(&mut result, *r); }
+            result.push(b')');
+        }
+        for (rule, breaking) in self.restricted_breakings() {
+            result.push(b'+');
+            result.push(rule.letter());
+            for (i, b) in breaking.iter().enumerate() {
+                if i != 0 { result.push(b','); }
+                result.extend_from_slice(b.to_string().as_bytes());
+            }
         }
         result
     }
@@ -90,18 +462,20 @@ impl Game {
     /// Returns the total number of taking iterations needed (by any of the methods: naive, RC or RC2)
     /// to calculate the nimbers of all positions up to and including the one given.
     pub fn taking_iters(&self, position: usize) -> usize {
-        self.taking.iter().map(|t| position.saturating_sub(*t as usize)).sum()
+        self.unrolled_to(position).taking.iter().map(|t| position.saturating_sub(*t)).sum()
     }
 
     /// Returns the total number of breaking iterations needed by the naive method
     /// to calculate the nimbers of all positions up to and including the one specified.
     pub fn breaking_naive_iters(&self, position: usize) -> usize {
-        self.breaking.iter().map(|b| {
-            let b = *b as usize;
+        self.unrolled_to(position).breaking.iter().map(|b| {
+            let b = *b;
             if position < b + 2 { 0 } else
             if position & 1 != b & 1 { let k = (position - b - 1) / 2; k*k+k } // difference is odd
             else { let hd = (position - b) / 2; let k = hd-1; k*k+k + hd }  // difference is even
-        }).sum()
+        }).sum::<usize>() + self.restricted_breakings().map(|(rule, breaking)|
+            breaking.iter().map(|b| (*b as usize + 2 ..= position).map(|n| rule.number_of_splits(n - *b as usize)).sum::<usize>()).sum::<usize>()
+        ).sum::<usize>()
     }
 
     /// Returns the total number of iterations needed by the naive method
@@ -110,40 +484,106 @@ impl Game {
         self.taking_iters(position) + self.breaking_naive_iters(position)
     }
 
+    /// Returns the largest number of tokens that can be taken by a single move.
+    /// The code of the game must be finite.
+    fn max_to_take(&self) -> usize {
+        let mut max_to_take = self.code_len() - 1;
+        for (_, breaking) in self.restricted_breakings() { max_to_take = max_to_take.max(*breaking.last().unwrap() as usize); }
+        max_to_take
+    }
+
+    /// Returns the number of the first nimbers (of heaps 0, 1, 2, ...) that,
+    /// if they are repeated with the given `period` starting from the `preperiod`,
+    /// prove (by the theorem used by [`Game::period`]) that all further nimbers are repeated as well.
+    /// 
+    /// Returns `None` if the theorem does not hold for the game (in particular, if its code is infinite) or the `period`.
+    pub fn period_proof_len(&self, preperiod: usize, period: usize) -> Option<usize> {
+        if period == 0 || !self.breaking_unequal.is_empty() || !self.is_code_finite() { return None; }
+        if period % 2 == 1 && self.has_restricted_breaking() { return None; }
+        // the number of heaps a move can leave, i.e. 2 for octal games with breaking moves, and 1 for games without
+        let mult = self.max_heaps().max(1);
+        let max_to_take = self.max_to_take();
+        // the largest heap left by a breaking move must be non-empty also after shortening it by the period
+        let preperiod = if mult > 1 { preperiod.max(1) } else { preperiod };
+        // the moves available from heaps of less than max_to_take+mult tokens can differ from the moves available from larger heaps,
+        // so the heaps shortened by the period must be at least that large
+        Some(((preperiod + period) * mult + max_to_take).max(max_to_take + mult + period))
+    }
+
+    /// Returns the number of the first nimbers (of heaps 0, 1, 2, ...) that,
+    /// if they grow by a saltus with the given `period` starting from the `preperiod`,
+    /// prove (by the theorem used by [`Game::arithmetic_period`]) that all further nimbers grow by the saltus as well.
+    /// 
+    /// Returns `None` if the theorem does not hold for the game or the `period`.
+    pub fn arithmetic_period_proof_len(&self, preperiod: usize, period: usize) -> Option<usize> {
+        self.period_proof_len(self.arithmetic_preperiod(preperiod), period).map(|len| len + period)
+    }
+
+    /// Returns the pre-period considered by the theorem used by [`Game::arithmetic_period`] to calculate the number of nimbers needed,
+    /// which covers all heaps of less than max_to_take+mult tokens (see [`Game::period_proof_len`]).
+    fn arithmetic_preperiod(&self, preperiod: usize) -> usize {
+        preperiod.max(self.max_to_take() + self.max_heaps().max(1))
+    }
+
     /// Tries to calculates (pre-period, period) of the game using the nimbers of its few first positions.
     /// 
     /// Uses a generalized version of a theorem from the paper:
     /// Richard K. Guy and Cedric A. B. Smith, The G-values of various games, 1956
-    pub fn period(&self, nimbers: &[u16]) -> Option<(usize, usize)> {
-        let mut max_to_take = 
-            (self.taking_all.msb_index().unwrap_or(0) as u8)
-            .max(*self.taking.last().unwrap_or(&0));
-
-        let log2mult = if let Some(b) = self.breaking.last() {   // has breaking moves?
-            max_to_take = max_to_take.max(*b);
-            1   // we need *2 (or <<1) for octal games
-        } else { 0 };   // and *1 (or <<0) otherwise
-
+    /// 
+    /// The theorem does not hold for the games with unequal splits (like Grundy's game) or infinite codes,
+    /// so `None` is always returned for them.
+    /// For the games with even or odd splits, only even periods are considered, as they preserve the parities of the heaps.
+    pub fn period(&self, nimbers: &[Nimber]) -> Option<(usize, usize)> {
+        if !self.breaking_unequal.is_empty() || !self.is_code_finite() { return None; }
+        let period_step = if self.has_restricted_breaking() { 2 } else { 1 };
         let len = nimbers.len();
-        for period in 1 ..= (len >> log2mult) {
+        for period in (period_step ..= (len / self.max_heaps().max(1))).step_by(period_step) {
             let mut preperiod = len - period;
             while preperiod > 0 && nimbers[preperiod-1] == nimbers[preperiod-1+period] {
                 preperiod -= 1;
             }
-            //if len >= (2*preperiod + 2*period + max_to_take - 1).max(max_to_take+2) {
-            if len >= ((preperiod + period) << log2mult) + max_to_take as usize {
+            if self.period_proof_len(preperiod, period).is_some_and(|proof_len| len >= proof_len) {
                 return Some((preperiod, period));
             }
         }
         None
     }
+
+    /// Tries to calculates (pre-period, period, saltus) of the arithmetically periodic game
+    /// using the nimbers of its few first positions.
+    /// 
+    /// The nimbers are arithmetically periodic if, from the pre-period on, each nimber
+    /// is greater by the (non-zero) saltus than the nimber the period before.
+    /// The saltus is required to be a power of two that exceeds all the nimbers of the pre-period and the first period.
+    /// Then, following the theorem of:
+    /// Richard B. Austin, Impartial and partizan games, 1976,
+    /// the arithmetic periodicity is proven by the number of the first nimbers given by [`Game::arithmetic_period_proof_len`],
+    /// which exceeds the number needed by [`Game::period`] by the period.
+    pub fn arithmetic_period(&self, nimbers: &[Nimber]) -> Option<(usize, usize, Nimber)> {
+        if !self.breaking_unequal.is_empty() || !self.is_code_finite() { return None; }
+        let period_step = if self.has_restricted_breaking() { 2 } else { 1 };
+        let len = nimbers.len();
+        for period in (period_step ..= (len.saturating_sub(1) / self.max_heaps().max(1))).step_by(period_step) {
+            let Some(saltus) = nimbers[len-1].checked_sub(nimbers[len-1-period]) else { continue };
+            if !saltus.is_power_of_two() { continue; }
+            let mut preperiod = len - period;
+            while preperiod > 0 && nimbers[preperiod-1].checked_add(saltus) == Some(nimbers[preperiod-1+period]) {
+                preperiod -= 1;
+            }
+            if nimbers[..preperiod+period].iter().any(|n| *n >= saltus) { continue; }
+            if self.arithmetic_period_proof_len(preperiod, period).is_some_and(|proof_len| len >= proof_len) {
+                return Some((preperiod, period, saltus));
+            }
+        }
+        None
+    }
 }
 
 impl FromStr for Game {
     type Err = &'static str;
     
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Self::from_ascii(s.as_bytes()).ok_or("game description must be in format [X.]OOO... where X is 0 or 4 and (up to 255) Os are octal digits")
+        Self::from_ascii(s.as_bytes()).ok_or("game description must be in format [X.]DDD...[(DDD...)][+RT,T,...] where X is 0, 4, 8 or C, Ds are octal or hexadecimal digits (or decimal numbers in square brackets), digits in parentheses are repeated infinitely, R is u, e or o (split rule) and Ts are numbers of tokens taken, or S(T,T,...) or S-all-but(T,T,...) for subtraction games")
     }
 }
 
@@ -159,6 +599,61 @@ impl ToString for Game {
     }
 }*/
 
+/// Calls `f` with the xor of `acc` and the nimbers of the heaps of each split of `n` tokens
+/// into `heaps` non-empty heaps, not smaller than `min`, whose sizes are non-decreasing.
+fn for_each_split<F: FnMut(Nimber)>(nimbers: &[Nimber], n: usize, heaps: usize, min: usize, acc: Nimber, f: &mut F) {
+    if heaps == 1 { f(acc ^ nimbers[n]); return; }
+    for a in min ..= n / heaps {
+        for_each_split(nimbers, n - a, heaps - 1, a, acc ^ nimbers[a], f);
+    }
+}
+
+/// Iterator over the splits of `n` tokens into the given number of non-empty heaps.
+/// Each split is given as a sequence of (non-decreasing) heap sizes.
+pub struct SplitIterator {
+    n: usize,
+    /// The split to return by the next call of `next`, empty if there are no more splits.
+    parts: Vec<usize>
+}
+
+impl SplitIterator {
+    /// Returns iterator over the splits of `n` tokens into `heaps` non-empty heaps.
+    pub fn new(n: usize, heaps: usize) -> Self {
+        let parts = if heaps == 0 || n < heaps { Vec::new() } else {
+            let mut parts = vec![1; heaps];
+            parts[heaps - 1] = n - (heaps - 1);
+            parts
+        };
+        Self { n, parts }
+    }
+}
+
+impl Iterator for SplitIterator {
+    type Item = Vec<usize>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.parts.is_empty() { return None; }
+        let result = self.parts.clone();
+        let k = self.parts.len();
+        // find the last heap (except the last one) that can be enlarged; all the next ones (except the last) get its size
+        let mut prefix_sum: usize = self.parts[..k-1].iter().sum();
+        for i in (0..k-1).rev() {
+            prefix_sum -= self.parts[i];
+            let v = self.parts[i] + 1;
+            let sum = prefix_sum + v * (k - 1 - i);
+            if sum <= self.n && self.n - sum >= v {
+                for p in &mut self.parts[i..k-1] { *p = v; }
+                self.parts[k-1] = self.n - sum;
+                return Some(result);
+            }
+        }
+        self.parts.clear();
+        Some(result)
+    }
+}
+
+impl FusedIterator for SplitIterator {}
+
 pub struct BreakingMoveIterator<I> {
     current: I,
     n: usize,
@@ -224,4 +719,205 @@ pub(crate) mod tests {
         assert_eq!(g.breaking_moves(4).collect::<Vec<_>>(), vec![(1, 2), (1, 1)]);
         assert_eq!(g.breaking_moves(5).collect::<Vec<_>>(), vec![(1, 3), (2, 2), (1, 2)]);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_hexadecimal_code() {
+        let g = Game::from_str("8.F4[16]").unwrap();
+        assert_eq!(g.taking_all, [0b10]);
+        assert_eq!(g.taking, vec![1]);
+        assert_eq!(g.breaking, vec![1, 2]);
+        assert_eq!(g.breaking_more, vec![vec![0, 1], vec![3]]);
+        assert_eq!(g.max_heaps(), 4);
+        assert_eq!(g.to_string(), "8.F4[16]");
+        assert_eq!(Game::from_str("0dd").unwrap().to_string(), "0.D");
+        assert_eq!(Game::from_str(".137").unwrap().to_string(), "0.137");
+        assert!(Game::from_str("1.7").is_err());
+        assert!(Game::from_str("0.[16").is_err());
+        assert!(Game::from_str("0.G").is_err());
+    }
+
+    #[test]
+    fn test_infinite_code() {
+        let g = Game::from_str("0.1(6)").unwrap();
+        assert_eq!((g.repeating.as_slice(), g.repeating_from, g.unrolled()), ([6].as_slice(), 2, 2));
+        assert_eq!(g.to_string(), "0.1(6)");
+        assert_eq!(Game::from_str("0.1\\overline{6}").unwrap().to_string(), "0.1(6)");
+        assert_eq!(Game::from_str("4.(0)").unwrap().to_string(), "4.");
+        let mut g = g.clone();
+        g.unroll(4);
+        assert_eq!((g.taking.as_slice(), g.breaking.as_slice(), g.unrolled()), ([2, 3, 4].as_slice(), [2, 3, 4].as_slice(), 5));
+        assert_eq!(g.to_string(), "0.1(6)");
+        assert_eq!(g.max_heaps(), 2);
+        assert_eq!(Game::from_str("0.(8)").unwrap().max_heaps(), 3);
+        assert!(Game::from_str("0.1()").is_err());
+        assert!(Game::from_str("0.1(6").is_err());
+        assert!(Game::from_str("0.1(6)7").is_err());
+        let long = format!("0.{}", "4".repeat(300));
+        assert_eq!(Game::from_str(&long).unwrap().to_string(), long);
+    }
+
+    #[test]
+    fn test_subtraction_sets() {
+        assert_eq!(Game::from_str("S(1,3,4)").unwrap().to_string(), "0.3033");
+        assert_eq!(Game::from_str("S(4, 1,3)").unwrap().to_string(), "0.3033");
+        assert_eq!(Game::from_str("S-all-but(2,5)").unwrap().to_string(), "0.30330(3)");
+        assert_eq!(Game::from_str("S-all-but()").unwrap().to_string(), "0.(3)");
+        assert!(Game::from_str("S(0,1)").is_err());
+        assert!(Game::from_str("S(1,x)").is_err());
+        assert!(Game::from_str("S-all-but(2").is_err());
+        // all subtractions allowed, like in nim
+        assert_eq!(nimbers::<crate::NaiveSolver>("S-all-but()", 300), (0..300).collect::<Vec<Nimber>>());
+    }
+
+    #[test]
+    fn test_infinite_code_solvers() {
+        for game in ["0.(6)", "0.1(4)", "0.(37)", "S-all-but(2,5)", "4.(1)", "0.0(9)", "0.(3)+e0"] {
+            // the game with the finite code that agrees with the infinite one for the heaps considered
+            let mut finite = Game::from_str(game).unwrap().unrolled_to(300).into_owned();
+            finite.repeating.clear();
+            let naive: Vec<Nimber> = <crate::NaiveSolver as crate::Solver>::new(finite).take(300).collect();
+            assert_eq!(nimbers::<crate::NaiveSolver>(game, 300), naive, "naive, {game}");
+            assert_eq!(nimbers::<crate::RCSolver>(game, 300), naive, "RC, {game}");
+            assert_eq!(nimbers::<crate::RC2Solver>(game, 300), naive, "RC2, {game}");
+            assert_eq!(Game::from_str(game).unwrap().period(&naive), None);
+        }
+    }
+
+    #[test]
+    fn test_split_iterator() {
+        assert_eq!(SplitIterator::new(6, 3).collect::<Vec<_>>(), vec![vec![1, 1, 4], vec![1, 2, 3], vec![2, 2, 2]]);
+        assert_eq!(SplitIterator::new(3, 3).collect::<Vec<_>>(), vec![vec![1, 1, 1]]);
+        assert_eq!(SplitIterator::new(2, 3).count(), 0);
+        assert_eq!(SplitIterator::new(8, 4).count(), 5);
+        let g = Game::from_str("0.08").unwrap();
+        assert_eq!(g.breaking_more_moves(5).collect::<Vec<_>>(), vec![vec![1, 1, 1]]);
+        assert_eq!(g.breaking_more_moves(4).count(), 0);
+    }
+
+    fn nimbers<S: crate::Solver<Stats = ()>>(game: &str, n: usize) -> Vec<Nimber> {
+        S::new(Game::from_str(game).unwrap()).take(n).collect()
+    }
+
+    #[test]
+    fn test_hexadecimal_solvers() {
+        assert_eq!(nimbers::<crate::NaiveSolver>("8.", 7), [0, 0, 0, 1, 1, 2, 2]);
+        assert_eq!(nimbers::<crate::NaiveSolver>("[16].", 8), [0, 0, 0, 0, 1, 1, 1, 2]);
+        for game in ["8.", "0.F", "4.8", "0.B", "0.9F", "0.[15][24]7", "C.3"] {
+            let naive = nimbers::<crate::NaiveSolver>(game, 300);
+            assert_eq!(nimbers::<crate::RCSolver>(game, 300), naive, "RC, {game}");
+            assert_eq!(nimbers::<crate::RC2Solver>(game, 300), naive, "RC2, {game}");
+        }
+    }
+
+    fn next_after<S: crate::Solver<Stats = ()>>(game: &str, nimbers: &[Nimber]) -> Option<Nimber> {
+        S::with_nimbers(Game::from_str(game).unwrap(), nimbers.to_vec()).next()
+    }
+
+    #[test]
+    fn test_max_nimber() {
+        let nim: Vec<Nimber> = (0..=0xFFFF).collect();   // in nim, the nimber of each heap equals its size
+        assert_eq!(next_after::<crate::NaiveSolver>("0.(3)", &nim[..1000]), Some(1000));
+        #[cfg(not(feature = "wide-nimbers"))] assert_eq!(next_after::<crate::NaiveSolver>("0.(3)", &nim), None);
+        #[cfg(feature = "wide-nimbers")] assert_eq!(next_after::<crate::NaiveSolver>("0.(3)", &nim), Some(1<<16));
+        #[cfg(not(feature = "wide-nimbers"))] {
+            let rc2_max = <crate::RC2Solver as crate::Solver>::MAX_NIMBER as usize;
+            let nim_up_to_rc2_max = format!("S({})", (1..=rc2_max+1).map(|t| t.to_string()).collect::<Vec<_>>().join(","));
+            assert_eq!(next_after::<crate::RC2Solver>(&nim_up_to_rc2_max, &nim[..rc2_max]), Some(rc2_max as Nimber));
+            assert_eq!(next_after::<crate::RC2Solver>(&nim_up_to_rc2_max, &nim[..=rc2_max]), None);
+        }
+    }
+
+    #[test]
+    fn test_option_nimbers() {
+        let g = Game::from_str("0.07").unwrap();
+        assert_eq!(g.option_nimbers(&[0, 0, 1, 1, 2]), [1]);
+        for game in ["0.137", "4.F", "0.08", "0.9F", "0.3+e0,2+o1", "grundy"] {
+            let g = Game::from_str(game).unwrap();
+            let nimbers = nimbers::<crate::NaiveSolver>(game, 200);
+            for n in 0..nimbers.len() {
+                let options = g.option_nimbers(&nimbers[..n]);
+                let mex = options.iter().enumerate().find(|(i, o)| *i as Nimber != **o).map_or(options.len(), |(i, _)| i);
+                assert_eq!(mex as Nimber, nimbers[n], "{game}, {n}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_restricted_code() {
+        let g = Game::from_str("0.7+u0+e2,1+o1").unwrap();
+        assert_eq!(g.breaking_unequal, vec![0]);
+        assert_eq!(g.breaking_even, vec![1, 2]);
+        assert_eq!(g.breaking_odd, vec![1]);
+        assert_eq!(g.max_heaps(), 2);
+        assert_eq!(g.to_string(), "0.7+u0+e1,2+o1");
+        assert_eq!(Game::from_str("grundy").unwrap().to_string(), "0.+u0");
+        assert!(Game::from_str("0.7+").is_err());
+        assert!(Game::from_str("0.7+x1").is_err());
+        assert!(Game::from_str("0.7+u").is_err());
+        assert!(Game::from_str("0.7+u256").is_err());
+    }
+
+    #[test]
+    fn test_split_rules() {
+        assert_eq!(SplitRule::Unequal.splits(6).collect::<Vec<_>>(), vec![(1, 5), (2, 4)]);
+        assert_eq!(SplitRule::Even.splits(8).collect::<Vec<_>>(), vec![(2, 6), (4, 4)]);
+        assert_eq!(SplitRule::Odd.splits(8).collect::<Vec<_>>(), vec![(1, 7), (3, 5)]);
+        assert_eq!(SplitRule::Odd.splits(7).count(), 0);
+        for rule in [SplitRule::Unequal, SplitRule::Even, SplitRule::Odd] {
+            for n in 0..12 {
+                assert_eq!(rule.number_of_splits(n), rule.splits(n).count());
+                assert_eq!(rule.number_of_splits(n), (1..=n/2).filter(|i| rule.allows(*i, n-i)).count());
+            }
+        }
+        let g = Game::from_str("0.+u0+e1").unwrap();
+        assert_eq!(g.restricted_breaking_moves(5).collect::<Vec<_>>(), vec![(1, 4), (2, 3), (2, 2)]);
+        assert_eq!(g.breaking_naive_iters(5), (1 + 1 + 2) + 1);
+    }
+
+    #[test]
+    fn test_restricted_solvers() {
+        assert_eq!(nimbers::<crate::NaiveSolver>("grundy", 21), [0, 0, 0, 1, 0, 2, 1, 0, 2, 1, 0, 2, 1, 3, 2, 1, 3, 2, 4, 3, 0]);
+        for game in ["grundy", "0.+e0", "0.+o0", "0.7+u0", "0.1+u1", "0.3+e0,2+o1", "4.+u1"] {
+            let naive = nimbers::<crate::NaiveSolver>(game, 500);
+            assert_eq!(nimbers::<crate::RCSolver>(game, 500), naive, "RC, {game}");
+            assert_eq!(nimbers::<crate::RC2Solver>(game, 500), naive, "RC2, {game}");
+        }
+    }
+
+    #[test]
+    fn test_restricted_period() {
+        let g = Game::from_str("grundy").unwrap();
+        assert_eq!(g.period(&nimbers::<crate::NaiveSolver>("grundy", 1000)), None);
+        let g = Game::from_str("0.+e0").unwrap();
+        let (_, period) = g.period(&nimbers::<crate::NaiveSolver>("0.+e0", 1000)).unwrap();
+        assert_eq!(period % 2, 0);
+    }
+
+    #[test]
+    fn test_period_proof() {
+        let g = Game::from_str("0.07").unwrap();
+        let nimbers = nimbers::<crate::NaiveSolver>("0.07", 200);
+        assert_eq!(g.period(&nimbers), Some((53, 34)));
+        assert_eq!(g.period_proof_len(53, 34), Some((53 + 34) * 2 + 2));
+        assert_eq!(g.period(&nimbers[..(53 + 34) * 2 + 1]), None);
+        assert_eq!(Game::from_str("0.3").unwrap().period_proof_len(0, 2), Some(1 + 1 + 2));
+        assert_eq!(Game::from_str("0.1").unwrap().period(&[0, 1, 0]), None);
+        assert_eq!(Game::from_str("0.4").unwrap().period(&[0, 0, 0]), None);
+        assert_eq!(Game::from_str("0.2").unwrap().period(&[0, 0]), None);
+        assert_eq!(Game::from_str("0.1").unwrap().arithmetic_period(&[0, 1]), None);
+        assert_eq!(Game::from_str("grundy").unwrap().period_proof_len(0, 2), None);
+        assert_eq!(Game::from_str("0.+e0").unwrap().period_proof_len(0, 3), None);
+    }
+
+    #[test]
+    fn test_arithmetic_period() {
+        let g = Game::from_str("0.28").unwrap();
+        let n = nimbers::<crate::NaiveSolver>("0.28", 300);
+        assert_eq!(g.period(&n), None);
+        assert_eq!(g.arithmetic_period(&n), Some((1, 53, 16)));
+        assert_eq!(g.arithmetic_period_proof_len(1, 53), Some((2 + 3 + 53) * 3 + 2 + 53));
+        assert_eq!(g.arithmetic_period(&n[..g.arithmetic_period_proof_len(1, 53).unwrap()-1]), None);
+        assert_eq!(Game::from_str("0.44").unwrap().arithmetic_period(&nimbers::<crate::NaiveSolver>("0.44", 10)), None);
+        assert_eq!(Game::from_str("0.07").unwrap().arithmetic_period(&nimbers::<crate::NaiveSolver>("0.07", 300)), None);
+    }
+}
@@ -0,0 +1,351 @@
+//! Kernels for evaluating breaking moves.
+//!
+//! The nimbers of the splits `(i, after_take-i)` are calculated in blocks of `LANES` consecutive `i`,
+//! by xoring a block of the sequence with the reversed (mirrored) block from the other half of the sequence,
+//! or (for the listed positions) by gathering the nimbers of both heaps.
+//! The portable kernels are plain Rust, with fixed-size loops shaped so that the compiler can vectorize them.
+//! With the `simd` feature (enabled by default), on x86-64 processors that support AVX2 (detected at run time),
+//! explicit AVX2 kernels are used instead, which gather, xor and convert the nimbers to bits of the option set in vector registers.
+
+use std::ops::Range;
+
+use crate::Nimber;
+
+/// Number of options calculated in a single block, sized to fit in vector registers if the compiler vectorizes the loop.
+const LANES: usize = 16;
+
+/// Number of consecutive positions (of the smaller heap) considered for all breaking moves
+/// before going to the next block, which keeps the nimbers used in cache.
+const BLOCK: usize = 1 << 12;
+
+/// Adds `nimber` to `option_nimbers`, which must be large enough to include it.
+#[inline(always)] fn scatter(option_nimbers: &mut [u64], nimber: Nimber) {
+    option_nimbers[(nimber >> 6) as usize] |= 1u64 << (nimber & 63);
+}
+
+/// Collects option nimbers calculated in blocks of `LANES`.
+///
+/// Nimbers below 64 are collected in separate words for each lane (which the compiler can keep in registers),
+/// which are merged into the option bitset at the end.
+/// This breaks the chain of dependent memory updates of the first word of the bitset,
+/// which otherwise limits the speed of games with small nimbers.
+struct Collector<'o> {
+    option_nimbers: &'o mut [u64],
+    small: [u64; LANES]
+}
+
+impl<'o> Collector<'o> {
+    #[inline(always)] fn new(option_nimbers: &'o mut [u64]) -> Self {
+        Self { option_nimbers, small: [0; LANES] }
+    }
+
+    #[inline(always)] fn add_block(&mut self, xors: &[Nimber; LANES]) {
+        if xors.iter().fold(0, |a, x| a | x) < 64 {
+            for (small, x) in self.small.iter_mut().zip(xors) { *small |= 1u64 << x; }
+        } else {
+            for x in xors { scatter(self.option_nimbers, *x); }
+        }
+    }
+
+    #[inline(always)] fn add(&mut self, nimber: Nimber) { scatter(self.option_nimbers, nimber) }
+}
+
+impl Drop for Collector<'_> {
+    #[inline(always)] fn drop(&mut self) {
+        self.option_nimbers[0] |= self.small.iter().fold(0, |a, s| a | s);
+    }
+}
+
+/// Adds to `option_nimbers` the nimbers of the splits `(i, after_take-i)` for all `i` in `range`.
+/// All `i` must not exceed `after_take`.
+#[inline] pub(crate) fn add_mirrored_xors(option_nimbers: &mut [u64], nimbers: &[Nimber], range: Range<usize>, after_take: usize) {
+    if range.is_empty() { return; }
+    let front = &nimbers[range.clone()];
+    let back = &nimbers[after_take+1-range.end .. after_take+1-range.start];  // back[len-1-k] is paired with front[k]
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    if avx2::is_available() {
+        // SAFETY: AVX2 is supported by the processor
+        return unsafe { avx2::add_mirrored_xors(option_nimbers, front, back) };
+    }
+    add_mirrored_xors_portable(option_nimbers, front, back)
+}
+
+/// Adds to `option_nimbers` the xors of `front[k]` and `back[back.len()-1-k]` for all `k`, by the portable kernel.
+/// Both slices must be of the same length.
+#[inline] fn add_mirrored_xors_portable(option_nimbers: &mut [u64], front: &[Nimber], back: &[Nimber]) {
+    let mut collector = Collector::new(option_nimbers);
+    let mut front_blocks = front.chunks_exact(LANES);
+    let mut back_blocks = back.rchunks_exact(LANES);
+    for (f, b) in (&mut front_blocks).zip(&mut back_blocks) {
+        let f: &[Nimber; LANES] = f.try_into().unwrap();
+        let b: &[Nimber; LANES] = b.try_into().unwrap();
+        let mut xors: [Nimber; LANES] = [0; LANES];
+        for l in 0..LANES { xors[l] = f[l] ^ b[LANES-1-l]; }
+        collector.add_block(&xors);
+    }
+    let (f, b) = (front_blocks.remainder(), back_blocks.remainder());
+    for (k, f) in f.iter().enumerate() { collector.add(f ^ b[b.len()-1-k]); }
+}
+
+/// Adds to `option_nimbers` the nimbers of the splits `(i, after_take-i)` for `i` listed in `positions`
+/// (all not exceeding `after_take`).
+#[inline] pub(crate) fn add_gathered_xors(option_nimbers: &mut [u64], nimbers: &[Nimber], positions: &[usize], after_take: usize) {
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    if avx2::is_available() {
+        // SAFETY: AVX2 is supported by the processor
+        return unsafe { avx2::add_gathered_xors(option_nimbers, nimbers, positions, after_take) };
+    }
+    add_gathered_xors_portable(option_nimbers, nimbers, positions, after_take)
+}
+
+/// Adds to `option_nimbers` the nimbers of the splits `(i, after_take-i)` for `i` listed in `positions`, by the portable kernel.
+#[inline] fn add_gathered_xors_portable(option_nimbers: &mut [u64], nimbers: &[Nimber], positions: &[usize], after_take: usize) {
+    let mut collector = Collector::new(option_nimbers);
+    let mut blocks = positions.chunks_exact(LANES);
+    for p in &mut blocks {
+        let mut xors: [Nimber; LANES] = [0; LANES];
+        for l in 0..LANES { xors[l] = nimbers[p[l]] ^ nimbers[after_take - p[l]]; }
+        collector.add_block(&xors);
+    }
+    for i in blocks.remainder() { collector.add(nimbers[*i] ^ nimbers[after_take - i]); }
+}
+
+/// Adds to `option_nimbers` the nimbers of the splits `(i, after_take-i)` for each `(after_take, range)` in `ranges`
+/// and all `i` from both `range` and `part`.
+/// The positions are traversed in blocks, each considered for all ranges.
+pub(crate) fn add_blocked_xors(option_nimbers: &mut [u64], nimbers: &[Nimber], ranges: &[(usize, Range<usize>)], part: Range<usize>) {
+    let mut block_start = part.start;
+    while block_start < part.end {
+        let block_end = (block_start + BLOCK).min(part.end);
+        for (after_take, range) in ranges {
+            let start = range.start.max(block_start);
+            let end = range.end.min(block_end);
+            if start < end { add_mirrored_xors(option_nimbers, nimbers, start..end, *after_take); }
+        }
+        block_start = block_end;
+    }
+}
+
+/// AVX2 kernels for x86-64.
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+mod avx2 {
+    use std::arch::x86_64::*;
+
+    use crate::Nimber;
+
+    /// Number of nimbers in a vector register.
+    const LANES: usize = 32 / std::mem::size_of::<Nimber>();
+
+    /// Returns whether the processor supports AVX2 (the result of the detection is cached by the standard library).
+    #[inline(always)] pub(super) fn is_available() -> bool {
+        cfg!(target_feature = "avx2") || std::is_x86_feature_detected!("avx2")
+    }
+
+    /// Returns the nimbers of `v` in the reversed order.
+    #[cfg(not(feature = "wide-nimbers"))]
+    #[inline] #[target_feature(enable = "avx2")]
+    fn reversed(v: __m256i) -> __m256i {
+        let within_halves = _mm256_shuffle_epi8(v, _mm256_setr_epi8(
+            14, 15, 12, 13, 10, 11, 8, 9, 6, 7, 4, 5, 2, 3, 0, 1,
+            14, 15, 12, 13, 10, 11, 8, 9, 6, 7, 4, 5, 2, 3, 0, 1));
+        _mm256_permute4x64_epi64(within_halves, 0b01_00_11_10)
+    }
+
+    /// Returns the nimbers of `v` in the reversed order.
+    #[cfg(feature = "wide-nimbers")]
+    #[inline] #[target_feature(enable = "avx2")]
+    fn reversed(v: __m256i) -> __m256i {
+        _mm256_permutevar8x32_epi32(v, _mm256_setr_epi32(7, 6, 5, 4, 3, 2, 1, 0))
+    }
+
+    /// Returns the nimbers, each with all but the lowest 6 bits set.
+    #[cfg(not(feature = "wide-nimbers"))]
+    #[inline] #[target_feature(enable = "avx2")]
+    fn above_63() -> __m256i { _mm256_set1_epi16(!63) }
+
+    /// Returns the nimbers, each with all but the lowest 6 bits set.
+    #[cfg(feature = "wide-nimbers")]
+    #[inline] #[target_feature(enable = "avx2")]
+    fn above_63() -> __m256i { _mm256_set1_epi32(!63) }
+
+    /// Returns the 8 nimbers of `v` in two vectors, each nimber in a 64-bit lane.
+    #[cfg(not(feature = "wide-nimbers"))]
+    #[inline] #[target_feature(enable = "avx2")]
+    fn widened(v: __m128i) -> [__m256i; 2] {
+        [_mm256_cvtepu16_epi64(v), _mm256_cvtepu16_epi64(_mm_srli_si128(v, 8))]
+    }
+
+    /// Returns the 4 nimbers of `v`, each in a 64-bit lane.
+    #[cfg(feature = "wide-nimbers")]
+    #[inline] #[target_feature(enable = "avx2")]
+    fn widened(v: __m128i) -> [__m256i; 1] {
+        [_mm256_cvtepu32_epi64(v)]
+    }
+
+    /// Returns `small` with the bits of the nimbers given in the 64-bit lanes of `nimbers` (all smaller than 64) set.
+    #[inline] #[target_feature(enable = "avx2")]
+    fn with_bits(small: __m256i, nimbers: __m256i) -> __m256i {
+        _mm256_or_si256(small, _mm256_sllv_epi64(_mm256_set1_epi64x(1), nimbers))
+    }
+
+    /// Adds the nimbers of `v` to the option set. The nimbers smaller than 64 are collected in the lanes of `small`
+    /// (which are merged into `option_nimbers` at the end, by [`merge`]), and larger ones are added by scalar code.
+    #[inline] #[target_feature(enable = "avx2")]
+    fn collect(option_nimbers: &mut [u64], small: __m256i, v: __m256i) -> __m256i {
+        if _mm256_testz_si256(v, above_63()) != 0 {
+            let mut small = small;
+            for half in [_mm256_castsi256_si128(v), _mm256_extracti128_si256::<1>(v)] {
+                for quarter in widened(half) { small = with_bits(small, quarter); }
+            }
+            return small;
+        }
+        let mut nimbers: [Nimber; LANES] = [0; LANES];
+        // SAFETY: nimbers occupy 32 bytes
+        unsafe { _mm256_storeu_si256(nimbers.as_mut_ptr().cast(), v) };
+        for n in nimbers { option_nimbers[(n >> 6) as usize] |= 1u64 << (n & 63); }
+        small
+    }
+
+    /// Adds the nimbers given in the 64-bit lanes of `v` to the option set, like [`collect`].
+    #[inline] #[target_feature(enable = "avx2")]
+    fn collect_4(option_nimbers: &mut [u64], small: __m256i, v: __m256i) -> __m256i {
+        if _mm256_testz_si256(v, _mm256_set1_epi64x(!63)) != 0 { return with_bits(small, v); }
+        let mut nimbers = [0u64; 4];
+        // SAFETY: nimbers occupy 32 bytes
+        unsafe { _mm256_storeu_si256(nimbers.as_mut_ptr().cast(), v) };
+        for n in nimbers { option_nimbers[(n >> 6) as usize] |= 1u64 << (n & 63); }
+        small
+    }
+
+    /// Merges the nimbers collected in `small` into `option_nimbers`.
+    #[inline] #[target_feature(enable = "avx2")]
+    fn merge(option_nimbers: &mut [u64], small: __m256i) {
+        let mut lanes = [0u64; 4];
+        // SAFETY: lanes occupy 32 bytes
+        unsafe { _mm256_storeu_si256(lanes.as_mut_ptr().cast(), small) };
+        option_nimbers[0] |= lanes.iter().fold(0, |a, l| a | l);
+    }
+
+    /// Returns the nimbers of the positions given in the 64-bit lanes of `positions`, each in a 32-bit lane.
+    ///
+    /// # Safety
+    /// Each position must be in the range `1..nimbers.len()`.
+    #[cfg(not(feature = "wide-nimbers"))]
+    #[inline] #[target_feature(enable = "avx2")]
+    unsafe fn gather(nimbers: &[Nimber], positions: __m256i) -> __m128i {
+        // gathers 32 bits that end with the nimber of each position (and start with the nimber of the previous one)
+        let pairs = unsafe { _mm256_i64gather_epi32::<2>(nimbers.as_ptr().wrapping_sub(1).cast(), positions) };
+        _mm_srli_epi32(pairs, 16)
+    }
+
+    /// Returns the nimbers of the positions given in the 64-bit lanes of `positions`, each in a 32-bit lane.
+    ///
+    /// # Safety
+    /// Each position must be in the range `1..nimbers.len()`.
+    #[cfg(feature = "wide-nimbers")]
+    #[inline] #[target_feature(enable = "avx2")]
+    unsafe fn gather(nimbers: &[Nimber], positions: __m256i) -> __m128i {
+        unsafe { _mm256_i64gather_epi32::<4>(nimbers.as_ptr().cast(), positions) }
+    }
+
+    /// Adds to `option_nimbers` the xors of `front[k]` and `back[back.len()-1-k]` for all `k`.
+    /// Both slices must be of the same length.
+    ///
+    /// # Safety
+    /// The processor must support AVX2.
+    #[target_feature(enable = "avx2")]
+    pub(super) unsafe fn add_mirrored_xors(option_nimbers: &mut [u64], front: &[Nimber], back: &[Nimber]) {
+        let len = front.len();
+        assert_eq!(back.len(), len);
+        let done = len / LANES * LANES;
+        let mut small = _mm256_setzero_si256();
+        for k in (0..done).step_by(LANES) {
+            // SAFETY: k+LANES <= len, so both loads read within the slices
+            let (f, b) = unsafe { (_mm256_loadu_si256(front.as_ptr().add(k).cast()), _mm256_loadu_si256(back.as_ptr().add(len - k - LANES).cast())) };
+            small = collect(option_nimbers, small, _mm256_xor_si256(f, reversed(b)));
+        }
+        merge(option_nimbers, small);
+        super::add_mirrored_xors_portable(option_nimbers, &front[done..], &back[..len - done]);
+    }
+
+    /// Adds to `option_nimbers` the nimbers of the splits `(i, after_take-i)` for `i` listed in `positions`
+    /// (all not exceeding `after_take`).
+    ///
+    /// # Safety
+    /// The processor must support AVX2.
+    #[target_feature(enable = "avx2")]
+    pub(super) unsafe fn add_gathered_xors(option_nimbers: &mut [u64], nimbers: &[Nimber], positions: &[usize], after_take: usize) {
+        if after_take >= nimbers.len() {
+            return super::add_gathered_xors_portable(option_nimbers, nimbers, positions, after_take);
+        }
+        let mut small = _mm256_setzero_si256();
+        let after_take_v = _mm256_set1_epi64x(after_take as i64);
+        let mut blocks = positions.chunks_exact(4);
+        for block in &mut blocks {
+            // SAFETY: the block consists of 4 positions, which occupy 32 bytes
+            let left = unsafe { _mm256_loadu_si256(block.as_ptr().cast()) };
+            let in_range = _mm256_and_si256(_mm256_cmpgt_epi64(left, _mm256_setzero_si256()), _mm256_cmpgt_epi64(after_take_v, left));
+            if _mm256_movemask_epi8(in_range) != -1 {  // the positions outside 1..after_take are handled (and checked) by the portable kernel
+                super::add_gathered_xors_portable(option_nimbers, nimbers, block, after_take);
+                continue;
+            }
+            let right = _mm256_sub_epi64(after_take_v, left);
+            // SAFETY: 0 < left < after_take < nimbers.len(), so 0 < right < after_take as well
+            let xors = unsafe { _mm_xor_si128(gather(nimbers, left), gather(nimbers, right)) };
+            small = collect_4(option_nimbers, small, _mm256_cvtepu32_epi64(xors));
+        }
+        merge(option_nimbers, small);
+        super::add_gathered_xors_portable(option_nimbers, nimbers, blocks.remainder(), after_take);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BitSet;
+
+    fn test_nimbers() -> Vec<Nimber> {
+        (0..5000u32).map(|i| (i.wrapping_mul(2654435761) >> 21) as Nimber).collect()
+    }
+
+    #[test]
+    fn test_mirrored_xors() {
+        let nimbers = test_nimbers();
+        let small_nimbers: Vec<Nimber> = nimbers.iter().map(|n| n & 31).collect();
+        for nimbers in [nimbers, small_nimbers] {
+            for (range, after_take) in [(1..2, 3), (1..40, 80), (3..37, 39), (100..2000, 4000), (0..0, 5)] {
+                let (mut expected, mut got, mut got_portable) = (vec![0u64; 1<<(16-6)], vec![0u64; 1<<(16-6)], vec![0u64; 1<<(16-6)]);
+                for i in range.clone() { expected.add_nimber(nimbers[i] ^ nimbers[after_take-i]); }
+                add_mirrored_xors(&mut got, &nimbers, range.clone(), after_take);
+                assert_eq!(got, expected);
+                add_mirrored_xors_portable(&mut got_portable, &nimbers[range.clone()], &nimbers[after_take+1-range.end .. after_take+1-range.start]);
+                assert_eq!(got_portable, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_gathered_and_blocked_xors() {
+        let nimbers = test_nimbers();
+        let small_nimbers: Vec<Nimber> = nimbers.iter().map(|n| n & 31).collect();
+        for nimbers in [&nimbers, &small_nimbers] {
+            // the second case includes positions 0 and after_take, the third one positions from the whole sequence
+            for (positions, after_take) in [((1..2400).filter(|i| i % 7 < 3).collect::<Vec<_>>(), 4999), ((0..40).collect(), 39), ((0..5000).step_by(3).collect(), 4999)] {
+                let (mut expected, mut got, mut got_portable) = (vec![0u64; 1<<(16-6)], vec![0u64; 1<<(16-6)], vec![0u64; 1<<(16-6)]);
+                for i in &positions { expected.add_nimber(nimbers[*i] ^ nimbers[after_take-i]); }
+                add_gathered_xors(&mut got, nimbers, &positions, after_take);
+                assert_eq!(got, expected);
+                add_gathered_xors_portable(&mut got_portable, nimbers, &positions, after_take);
+                assert_eq!(got_portable, expected);
+            }
+        }
+
+        let ranges = [(4999, 1..2500), (4996, 1..2499), (3000, 1..1501)];
+        let (mut expected, mut got) = (vec![0u64; 1<<(16-6)], vec![0u64; 1<<(16-6)]);
+        for (after_take, range) in &ranges {
+            for i in range.clone().filter(|i| (500..2100).contains(i)) { expected.add_nimber(nimbers[i] ^ nimbers[after_take-i]); }
+        }
+        add_blocked_xors(&mut got, &nimbers, &ranges, 500..2100);
+        assert_eq!(got, expected);
+    }
+}
@@ -2,18 +2,32 @@ mod set;
 mod game;
 mod solver;
 mod stats;
+mod sequence;
+mod parallel;
+mod kernels;
+mod analysis;
+mod packed;
 
 // solvers:
 mod naive;
 mod rcsplit;
 mod rc;
 mod rc2;
+mod misere;
+mod streaming;
+mod rcpacked;
 
-pub use set::BitSet;
-pub use game::{Game, BreakingMoveIterator};
+pub use set::{BitSet, Nimber};
+pub use game::{Game, BreakingMoveIterator, SplitIterator, SplitRule};
 pub use stats::{SolverEvent, SolverIterations};
+pub use analysis::{Move, position_nimber, winning_moves};
+pub use sequence::{checksum, checksum_of, write_nimbers, read_nimbers, save_nimbers, load_nimbers};
 
 pub use solver::Solver;
 pub use naive::NaiveSolver;
 pub use rc::RCSolver;
-pub use rc2::RC2Solver;
\ No newline at end of file
+pub use rc2::RC2Solver;
+pub use misere::{MisereSolver, Genus};
+pub use streaming::StreamingSolver;
+pub use packed::PackedNimbers;
+pub use rcpacked::PackedRCSolver;
\ No newline at end of file
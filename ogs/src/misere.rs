@@ -0,0 +1,244 @@
+use crate::stats::NimberStats;
+use crate::{Game, NaiveSolver, Nimber, Solver, SolverEvent};
+use std::collections::HashMap;
+use std::fmt::Display;
+
+/// Position of a sum of heaps of an octal (or hexadecimal) game and (optional) nim-heaps of sizes 1 and 2,
+/// used as a key of the misère solver memory.
+#[derive(Clone, PartialEq, Eq, Hash, Default)]
+struct MiserePosition {
+    /// Sorted sizes of the heaps of the octal game, only the heaps that have moves are included.
+    heaps: Vec<u16>,
+    /// Number of nim-heaps of size 1.
+    ones: u16,
+    /// Number of nim-heaps of size 2.
+    twos: u16
+}
+
+/// Genus of a position (see Conway, On Numbers and Games, chapter 12),
+/// i.e. its normal-play nimber and the misère Grundy values
+/// of the sums of the position with 0, 1, 2, ... nim-heaps of size 2.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Genus {
+    /// Normal play nimber.
+    pub nimber: Nimber,
+    /// `misere[k]` is the misère Grundy value of the sum of the position and `k` nim-heaps of size 2.
+    pub misere: Vec<Nimber>
+}
+
+impl Display for Genus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}^", self.nimber)?;
+        let separator = if self.misere.iter().any(|v| *v > 9) { "," } else { "" };
+        for (i, v) in self.misere.iter().enumerate() {
+            if i != 0 { f.write_str(separator)?; }
+            write!(f, "{}", v)?;
+        }
+        Ok(())
+    }
+}
+
+/// Calculates misère Grundy values of the positions (heaps) of an octal game.
+///
+/// The misère Grundy value of a position without moves is 1,
+/// and of any other position, the mex of the values of its options.
+/// Position is losing under misère play (i.e. when the player who makes the last move loses)
+/// iff its misère Grundy value is 0.
+///
+/// Since the values of sums cannot be obtained from the values of their components under misère play,
+/// the solver searches the sums of heaps that arise from breaking moves directly,
+/// and keeps the values of all the sums searched.
+/// Therefore, it is much slower than the normal play solvers and is practical only for small heaps.
+pub struct MisereSolver<S = ()> {
+    game: Game,
+    nimbers: Vec<Nimber>,
+    normal: NaiveSolver,
+    memory: HashMap<MiserePosition, Nimber>,
+    pub stats: S
+}
+
+impl<S: SolverEvent> Solver for MisereSolver<S> {
+    type Stats = S;
+
+    #[inline] fn stats(&self) -> &Self::Stats { &self.stats }
+    #[inline] fn nimbers(&self) -> &[Nimber] { &self.nimbers }
+    #[inline] fn game(&self) -> &Game { &self.game }
+    #[inline] fn capacity(&self) -> usize { self.nimbers.capacity() }
+
+    #[inline] fn with_stats(game: Game, stats: S) -> Self {
+        Self { normal: NaiveSolver::new(game.clone()), game, nimbers: Vec::new(), memory: HashMap::new(), stats }
+    }
+
+    #[inline] fn with_capacity_stats(game: Game, capacity: usize, stats: S) -> Self {
+        Self { normal: NaiveSolver::new(game.clone()), game, nimbers: Vec::with_capacity(capacity), memory: HashMap::new(), stats }
+    }
+
+    /// The given `nimbers` should be misère Grundy values.
+    #[inline] fn with_nimbers_stats(game: Game, nimbers: Vec<Nimber>, stats: S) -> Self {
+        Self { normal: NaiveSolver::new(game.clone()), game, nimbers, memory: HashMap::new(), stats }
+    }
+
+    fn print_nimber_stat_to(&self, f: &mut dyn std::io::Write) -> std::io::Result<()> {
+        let mut stats = NimberStats::default();
+        for n in &self.nimbers { stats.count(*n) }
+        writeln!(f, "{}", &stats)
+    }
+
+    /// Always returns `None`, as the theorem used to find periods of normal play nimbers
+    /// does not hold for misère Grundy values.
+    #[inline] fn period(&self) -> Option<(usize, usize)> { None }
+
+    /// Always returns `None`, as the theorem used to find arithmetic periods of normal play nimbers
+    /// does not hold for misère Grundy values.
+    #[inline] fn arithmetic_period(&self) -> Option<(usize, usize, Nimber)> { None }
+}
+
+impl<S: SolverEvent> MisereSolver<S> {
+    /// The largest heap whose misère Grundy value can be calculated, as the heap sizes are 16-bit.
+    pub const MAX_HEAP: usize = u16::MAX as usize;
+
+    /// Checks if heap of size `n` has any moves.
+    fn has_moves(&self, n: u16) -> bool {
+        let n = n as usize;
+        self.game.can_take_all(n) ||
+        self.game.taking.first().is_some_and(|t| *t < n) ||
+        self.game.breaking.first().is_some_and(|b| b + 2 <= n) ||
+        self.game.restricted_breaking_moves(n).next().is_some() ||
+        self.game.breaking_more.iter().enumerate().any(|(i, b)| b.first().is_some_and(|b| b + i + 3 <= n))
+    }
+
+    /// Appends to `heaps` these of the given `new_heaps` that have moves.
+    #[inline] fn push_heaps(&self, heaps: &mut Vec<u16>, new_heaps: &[u16]) {
+        heaps.extend(new_heaps.iter().copied().filter(|h| self.has_moves(*h)))
+    }
+
+    /// Returns the misère Grundy value of `position`, whose heaps are sorted.
+    fn nimber_of(&mut self, position: MiserePosition) -> Nimber {
+        if let Some(v) = self.memory.get(&position) { return *v; }
+        let mut options = Vec::new();
+        for i in 0..position.heaps.len() {
+            let n = position.heaps[i];
+            if i != 0 && position.heaps[i-1] == n { continue; } // same heap gives the same options
+            let with_heaps = |solver: &mut Self, new_heaps: &[u16]| {
+                let mut heaps = Vec::with_capacity(position.heaps.len() + 1);
+                heaps.extend_from_slice(&position.heaps[..i]);
+                heaps.extend_from_slice(&position.heaps[i+1..]);
+                solver.push_heaps(&mut heaps, new_heaps);
+                heaps.sort_unstable();
+                solver.nimber_of(MiserePosition { heaps, ..position })
+            };
+            if self.game.can_take_all(n as usize) {
+                options.push(with_heaps(self, &[]));
+            }
+            for t in 0..self.game.taking.len() {
+                let t = self.game.taking[t] as u16;
+                if t >= n { break }
+                options.push(with_heaps(self, &[n-t]));
+                self.stats.take_option();
+            }
+            let moves: Vec<_> = self.game.breaking_moves(n as usize).collect();
+            for (a, b) in moves {
+                options.push(with_heaps(self, &[a as u16, b as u16]));
+                self.stats.break_option();
+            }
+            let moves: Vec<_> = self.game.restricted_breaking_moves(n as usize).collect();
+            for (a, b) in moves {
+                options.push(with_heaps(self, &[a as u16, b as u16]));
+                self.stats.break_option();
+            }
+            let moves: Vec<_> = self.game.breaking_more_moves(n as usize).collect();
+            for heaps in moves {
+                options.push(with_heaps(self, &heaps.iter().map(|h| *h as u16).collect::<Vec<_>>()));
+                self.stats.break_option();
+            }
+        }
+        if position.ones != 0 {
+            options.push(self.nimber_of(MiserePosition { ones: position.ones-1, ..position.clone() }));
+        }
+        if position.twos != 0 {
+            options.push(self.nimber_of(MiserePosition { twos: position.twos-1, ..position.clone() }));
+            options.push(self.nimber_of(MiserePosition { twos: position.twos-1, ones: position.ones+1, heaps: position.heaps.clone() }));
+        }
+        let result = if options.is_empty() { 1 } else {
+            options.sort_unstable();
+            options.dedup();
+            options.iter().enumerate().find(|(i, v)| *i != **v as usize).map_or(options.len(), |(i, _)| i) as Nimber
+        };
+        self.memory.insert(position, result);
+        result
+    }
+
+    /// Returns the misère Grundy value of the sum of heaps of the given sizes.
+    pub fn misere_nimber_of_sum(&mut self, heaps: &[u16]) -> Nimber {
+        if let Some(max) = heaps.iter().max() { self.game.unroll(*max as usize); }
+        let mut sorted = Vec::with_capacity(heaps.len());
+        self.push_heaps(&mut sorted, heaps);
+        sorted.sort_unstable();
+        self.nimber_of(MiserePosition { heaps: sorted, ones: 0, twos: 0 })
+    }
+
+    /// Returns the genus of the heap of size `n`, with `terms` misère Grundy values.
+    /// The normal play nimbers are calculated by the naive method.
+    pub fn genus(&mut self, n: u16, terms: u16) -> Genus {
+        while self.normal.nimbers().len() <= n as usize {
+            self.normal.next().expect("normal play nimber exceeds the largest supported nimber");
+        }
+        self.game.unroll(n as usize);
+        let mut heaps = Vec::new();
+        self.push_heaps(&mut heaps, &[n]);
+        Genus {
+            nimber: self.normal.nimbers()[n as usize],
+            misere: (0..terms).map(|twos| self.nimber_of(MiserePosition { heaps: heaps.clone(), ones: 0, twos })).collect()
+        }
+    }
+}
+
+impl<S: SolverEvent> Iterator for MisereSolver<S> {
+    type Item = Nimber;
+
+    /// Returns `None` if the heap exceeds [`MisereSolver::MAX_HEAP`].
+    fn next(&mut self) -> Option<Self::Item> {
+        let n = u16::try_from(self.nimbers.len()).ok()?;  // heap sizes are 16-bit
+        let result = self.misere_nimber_of_sum(&[n]);
+        self.nimbers.push(result);
+        Some(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn check(game: &str, expected: &[Nimber]) {
+        let solver = MisereSolver::<()>::new(Game::from_str(game).unwrap());
+        assert_eq!(solver.take(expected.len()).collect::<Vec<_>>(), expected, "{}", game);
+    }
+
+    #[test]
+    fn misere_values() {
+        check("0.07", &[1, 1, 0, 0, 2, 1, 3, 0, 0, 1, 1, 3, 0, 2, 1, 1, 0, 0, 2, 1, 3, 0, 0, 1, 1]);
+        check("0.77", &[1, 0, 2, 3, 0, 1, 3, 2, 1, 0, 2, 4, 0, 1, 2, 5, 1, 6, 3, 2, 0, 6, 4, 5, 6]);
+        check("0.137", &[1, 0, 0, 2, 1, 3, 0, 0, 1, 1, 3, 0, 2, 1, 1, 0, 0, 2, 1, 3, 0, 0, 1, 1, 3]);
+        check("0.6", &[1, 1, 0, 2, 1, 0, 2, 1, 0, 2, 1, 0, 2, 1, 0, 2, 1, 0, 2, 1, 0, 2, 1, 0, 4]);
+        check("8.", &[1, 1, 1, 0, 0, 2]);
+    }
+
+    #[test]
+    fn nim_genus() {
+        let mut solver = MisereSolver::<()>::new(Game::from_str("0.333").unwrap());
+        assert_eq!(solver.genus(0, 3).to_string(), "0^120");
+        assert_eq!(solver.genus(1, 3).to_string(), "1^031");
+        assert_eq!(solver.genus(2, 2).to_string(), "2^20");
+        assert_eq!(solver.genus(3, 2).to_string(), "3^31");
+    }
+
+    #[test]
+    fn sums() {
+        let mut solver = MisereSolver::<()>::new(Game::from_str("0.333").unwrap());
+        assert_eq!(solver.misere_nimber_of_sum(&[1, 1]), 1);
+        assert_eq!(solver.misere_nimber_of_sum(&[1, 1, 1]), 0);
+        assert_eq!(solver.misere_nimber_of_sum(&[2, 3]), 1);
+        assert_eq!(solver.misere_nimber_of_sum(&[3, 3]), 0);
+    }
+}
@@ -1,12 +1,20 @@
 use crate::stats::NimberStats;
 use crate::Solver;
-use crate::Game;
+use crate::{Game, Nimber};
 use crate::BitSet;
+use crate::set::cleared_options;
 use crate::SolverEvent;
+use crate::parallel::add_breaking_options;
 
 pub struct NaiveSolver<S = ()> {
     game: Game,
-    nimbers: Vec<u16>,
+    nimbers: Vec<Nimber>,
+    /// The largest of the `nimbers`.
+    max_nimber: Nimber,
+    /// Option set reused by each calculation, cleared and sized by [`cleared_options`].
+    option_nimbers: Vec<u64>,
+    /// Number of threads used to consider the breaking moves of large heaps (1 by default).
+    pub threads: usize,
     pub stats: S
 }
 
@@ -14,18 +22,25 @@ impl<S: SolverEvent> Solver for NaiveSolver<S> {
     type Stats = S;
     
     #[inline] fn stats(&self) -> &Self::Stats { &self.stats }
-    #[inline] fn nimbers(&self) -> &[u16] { &self.nimbers }
+    #[inline] fn nimbers(&self) -> &[Nimber] { &self.nimbers }
     #[inline] fn game(&self) -> &Game { &self.game }
     #[inline] fn capacity(&self) -> usize { self.nimbers.capacity() }
 
     #[inline] fn with_stats(game: Game, stats: S) -> Self {
-        Self { game, nimbers: Vec::new(), stats }
+        Self { game, nimbers: Vec::new(), max_nimber: 0, option_nimbers: Vec::new(), threads: 1, stats }
     }
 
     #[inline] fn with_capacity_stats(game: Game, capacity: usize, stats: S) -> Self {
-        Self { game, nimbers: Vec::with_capacity(capacity), stats }
+        Self { game, nimbers: Vec::with_capacity(capacity), max_nimber: 0, option_nimbers: Vec::new(), threads: 1, stats }
+    }
+
+    #[inline] fn with_nimbers_stats(game: Game, nimbers: Vec<Nimber>, stats: S) -> Self {
+        let max_nimber = nimbers.iter().copied().max().unwrap_or(0);
+        Self { game, nimbers, max_nimber, option_nimbers: Vec::new(), threads: 1, stats }
     }
     
+    #[inline] fn set_threads(&mut self, threads: usize) { self.threads = threads.max(1); }
+
     fn print_nimber_stat_to(&self, f: &mut dyn std::io::Write) -> std::io::Result<()> {
         let mut stats = NimberStats::default();
         for n in &self.nimbers { stats.count(*n) }
@@ -33,23 +48,28 @@ impl<S: SolverEvent> Solver for NaiveSolver<S> {
     }
 }
 
+impl<S> NaiveSolver<S> {
+    /// Returns the solver that uses the given number of `threads` to consider the breaking moves of large heaps.
+    #[inline] pub fn with_threads(mut self, threads: usize) -> Self {
+        self.threads = threads.max(1);
+        self
+    }
+}
+
 impl<S: SolverEvent> Iterator for NaiveSolver<S> {
-    type Item = u16;
+    type Item = Nimber;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let mut option_nimbers = [0u64; 1<<(16-6)]; // 2**16 bits
+        let option_nimbers = cleared_options(&mut self.option_nimbers, self.max_nimber);
         let n = self.nimbers.len();
-        self.game.consider_taking(&self.nimbers, &mut option_nimbers, &mut self.stats);
-        for b in &self.game.breaking {
-            let b = *b as usize;
-            if b >= n { break }
-            let after_take = n - b;
-            for i in 1 .. after_take/2 + 1 {
-                option_nimbers.add_nimber(self.nimbers[i] ^ self.nimbers[after_take-i]);
-                self.stats.break_option();
-            }
-        }
-        let result = option_nimbers.mex();
+        self.game.unroll(n);
+        self.game.consider_taking(&self.nimbers, option_nimbers, &mut self.stats);
+        self.game.consider_breaking_more(&self.nimbers, option_nimbers, &mut self.stats);
+        self.game.consider_breaking_restricted(&self.nimbers, option_nimbers, &mut self.stats);
+        let options = add_breaking_options(&self.nimbers, &self.game.breaking, n, option_nimbers, self.threads);
+        self.stats.break_options(options);
+        let result = option_nimbers.checked_mex()?;
+        self.max_nimber = self.max_nimber.max(result);
         self.nimbers.push(result);
         Some(result)
     }
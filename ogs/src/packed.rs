@@ -0,0 +1,237 @@
+use crate::Nimber;
+
+/// Pushes the `value` to the `vec`, growing its capacity by an eighth (instead of doubling it) when it is full,
+/// which bounds the memory allocated but not used.
+#[inline(always)] fn push_compact<T>(vec: &mut Vec<T>, value: T) {
+    if vec.len() == vec.capacity() { vec.reserve_exact(vec.len() / 8 + 8); }
+    vec.push(value);
+}
+
+/// Sequence of unsigned integers, `width` bits each, packed (the lowest bits first) into words.
+/// The width grows (and all the values are repacked) when a value that does not fit is pushed.
+#[derive(Clone)]
+struct PackedInts {
+    /// Packed values, in at least `len * width / 64 + 2` words, so that the value of any index up to `len`
+    /// (which is 0 for `len`) can be read from two consecutive words without branching.
+    words: Vec<u64>,
+    width: u32,
+    len: usize
+}
+
+impl Default for PackedInts {
+    #[inline] fn default() -> Self { Self::with_width(1) }
+}
+
+impl PackedInts {
+    /// Returns the empty sequence of `width`-bit values.
+    #[inline] fn with_width(width: u32) -> Self { Self { words: vec![0; 2], width, len: 0 } }
+
+    /// Returns the value at the given `index`, which must not exceed `len`.
+    #[inline(always)] fn get(&self, index: usize) -> u64 {
+        let bit = index * self.width as usize;
+        let (word, offset) = (bit / 64, bit % 64);
+        let value = (self.words[word] >> offset) | ((self.words[word + 1] << 1) << (63 - offset));
+        value & ((1 << self.width) - 1)
+    }
+
+    /// Writes to `out` the values of the indices starting from `first`, mapped by `map`.
+    /// The indices must not exceed `len`.
+    #[inline(always)] fn unpack<T>(&self, first: usize, out: &mut [T], mut map: impl FnMut(u64) -> T) {
+        let (width, mask) = (self.width as usize, (1u64 << self.width) - 1);
+        let mut bit = first * width;
+        for out in out {
+            let (word, offset) = (bit / 64, bit % 64);
+            *out = map(((self.words[word] >> offset) | ((self.words[word + 1] << 1) << (63 - offset))) & mask);
+            bit += width;
+        }
+    }
+
+    /// Writes the `value`, which must fit in `width` bits, at the end.
+    #[inline(always)] fn push_fitting(&mut self, value: u64) {
+        let bit = self.len * self.width as usize;
+        let (word, offset) = (bit / 64, bit % 64);
+        while self.words.len() < (bit + self.width as usize) / 64 + 2 { push_compact(&mut self.words, 0); }
+        self.words[word] |= value << offset;
+        self.words[word + 1] |= (value >> 1) >> (63 - offset);
+        self.len += 1;
+    }
+
+    /// Appends the `value`, widening all the values before if needed.
+    #[inline] fn push(&mut self, value: u64) {
+        if value >> self.width != 0 { self.widen(u64::BITS - value.leading_zeros()); }
+        self.push_fitting(value);
+    }
+
+    /// Repacks all the values to `width` bits each.
+    fn widen(&mut self, width: u32) {
+        let mut wider = Self::with_width(width);
+        wider.words.reserve_exact((self.len * width as usize).div_ceil(64));
+        for index in 0..self.len { wider.push_fitting(self.get(index)); }
+        *self = wider;
+    }
+
+    /// Returns the number of bytes occupied by the values.
+    #[inline] fn size_bytes(&self) -> usize { self.words.capacity() * 8 }
+}
+
+/// Number of words of the rare bitmap covered by each entry of the block-level rank index.
+const RANK_BLOCK_WORDS: usize = 8;
+
+/// Compact sequence of nimbers, which exploits the structure of sparse space
+/// (see the R/C split of the RC method).
+///
+/// The common nimbers are stored as bit-packed codes of a dictionary,
+/// using as few bits as needed to distinguish the common nimbers met so far (and the escape code),
+/// while the rare nimbers (of R) are stored explicitly, packed with as few bits as the largest of them needs.
+/// The positions of the rare nimbers are marked in a bitmap, with a rank index that
+/// maps a position to the index of its nimber among the rare ones in constant time.
+/// Thus the sequence takes less memory than `Vec<Nimber>`, the fewer distinct common nimbers and the fewer rare nimbers, the less.
+#[derive(Clone, Default)]
+pub struct PackedNimbers {
+    /// Codes of the nimbers of all positions.
+    codes: PackedInts,
+    /// Rare nimbers, in the order of their positions.
+    rare_nimbers: PackedInts,
+    /// Bit `position % 64` of `rare_bits[position / 64]` is set if the nimber of `position` is rare.
+    rare_bits: Vec<u64>,
+    /// `rank_blocks[b]` is the number of rare nimbers before the word `b * RANK_BLOCK_WORDS` of `rare_bits`.
+    rank_blocks: Vec<u64>,
+    /// `rank_words[w]` is the number of rare nimbers before the word `w` of `rare_bits`, counted from the beginning of its block.
+    rank_words: Vec<u16>,
+    /// `dictionary[code-1]` is the nimber of the `code`. Code 0 marks the rare nimbers.
+    dictionary: Vec<Nimber>,
+    /// `code_of[nimber]` is the code of the `nimber`, or 0 if it has no code.
+    code_of: Vec<u16>
+}
+
+impl PackedNimbers {
+    /// Returns the number of nimbers in the sequence.
+    #[inline] pub fn len(&self) -> usize { self.codes.len }
+
+    /// Returns whether the sequence is empty.
+    #[inline] pub fn is_empty(&self) -> bool { self.len() == 0 }
+
+    /// Returns the number of the rare nimbers, which are stored explicitly.
+    #[inline] pub fn rare(&self) -> usize { self.rare_nimbers.len }
+
+    /// Returns the (approximate) number of bytes occupied by the sequence.
+    pub fn size_bytes(&self) -> usize {
+        self.codes.size_bytes() + self.rare_nimbers.size_bytes() + self.rare_bits.capacity() * 8 + self.rank_blocks.capacity() * 8
+            + self.rank_words.capacity() * 2 + self.dictionary.capacity() * std::mem::size_of::<Nimber>() + self.code_of.capacity() * 2
+    }
+
+    /// Returns the number of rare nimbers before the given `position`.
+    #[inline(always)] fn rank(&self, position: usize) -> usize {
+        let word = position / 64;
+        self.rank_blocks[word / RANK_BLOCK_WORDS] as usize + self.rank_words[word] as usize
+            + (self.rare_bits[word] & ((1u64 << (position % 64)) - 1)).count_ones() as usize
+    }
+
+    /// Returns the code of the `nimber`, adding it to the dictionary if needed.
+    /// Returns 0 if the dictionary is full.
+    fn code_of_common(&mut self, nimber: Nimber) -> u16 {
+        if let Some(code) = self.code_of.get(nimber as usize).filter(|c| **c != 0) { return *code; }
+        if self.dictionary.len() == u16::MAX as usize { return 0; }
+        self.dictionary.push(nimber);
+        let code = self.dictionary.len() as u16;
+        if self.code_of.len() <= nimber as usize { self.code_of.resize(nimber as usize + 1, 0); }
+        self.code_of[nimber as usize] = code;
+        code
+    }
+
+    /// Appends the `nimber` to the sequence. The nimber is stored explicitly if it is `rare`.
+    pub fn push(&mut self, nimber: Nimber, rare: bool) {
+        let position = self.len();
+        let word = position / 64;
+        if position.is_multiple_of(64) {
+            let rare = self.rare();
+            if word.is_multiple_of(RANK_BLOCK_WORDS) { push_compact(&mut self.rank_blocks, rare as u64); }
+            push_compact(&mut self.rank_words, (rare - self.rank_blocks[word / RANK_BLOCK_WORDS] as usize) as u16);
+            push_compact(&mut self.rare_bits, 0);
+        }
+        let code = if rare { 0 } else { self.code_of_common(nimber) };
+        if code == 0 {
+            self.rare_bits[word] |= 1u64 << (position % 64);
+            self.rare_nimbers.push(nimber as u64);
+        }
+        self.codes.push(code as u64);
+    }
+
+    /// Stores the sequence anew, keeping explicitly the nimbers that are `rare` now
+    /// (which is useful when the classification of the nimbers has changed since they were pushed).
+    pub fn repack(&mut self, rare: impl Fn(Nimber) -> bool) {
+        let mut repacked = Self::default();
+        for nimber in self.iter() { repacked.push(nimber, rare(nimber)); }
+        *self = repacked;
+    }
+
+    /// Returns the nimber of the given `position`.
+    #[inline] pub fn get(&self, position: usize) -> Nimber {
+        match self.codes.get(position) {
+            0 => self.rare_nimbers.get(self.rank(position)) as Nimber,
+            code => self.dictionary[code as usize - 1]
+        }
+    }
+
+    /// Writes to `out` the nimbers of the positions starting from `first`.
+    pub fn decode(&self, first: usize, out: &mut [Nimber]) {
+        let mut rare = self.rank(first);
+        self.codes.unpack(first, out, |code| match code {
+            0 => { rare += 1; self.rare_nimbers.get(rare - 1) as Nimber },
+            code => self.dictionary[code as usize - 1]
+        });
+    }
+
+    /// Returns iterator over the positions of the rare nimbers (in increasing order), together with the nimbers.
+    pub fn rare_iter(&self) -> impl Iterator<Item = (usize, Nimber)> + '_ {
+        let (mut word, mut bits, mut rare) = (0, self.rare_bits.first().copied().unwrap_or(0), 0);
+        std::iter::from_fn(move || {
+            while bits == 0 {
+                word += 1;
+                bits = *self.rare_bits.get(word)?;
+            }
+            let position = word * 64 + bits.trailing_zeros() as usize;
+            bits &= bits - 1;
+            rare += 1;
+            Some((position, self.rare_nimbers.get(rare - 1) as Nimber))
+        })
+    }
+
+    /// Returns iterator over the nimbers of the sequence.
+    pub fn iter(&self) -> impl Iterator<Item = Nimber> + '_ {
+        let mut rare = 0;
+        (0..self.len()).map(move |position| match self.codes.get(position) {
+            0 => { rare += 1; self.rare_nimbers.get(rare - 1) as Nimber },
+            code => self.dictionary[code as usize - 1]
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_packed_nimbers() {
+        let mut packed = PackedNimbers::default();
+        let nimbers: Vec<Nimber> = (0..5000u32).map(|i| if i % 97 == 0 { 1000 + (i % 7) as Nimber } else { ((i * i) % 37) as Nimber }).collect();
+        for (i, n) in nimbers.iter().enumerate() {
+            packed.push(*n, i % 97 == 0);
+        }
+        assert_eq!(packed.len(), nimbers.len());
+        assert_eq!(packed.rare(), nimbers.len().div_ceil(97));
+        assert_eq!(packed.codes.width, 5);
+        assert_eq!(packed.rare_nimbers.width, 10);
+        for (i, n) in nimbers.iter().enumerate() { assert_eq!(packed.get(i), *n, "{i}"); }
+        assert_eq!(packed.iter().collect::<Vec<_>>(), nimbers);
+        let mut out = [0; 300];
+        packed.decode(1234, &mut out);
+        assert_eq!(out, nimbers[1234..1534]);
+        assert!(packed.size_bytes() < nimbers.len() * std::mem::size_of::<Nimber>() / 2);
+        packed.repack(|n| n >= 30);
+        assert_eq!(packed.rare(), nimbers.iter().filter(|n| **n >= 30).count());
+        assert_eq!(packed.iter().collect::<Vec<_>>(), nimbers);
+        for (i, n) in nimbers.iter().enumerate() { assert_eq!(packed.get(i), *n, "{i}"); }
+        assert!(packed.rare_iter().eq(nimbers.iter().copied().enumerate().filter(|(_, n)| *n >= 30)));
+    }
+}
@@ -0,0 +1,126 @@
+use crate::kernels::{add_blocked_xors, add_gathered_xors};
+use crate::Nimber;
+
+/// Minimal number of options considered by each thread, which makes the use of the thread worthwhile.
+/// It is small in tests, to make them use many threads.
+const MIN_OPTIONS_PER_THREAD: usize = if cfg!(test) { 1 << 5 } else { 1 << 15 };
+
+/// Returns the number of threads (at most `threads`) worth to use to consider `options` options.
+#[inline] fn threads_for(threads: usize, options: usize) -> usize {
+    threads.min(options / MIN_OPTIONS_PER_THREAD).max(1)
+}
+
+/// Returns `range` of indices divided into `parts` and the `part`-th of the results.
+#[inline] fn part_of(range: std::ops::Range<usize>, part: usize, parts: usize) -> std::ops::Range<usize> {
+    let len = range.len();
+    range.start + len * part / parts .. range.start + len * (part+1) / parts
+}
+
+/// Calls `consider` with option bitset and the `thread`-th of `threads` parts of the work,
+/// in separate threads if `threads > 1`. Merges the option bitsets into `option_nimbers`.
+fn in_parallel<F>(threads: usize, option_nimbers: &mut [u64], consider: F)
+    where F: Fn(&mut [u64], usize) + Sync
+{
+    if threads <= 1 {
+        consider(option_nimbers, 0);
+        return;
+    }
+    let (consider, words) = (&consider, option_nimbers.len());
+    let partial = std::thread::scope(|s| {
+        let handles: Vec<_> = (1..threads).map(|thread| s.spawn(move || {
+            let mut option_nimbers = vec![0u64; words];
+            consider(&mut option_nimbers, thread);
+            option_nimbers
+        })).collect();
+        consider(option_nimbers, 0);
+        handles.into_iter().map(|h| h.join().unwrap()).collect::<Vec<_>>()
+    });
+    for p in partial {
+        for (o, p) in option_nimbers.iter_mut().zip(p.iter()) { *o |= *p; }
+    }
+}
+
+/// Adds to `option_nimbers` the nimbers of all options of the heap of `n` tokens,
+/// which are obtained by the `breaking` moves (each leaving two non-empty heaps),
+/// using up to `threads` threads. Returns the number of options considered.
+///
+/// Each thread considers a part of the smaller heap sizes, for all breaking moves, in cache-friendly blocks.
+pub(crate) fn add_breaking_options(nimbers: &[Nimber], breaking: &[usize], n: usize, option_nimbers: &mut [u64], threads: usize) -> usize {
+    let ranges: Vec<_> = breaking.iter()
+        .copied().take_while(|b| *b < n)
+        .map(|b| (n - b, 1 .. (n - b)/2 + 1)).collect();
+    let options = ranges.iter().map(|(_, r)| r.len()).sum();
+    let threads = threads_for(threads, options);
+    let smaller = 1 .. ranges.first().map_or(1, |(_, r)| r.end);
+    in_parallel(threads, option_nimbers, |option_nimbers, thread| {
+        add_blocked_xors(option_nimbers, nimbers, &ranges, part_of(smaller.clone(), thread, threads));
+    });
+    options
+}
+
+/// Adds to `option_nimbers` the nimbers of the options of the heap of `n` tokens,
+/// which are obtained by the `breaking` moves that leave a heap whose size is listed in (sorted) `r_positions`,
+/// using up to `threads` threads. Returns the number of options considered.
+pub(crate) fn add_r_breaking_options(nimbers: &[Nimber], breaking: &[usize], r_positions: &[usize], n: usize, option_nimbers: &mut [u64], threads: usize) -> usize {
+    let ranges: Vec<_> = breaking.iter()
+        .copied().take_while(|b| b+1 < n)
+        .map(|b| (n - b, 0 .. r_positions.partition_point(|i| *i < n - b))).collect();
+    let options = ranges.iter().map(|(_, r)| r.len()).sum();
+    let threads = threads_for(threads, options);
+    in_parallel(threads, option_nimbers, |option_nimbers, thread| {
+        for (after_take, range) in &ranges {
+            add_gathered_xors(option_nimbers, nimbers, &r_positions[part_of(range.clone(), thread, threads)], *after_take);
+        }
+    });
+    options
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Solver;
+
+    #[test]
+    fn test_part_of() {
+        let parts: Vec<_> = (0..3).map(|p| part_of(2..12, p, 3)).collect();
+        assert_eq!(parts, [2..5, 5..8, 8..12]);
+        assert_eq!(part_of(0..1, 1, 2), 0..1);
+    }
+
+    #[test]
+    fn test_parallel_breaking_options() {
+        let nimbers: Vec<Nimber> = (0..2_000u32).map(|i| (i.wrapping_mul(2654435761) >> 20) as Nimber).collect();
+        let n = nimbers.len();
+        let breaking = [0, 2, 3];
+        let r_positions: Vec<usize> = (1..n).filter(|i| i % 3 == 0).collect();
+        let mut serial = vec![0u64; 1<<(16-6)];
+        let mut parallel = vec![0u64; 1<<(16-6)];
+        assert_eq!(add_breaking_options(&nimbers, &breaking, n, &mut serial, 1),
+                   add_breaking_options(&nimbers, &breaking, n, &mut parallel, 4));
+        assert_eq!(serial, parallel);
+        let (mut serial, mut parallel) = (vec![0u64; 1<<(16-6)], vec![0u64; 1<<(16-6)]);
+        assert_eq!(add_r_breaking_options(&nimbers, &breaking, &r_positions, n, &mut serial, 1),
+                   add_r_breaking_options(&nimbers, &breaking, &r_positions, n, &mut parallel, 4));
+        assert_eq!(serial, parallel);
+    }
+
+    fn naive(game: &crate::Game, threads: usize) -> Vec<Nimber> {
+        crate::NaiveSolver::<()>::new(game.clone()).with_threads(threads).take(1000).collect()
+    }
+
+    fn rc<const DYNAMIC_REBUILD: bool>(game: &crate::Game) -> Vec<Nimber> {
+        crate::RCSolver::<DYNAMIC_REBUILD>::new(game.clone()).with_threads(3).take(1000).collect()
+    }
+
+    #[test]
+    fn test_parallel_solvers() {
+        use std::str::FromStr;
+        for game in ["0.07", "0.137", "4.F", "0.3+e0,2+o1", "0.6+u1"] {
+            let game = crate::Game::from_str(game).unwrap();
+            let nimbers = naive(&game, 1);
+            assert_eq!(naive(&game, 3), nimbers);
+            assert_eq!(rc::<true>(&game), nimbers);
+            assert_eq!(rc::<false>(&game), nimbers);
+        }
+    }
+}
@@ -1,15 +1,25 @@
 use crate::rcsplit::RCSplit;
 use crate::stats::NimberStats;
-use crate::Game;
+use crate::{Game, Nimber};
 use crate::BitSet;
+use crate::set::cleared_options;
 use crate::Solver;
 use crate::SolverEvent;
+use crate::parallel::{add_breaking_options, add_r_breaking_options};
+use crate::kernels::add_mirrored_xors;
+
+/// Number of breaking options considered between the checks whether the nimber being calculated is in C.
+pub(crate) const EARLY_EXIT_CHUNK: usize = 1 << 8;
 
 pub struct RCSolver<const DYNAMIC_REBUILD: bool = true, S = ()> {
     game: Game,
-    nimbers: Vec<u16>,
+    nimbers: Vec<Nimber>,
     nimber_num: NimberStats,
     split: RCSplit,
+    /// Option set reused by each calculation, cleared and sized by [`cleared_options`].
+    option_nimbers: Vec<u64>,
+    /// Number of threads used to consider the breaking moves of large heaps (1 by default).
+    pub threads: usize,
     //nimbers_by_num: HashMap<u32, HashSet<u16>>,
     pub stats: S
 }
@@ -18,55 +28,111 @@ impl<const DYNAMIC_REBUILD: bool, S: SolverEvent> Solver for RCSolver<DYNAMIC_RE
     type Stats = S;
     
     #[inline] fn stats(&self) -> &Self::Stats { &self.stats }
-    #[inline] fn nimbers(&self) -> &[u16] { &self.nimbers }
+    #[inline] fn nimbers(&self) -> &[Nimber] { &self.nimbers }
     #[inline] fn game(&self) -> &Game { &self.game }
     #[inline] fn capacity(&self) -> usize { self.nimbers.capacity() }
 
     #[inline] fn with_stats(game: Game, stats: S) -> Self {
-        Self { game, nimbers: Vec::new(), nimber_num: Default::default(), /*nimbers_by_num: Default::default(),*/ stats, split: Default::default() }
+        Self { game, nimbers: Vec::new(), nimber_num: Default::default(), /*nimbers_by_num: Default::default(),*/ option_nimbers: Vec::new(), stats, split: Default::default(), threads: 1 }
     }
 
     #[inline] fn with_capacity_stats(game: Game, capacity: usize, stats: S) -> Self {
-        Self { game, nimbers: Vec::with_capacity(capacity), nimber_num: Default::default(), /*nimbers_by_num: Default::default(),*/ stats, split: Default::default() }
+        Self { game, nimbers: Vec::with_capacity(capacity), nimber_num: Default::default(), /*nimbers_by_num: Default::default(),*/ option_nimbers: Vec::new(), stats, split: Default::default(), threads: 1 }
+    }
+
+    /// Rebuilds the R/C split for the given `nimbers`.
+    fn with_nimbers_stats(game: Game, nimbers: Vec<Nimber>, mut stats: S) -> Self {
+        let nimber_num = NimberStats::of(nimbers.iter().copied());
+        let mut split = RCSplit::default();
+        if !nimbers.is_empty() { split.rebuild(&nimber_num, &nimbers, &mut stats); }
+        Self { game, nimbers, nimber_num, option_nimbers: Vec::new(), stats, split, threads: 1 }
     }
     
+    #[inline] fn set_threads(&mut self, threads: usize) { self.threads = threads.max(1); }
+
     fn print_nimber_stat_to(&self, f: &mut dyn std::io::Write) -> std::io::Result<()> {
         writeln!(f, "{}", self.nimber_num)?;
         writeln!(f, "{}", self.split)
     }
 }
 
+impl<const DYNAMIC_REBUILD: bool, S> RCSolver<DYNAMIC_REBUILD, S> {
+    /// Returns the solver that uses the given number of `threads` to consider the breaking moves of large heaps.
+    #[inline] pub fn with_threads(mut self, threads: usize) -> Self {
+        self.threads = threads.max(1);
+        self
+    }
+}
+
 impl<const DYNAMIC_REBUILD: bool, S: SolverEvent> Iterator for RCSolver<DYNAMIC_REBUILD, S> {
-    type Item = u16;
+    type Item = Nimber;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let mut option_nimbers = [0u64; 1<<(16-6)]; // 2**16 bits
+        let option_nimbers = cleared_options(&mut self.option_nimbers, self.nimber_num.max);
         let n = self.nimbers.len();
-        self.game.consider_taking(&self.nimbers, &mut option_nimbers, &mut self.stats);
-        for b in &self.game.breaking {
-            let b = *b as usize;
-            if b+1 >= n { break }
-            let after_take = n - b;
-            for i in &self.split.r_positions {
-                if *i >= after_take { break; }
-                option_nimbers.add_nimber(self.nimbers[*i] ^ self.nimbers[after_take-i]);
-                self.stats.break_option();
-            }
-        }
-        let mut result = option_nimbers.mex();
-        if !self.split.in_c(result) {
-            'outer: for b in &self.game.breaking {
+        self.game.unroll(n);
+        self.game.consider_taking(&self.nimbers, option_nimbers, &mut self.stats);
+        self.game.consider_breaking_more(&self.nimbers, option_nimbers, &mut self.stats);
+        let options = add_r_breaking_options(&self.nimbers, &self.game.breaking, &self.split.r_positions, n, option_nimbers, self.threads);
+        self.stats.break_options(options);
+        // the nimber of any split into two heaps from C is not in C, so it is enough to consider the splits with heaps from R
+        for (rule, breaking) in self.game.restricted_breakings() {
+            for b in breaking {
                 let b = *b as usize;
                 if b+1 >= n { break }
                 let after_take = n - b;
-                for i in 1 .. after_take/2 + 1 {
-                    let option_nimber = self.nimbers[i] ^ self.nimbers[after_take-i];
-                    option_nimbers.add_nimber(option_nimber);
+                for i in &self.split.r_positions {
+                    if *i >= after_take { break; }
+                    if !rule.allows(*i, after_take-i) { continue; }
+                    option_nimbers.add_nimber(self.nimbers[*i] ^ self.nimbers[after_take-i]);
                     self.stats.break_option();
-                    if result == option_nimber {
-                        result = option_nimbers.mex();
-                        if self.split.in_c(result) {
-                            break 'outer;
+                }
+            }
+        }
+        let mut result = option_nimbers.checked_mex()?;
+        if !self.split.in_c(result) {
+            if self.threads > 1 {
+                // all the options are considered (without early exit), but in parallel
+                let options = add_breaking_options(&self.nimbers, &self.game.breaking, n, option_nimbers, self.threads);
+                self.stats.break_options(options);
+                result = option_nimbers.checked_mex()?;
+            } else {
+                // the options are considered in chunks, checking after each whether the result is in C
+                'outer: for b in &self.game.breaking {
+                    let b = *b;
+                    if b+1 >= n { break }
+                    let after_take = n - b;
+                    let end = after_take/2 + 1;
+                    let mut i = 1;
+                    while i < end {
+                        let chunk_end = (i + EARLY_EXIT_CHUNK).min(end);
+                        add_mirrored_xors(option_nimbers, &self.nimbers, i..chunk_end, after_take);
+                        self.stats.break_options(chunk_end - i);
+                        i = chunk_end;
+                        if option_nimbers.contain_nimber(result) {
+                            result = option_nimbers.checked_mex()?;
+                            if self.split.in_c(result) {
+                                break 'outer;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        if !self.split.in_c(result) {
+            'outer: for (rule, breaking) in self.game.restricted_breakings() {
+                for b in breaking {
+                    let b = *b as usize;
+                    if b+1 >= n { break }
+                    for (i, j) in rule.splits(n - b) {
+                        let option_nimber = self.nimbers[i] ^ self.nimbers[j];
+                        option_nimbers.add_nimber(option_nimber);
+                        self.stats.break_option();
+                        if result == option_nimber {
+                            result = option_nimbers.checked_mex()?;
+                            if self.split.in_c(result) {
+                                break 'outer;
+                            }
                         }
                     }
                 }
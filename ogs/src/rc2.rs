@@ -1,34 +1,56 @@
 use crate::{Solver, SolverEvent};
-use crate::{Game, BreakingMoveIterator};
+use crate::{Game, BreakingMoveIterator, Nimber};
 use crate::rcsplit::RCSplit;
 use crate::stats::NimberStats;
 use crate::BitSet;
+use crate::set::cleared_options;
+
+/// The largest nimber supported, as the nimbers are stored along with the parities of their positions, in a single [`Nimber`].
+const MAX_NIMBER: Nimber = Nimber::MAX >> 1;
 
 pub struct RC2Solver<const DYNAMIC_REBUILD: bool = true, S = ()> {
     game: Game,
-    breaking: [Vec<u8>; 2], // breaking moves splitted to even and odd
-    nimbers: Vec<u16>,
+    breaking: [Vec<usize>; 2], // breaking moves splitted to even and odd
+    nimbers: Vec<Nimber>,
     nimber_num: NimberStats,
     split: [RCSplit; 2],
+    /// Option set reused by each calculation, cleared and sized by [`cleared_options`].
+    option_nimbers: Vec<u64>,
     pub stats: S
 }
 
 impl<const DYNAMIC_REBUILD: bool, S: SolverEvent> Solver for RC2Solver<DYNAMIC_REBUILD, S> {   
     type Stats = S;
     
+    const MAX_NIMBER: Nimber = MAX_NIMBER;
+
     #[inline] fn stats(&self) -> &Self::Stats { &self.stats }
-    #[inline] fn nimbers(&self) -> &[u16] { &self.nimbers }
+    #[inline] fn nimbers(&self) -> &[Nimber] { &self.nimbers }
     #[inline] fn game(&self) -> &Game { &self.game }
     #[inline] fn capacity(&self) -> usize { self.nimbers.capacity() }
 
     #[inline] fn with_stats(game: Game, stats: S) -> Self {
         let breaking = Self::split_breaking_moves(&game);
-        Self { game, breaking, nimbers: Vec::new(), nimber_num: Default::default(), stats, split: [RCSplit::new(0), RCSplit::new(1)] }
+        Self { game, breaking, nimbers: Vec::new(), nimber_num: Default::default(), option_nimbers: Vec::new(), stats, split: [RCSplit::new(0), RCSplit::new(1)] }
     }
 
     #[inline] fn with_capacity_stats(game: Game, capacity: usize, stats: S) -> Self {
         let breaking = Self::split_breaking_moves(&game);
-        Self { game, breaking, nimbers: Vec::with_capacity(capacity), nimber_num: Default::default(), stats, split: [RCSplit::new(0), RCSplit::new(1)] }
+        Self { game, breaking, nimbers: Vec::with_capacity(capacity), nimber_num: Default::default(), option_nimbers: Vec::new(), stats, split: [RCSplit::new(0), RCSplit::new(1)] }
+    }
+
+    /// Rebuilds the R/C splits for the given `nimbers`.
+    fn with_nimbers_stats(game: Game, nimbers: Vec<Nimber>, mut stats: S) -> Self {
+        assert!(nimbers.iter().all(|n| *n <= MAX_NIMBER), "RC2 solver supports nimbers up to {MAX_NIMBER}");
+        let breaking = Self::split_breaking_moves(&game);
+        let nimber_num = NimberStats::of(nimbers.iter().enumerate().map(|(p, n)| (n << 1) | (p as Nimber & 1)));
+        let mut split = [RCSplit::new(0), RCSplit::new(1)];
+        if !nimbers.is_empty() {
+            for d in [0, 1] {
+                if !breaking[d].is_empty() || !game.is_code_finite() { split[d].rebuild_d(&nimber_num, &nimbers, d as Nimber, &mut stats); }
+            }
+        }
+        Self { game, breaking, nimbers, nimber_num, option_nimbers: Vec::new(), stats, split }
     }
 
     fn print_nimber_stat_to(&self, f: &mut dyn std::io::Write) -> std::io::Result<()> {
@@ -40,16 +62,28 @@ impl<const DYNAMIC_REBUILD: bool, S: SolverEvent> Solver for RC2Solver<DYNAMIC_R
 }
 
 impl<const DYNAMIC_REBUILD: bool, S> RC2Solver<DYNAMIC_REBUILD, S> {
-    fn split_breaking_moves(game: &Game) -> [Vec<u8>; 2] {
-        let mut result = [Vec::<u8>::new(), Vec::<u8>::new()];
+    fn split_breaking_moves(game: &Game) -> [Vec<usize>; 2] {
+        let mut result = [Vec::<usize>::new(), Vec::<usize>::new()];
         for m in game.breaking.iter().copied() {
-            result[(m & 1) as usize].push(m);
+            result[m & 1].push(m);
         }
         result
+    }
+
+    /// Returns whether R/C split `d` should be maintained, i.e. the game has (or can get after unrolling) breaking moves of parity `d`.
+    #[inline] fn uses_split(&self, d: usize) -> bool {
+        !self.breaking[d].is_empty() || !self.game.is_code_finite()
     }   
 }
 
-#[inline(always)] fn in_r<const D: u16>(breaking: &[Vec<u8>; 2], split: &mut [RCSplit; 2], nim_pos: u16) -> bool {
+/// Returns the smallest nimber not included in `option_nimbers`, along with the parity `nd` of the position,
+/// or `None` if it exceeds [`MAX_NIMBER`].
+#[inline(always)] fn mex_d(option_nimbers: &[u64], nd: Nimber) -> Option<Nimber> {
+    let mex = option_nimbers.checked_mex().filter(|m| *m <= MAX_NIMBER)?;
+    Some((mex << 1) | nd)
+}
+
+#[inline(always)] fn in_r<const D: Nimber>(breaking: &[Vec<usize>; 2], split: &mut [RCSplit; 2], nim_pos: Nimber) -> bool {
     if breaking[D as usize].is_empty() {
         false   // R does not exist so nothing is inside, R_positions is empty
     } else {
@@ -58,15 +92,22 @@ impl<const DYNAMIC_REBUILD: bool, S> RC2Solver<DYNAMIC_REBUILD, S> {
 }
 
 impl<const DYNAMIC_REBUILD: bool, S: SolverEvent> Iterator for RC2Solver<DYNAMIC_REBUILD, S> {
-    type Item = u16;
+    type Item = Nimber;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let mut option_nimbers = [0u64; 1<<(16-6)]; // 2**16 bits
+        let option_nimbers = cleared_options(&mut self.option_nimbers, self.nimber_num.max >> 1);
         let n = self.nimbers.len();
-        self.game.consider_taking(&self.nimbers, &mut option_nimbers, &mut self.stats);
+        let unrolled_breaking = self.game.breaking.len();
+        self.game.unroll(n);
+        for m in self.game.breaking[unrolled_breaking..].iter().copied() {
+            self.breaking[m & 1].push(m);
+        }
+        self.game.consider_taking(&self.nimbers, option_nimbers, &mut self.stats);
+        self.game.consider_breaking_more(&self.nimbers, option_nimbers, &mut self.stats);
+        self.game.consider_breaking_restricted(&self.nimbers, option_nimbers, &mut self.stats);  // not accelerated by RC2
         for d in [0, 1] {
             for b in &self.breaking[d] {
-                let b = *b as usize;
+                let b = *b;
                 if b+1 >= n { break }
                 let after_take = n - b;
                 for i in &self.split[d].r_positions {
@@ -76,8 +117,8 @@ impl<const DYNAMIC_REBUILD: bool, S: SolverEvent> Iterator for RC2Solver<DYNAMIC
                 }
             }
         }
-        let nd = n as u16 & 1;
-        let mut result = (option_nimbers.mex() << 1) | nd;
+        let nd = n as Nimber & 1;
+        let mut result = mex_d(option_nimbers, nd)?;
         let mut to_check = [
             in_r::<0>(&self.breaking, &mut self.split, result),
             in_r::<1>(&self.breaking, &mut self.split, result)
@@ -93,7 +134,7 @@ impl<const DYNAMIC_REBUILD: bool, S: SolverEvent> Iterator for RC2Solver<DYNAMIC
                         let option_nimber = self.nimbers[a] ^ self.nimbers[b];
                         option_nimbers.add_nimber(option_nimber);
                         if (result>>1) == option_nimber {
-                            result = (option_nimbers.mex() << 1) | nd;
+                            result = mex_d(option_nimbers, nd)?;
                             to_check = [
                                 in_r::<0>(&self.breaking, &mut self.split, result),
                                 in_r::<1>(&self.breaking, &mut self.split, result)
@@ -107,21 +148,21 @@ impl<const DYNAMIC_REBUILD: bool, S: SolverEvent> Iterator for RC2Solver<DYNAMIC
         self.nimber_num.count(result);
         self.nimbers.push(result>>1);
         for d in [0, 1] {
-            if self.breaking[d].is_empty() { continue; }
+            if !self.uses_split(d) { continue; }
             if DYNAMIC_REBUILD {
                 if self.split[d].r.contain_nimber(result) {
                     if n != 0 { self.split[d].r_positions.push(n); }
                     if self.split[d].should_rebuild_d(result, &self.nimber_num) {
-                        self.split[d].update_d(&self.nimber_num, &self.nimbers, d as u16, &mut self.stats);
-                        //self.split[d].rebuild_d(&self.nimber_num, &self.nimbers, d as u16);
+                        self.split[d].update_d(&self.nimber_num, &self.nimbers, d as Nimber, &mut self.stats);
+                        //self.split[d].rebuild_d(&self.nimber_num, &self.nimbers, d as Nimber);
                     }
-                    //self.split[d].rebuild_d(&self.nimber_num, &self.nimbers, d as u16);
+                    //self.split[d].rebuild_d(&self.nimber_num, &self.nimbers, d as Nimber);
                 } else {
                     self.split[d].add_to_c(result);
                 }
             } else {
                 if n.is_power_of_two() {
-                    self.split[d].rebuild_d(&self.nimber_num, &self.nimbers, d as u16, &mut self.stats);
+                    self.split[d].rebuild_d(&self.nimber_num, &self.nimbers, d as Nimber, &mut self.stats);
                 } else if self.split[d].r.contain_nimber(result) {
                     if n != 0 { self.split[d].r_positions.push(n); }
                 } else {
@@ -0,0 +1,152 @@
+use crate::rcsplit::RCSplit;
+use crate::rc::EARLY_EXIT_CHUNK;
+use crate::stats::NimberStats;
+use crate::kernels::add_mirrored_xors;
+use crate::set::cleared_options;
+use crate::{BitSet, Game, Nimber, PackedNimbers, SolverEvent};
+
+/// RC solver that keeps the nimbers in [`PackedNimbers`], storing the nimbers of R explicitly and the nimbers of C compactly.
+///
+/// It calculates the same nimbers as [`crate::RCSolver`] (with dynamic rebuilding of the R/C split, after which the nimbers are repacked),
+/// but takes less memory, as the positions of R are marked in the bitmap of [`PackedNimbers`] instead of being listed,
+/// and the nimbers are packed (the fewer distinct common nimbers, the fewer bits they take),
+/// at the cost of decoding the nimbers before using them, which makes the calculation several times slower.
+/// Supports the games whose moves leave at most two heaps, without restricted breaking moves.
+pub struct PackedRCSolver<S = ()> {
+    game: Game,
+    nimbers: PackedNimbers,
+    nimber_num: NimberStats,
+    split: RCSplit,
+    /// Option set reused by each calculation, cleared and sized by [`cleared_options`].
+    option_nimbers: Vec<u64>,
+    pub stats: S
+}
+
+impl<S: SolverEvent> PackedRCSolver<S> {
+    /// Returns the solver of the `game`, or `None` if some move of the `game` can leave more than two heaps or is a restricted split.
+    pub fn with_stats(game: Game, stats: S) -> Option<Self> {
+        if game.max_heaps() > 2 || game.has_restricted_breaking() { return None; }
+        Some(Self { game, nimbers: PackedNimbers::default(), nimber_num: Default::default(), split: Default::default(), option_nimbers: Vec::new(), stats })
+    }
+
+    /// Returns the solver of the `game`, or `None` if some move of the `game` can leave more than two heaps or is a restricted split.
+    #[inline] pub fn new(game: Game) -> Option<Self> where S: Default {
+        Self::with_stats(game, Default::default())
+    }
+
+    #[inline] pub fn game(&self) -> &Game { &self.game }
+
+    /// Returns the nimbers calculated so far.
+    #[inline] pub fn nimbers(&self) -> &PackedNimbers { &self.nimbers }
+
+    pub fn print_nimber_stat_to(&self, f: &mut dyn std::io::Write) -> std::io::Result<()> {
+        writeln!(f, "{}", self.nimber_num)?;
+        writeln!(f, "{}", self.split)
+    }
+}
+
+impl<S: SolverEvent> Iterator for PackedRCSolver<S> {
+    type Item = Nimber;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let option_nimbers = cleared_options(&mut self.option_nimbers, self.nimber_num.max);
+        let n = self.nimbers.len();
+        self.game.unroll(n);
+        if self.game.can_take_all(n) { option_nimbers.add_nimber(0) }
+        for t in &self.game.taking {
+            let t = *t;
+            if t >= n { break }
+            option_nimbers.add_nimber(self.nimbers.get(n-t));
+            self.stats.take_option();
+        }
+        for b in &self.game.breaking {
+            let b = *b;
+            if b+1 >= n { break }
+            let after_take = n - b;
+            // the positions of R are rare (as well as some positions of C if the dictionary is full, which only adds correct options)
+            for (i, nimber) in self.nimbers.rare_iter().skip_while(|(i, _)| *i == 0) {
+                if i >= after_take { break; }
+                option_nimbers.add_nimber(nimber ^ self.nimbers.get(after_take-i));
+                self.stats.break_option();
+            }
+        }
+        let mut result = option_nimbers.checked_mex()?;
+        if !self.split.in_c(result) {
+            // the front and the (mirrored) back of the chunk of splits, decoded
+            let mut decoded: [Nimber; 2 * EARLY_EXIT_CHUNK] = [0; 2 * EARLY_EXIT_CHUNK];
+            'outer: for b in &self.game.breaking {
+                let b = *b;
+                if b+1 >= n { break }
+                let after_take = n - b;
+                let end = after_take/2 + 1;
+                let mut i = 1;
+                while i < end {
+                    let chunk_end = (i + EARLY_EXIT_CHUNK).min(end);
+                    let len = chunk_end - i;
+                    self.nimbers.decode(i, &mut decoded[..len]);
+                    self.nimbers.decode(after_take + 1 - chunk_end, &mut decoded[len..2*len]);
+                    add_mirrored_xors(option_nimbers, &decoded[..2*len], 0..len, 2*len-1);
+                    self.stats.break_options(len);
+                    i = chunk_end;
+                    if option_nimbers.contain_nimber(result) {
+                        result = option_nimbers.checked_mex()?;
+                        if self.split.in_c(result) {
+                            break 'outer;
+                        }
+                    }
+                }
+            }
+        }
+        self.nimber_num.count(result);
+        // the positions of R are not listed in the split, as the rare positions of the nimbers are marked in the bitmap instead
+        let in_r = self.split.r.contain_nimber(result);
+        self.nimbers.push(result, in_r);
+        if in_r && self.split.should_rebuild(result, &self.nimber_num) {
+            if self.split.update_classification(&self.nimber_num, result) {
+                self.nimbers.repack(|nimber| self.split.r.contain_nimber(nimber));
+                self.stats.rebuilding_rc(n + 1);
+            } else {
+                self.stats.rebuilding_rc(0);
+            }
+        }
+        Some(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+    use crate::{RCSolver, Solver};
+
+    fn rc_nimbers(game: &str, n: usize) -> Vec<Nimber> {
+        RCSolver::<true>::new(Game::from_str(game).unwrap()).take(n).collect()
+    }
+
+    /// Returns the nimbers calculated by the packed solver, and decoded from its sequence.
+    fn packed_nimbers(game: &str, n: usize) -> (Vec<Nimber>, Vec<Nimber>) {
+        let mut solver = Box::new(PackedRCSolver::<()>::new(Game::from_str(game).unwrap()).unwrap());
+        let calculated: Vec<Nimber> = solver.by_ref().take(n).collect();
+        // the nimbers are repacked after rebuilding, so exactly the nimbers of the current R are rare
+        assert!(solver.nimbers.rare_iter().map(|(position, _)| position)
+            .eq(calculated.iter().enumerate().filter(|(_, nimber)| solver.split.r.contain_nimber(**nimber)).map(|(position, _)| position)), "{game}");
+        (calculated, solver.nimbers().iter().collect())
+    }
+
+    fn check(game: &str, n: usize) {
+        let expected = rc_nimbers(game, n);
+        let (calculated, decoded) = packed_nimbers(game, n);
+        assert_eq!(calculated, expected, "{game}");
+        assert_eq!(decoded, expected, "{game}");
+    }
+
+    #[test]
+    fn test_packed_rc() {
+        for game in ["0.4", "0.6", "0.07", "0.137", "4.7", "0.3033"] {
+            check(game, 3000);
+        }
+        check("0.3(4)", 300);
+        assert!(PackedRCSolver::<()>::new(Game::from_str("grundy").unwrap()).is_none());
+        assert!(PackedRCSolver::<()>::new(Game::from_str("0.F").unwrap()).is_none());
+    }
+}
@@ -1,11 +1,11 @@
 use std::fmt::Display;
 
-use crate::{stats::NimberStats, BitSet, SolverEvent};
+use crate::{set::SetConstructor, stats::NimberStats, BitSet, Nimber, SolverEvent};
 
 pub(crate) struct RCSplit {
-    pub(crate) r: [u64; 1<<(16-6)],
-    pub(crate) c: [u64; 1<<(16-6)],
-    pub(crate) max_c: u16, // largest nimber in c
+    pub(crate) r: Vec<u64>, // grown as larger nimbers are added
+    pub(crate) c: Vec<u64>, // grown as larger nimbers are added
+    pub(crate) max_c: Nimber, // largest nimber in c
     pub(crate) r_positions: Vec<usize>,
 }
 
@@ -14,13 +14,11 @@ impl Default for RCSplit {
 }
 
 impl RCSplit {
-    pub fn new(d: u16) -> Self {
-        let mut result = Self { r: [0; 1<<(16-6)], c: [0; 1<<(16-6)], max_c: 0, r_positions: Default::default() };
-        result.r[0] = d as u64+1;   // adds d to r
-        result
+    pub fn new(d: Nimber) -> Self {
+        Self { r: vec![d as u64+1], c: vec![0], max_c: 0, r_positions: Default::default() }   // adds d to r
     }
 
-    #[inline] pub fn can_add_to_c(&self, nimber: u16) -> bool {
+    #[inline] pub fn can_add_to_c(&self, nimber: Nimber) -> bool {
         for v in 1..=self.max_c {
             if self.c.contain_nimber(v) && self.c.contain_nimber(nimber ^ v) {
                 return false;
@@ -29,20 +27,20 @@ impl RCSplit {
         true
     }
 
-    #[inline] pub fn can_add_to_c_d(&self, nimber: u16, d: u16) -> bool {
+    #[inline] pub fn can_add_to_c_d(&self, nimber: Nimber, d: Nimber) -> bool {
         self.can_add_to_c(nimber ^ d)
     }
 
-    #[inline] pub fn add_to_c(&mut self, nimber: u16) {
-        self.c.add_nimber(nimber);
+    #[inline] pub fn add_to_c(&mut self, nimber: Nimber) {
+        self.c.grow_and_add_nimber(nimber);
         if nimber > self.max_c { self.max_c = nimber }
     }
 
-    #[inline] pub fn add_to_r(&mut self, nimber: u16) {
-        self.r.add_nimber(nimber);
+    #[inline] pub fn add_to_r(&mut self, nimber: Nimber) {
+        self.r.grow_and_add_nimber(nimber);
     }
 
-    #[inline] pub fn add_to(&mut self, nimber: u16, to_c: bool) -> bool {
+    #[inline] pub fn add_to(&mut self, nimber: Nimber, to_c: bool) -> bool {
         if to_c {
             self.add_to_c(nimber)
         } else {
@@ -51,28 +49,28 @@ impl RCSplit {
         to_c
     }
 
-    #[inline] pub fn classify(&mut self, nimber: u16) -> bool {
+    #[inline] pub fn classify(&mut self, nimber: Nimber) -> bool {
         self.add_to(nimber, self.can_add_to_c(nimber))
     }
 
-    #[inline] pub fn classify_d(&mut self, nimber: u16, d: u16) -> bool {
+    #[inline] pub fn classify_d(&mut self, nimber: Nimber, d: Nimber) -> bool {
         self.add_to(nimber, self.can_add_to_c(nimber ^ d))
     }
 
-    pub fn in_c(&mut self, nimber: u16) -> bool {
+    pub fn in_c(&mut self, nimber: Nimber) -> bool {
         if self.c.contain_nimber(nimber) { return true; }
         if self.r.contain_nimber(nimber) { return false; }
         self.classify(nimber)
     }
 
     /// Never adds nimber to c.
-    pub fn in_r(&mut self, nimber: u16, d: u16) -> bool {
+    pub fn in_r(&mut self, nimber: Nimber, d: Nimber) -> bool {
         if self.c.contain_nimber(nimber) { return false; }
         if self.r.contain_nimber(nimber) { return true; }
         if self.can_add_to_c_d(nimber, d) {
             false
         } else {
-            self.r.add_nimber(nimber);
+            self.add_to_r(nimber);
             true
         }
     }
@@ -84,17 +82,18 @@ impl RCSplit {
         self.r_positions.clear();
     }
 
-    fn fill_r_positions(&mut self, nimbers: &[u16]) {
-        for position in 1..nimbers.len() {
-            if self.r.contain_nimber(nimbers[position]) {
+    fn fill_r_positions(&mut self, nimbers: &[Nimber]) {
+        for (position, nimber) in nimbers.iter().enumerate().skip(1) {
+            if self.r.contain_nimber(*nimber) {
                 self.r_positions.push(position);
             }
         }
     }
 
-    pub fn update(&mut self, stats: &NimberStats, nimbers: &[u16], solver_stats: &mut impl SolverEvent) {
+    /// Updates the classification of the nimbers to R and C, given the `last_nimber` calculated.
+    /// Returns whether the classification has changed (`r_positions` are left empty in such a case).
+    pub fn update_classification(&mut self, stats: &NimberStats, last_nimber: Nimber) -> bool {
         let mut result = Self::new(0);
-        let last_nimber = *nimbers.last().unwrap();
         let mut nimbers_sorted = stats.nimbers_from_most_common(0).into_iter();
         while let Some(nimber) = nimbers_sorted.next() {
             if nimber == last_nimber {
@@ -102,19 +101,26 @@ impl RCSplit {
                     result.add_to_c(nimber);    // last_nimber is now in C
                     break;
                 } else {
-                    solver_stats.rebuilding_rc(0);
-                    return; // last_nimber still clarified to R, so nothing change
+                    return false; // last_nimber still clarified to R, so nothing change
                 }
             }
             result.add_to(nimber, self.c.contain_nimber(nimber));    // copying clarification from self
         }
         *self = result;
         for nimber in nimbers_sorted { self.classify(nimber); }
-        self.fill_r_positions(nimbers);
-        solver_stats.rebuilding_rc(nimbers.len());
+        true
+    }
+
+    pub fn update(&mut self, stats: &NimberStats, nimbers: &[Nimber], solver_stats: &mut impl SolverEvent) {
+        if self.update_classification(stats, *nimbers.last().unwrap()) {
+            self.fill_r_positions(nimbers);
+            solver_stats.rebuilding_rc(nimbers.len());
+        } else {
+            solver_stats.rebuilding_rc(0);
+        }
     }
 
-    pub fn rebuild(&mut self, stats: &NimberStats, nimbers: &[u16], solver_stats: &mut impl SolverEvent) {
+    pub fn rebuild(&mut self, stats: &NimberStats, nimbers: &[Nimber], solver_stats: &mut impl SolverEvent) {
         self.clear();
         self.r[0] = 1;
         for nimber in stats.nimbers_from_most_common(0) {
@@ -124,17 +130,17 @@ impl RCSplit {
         solver_stats.rebuilding_rc(nimbers.len());
     }
 
-    fn fill_r_positions_d(&mut self, nimbers: &[u16]) {
+    fn fill_r_positions_d(&mut self, nimbers: &[Nimber]) {
         for position in 1..nimbers.len() {
-            if self.r.contain_nimber((nimbers[position]<<1) | (position as u16 & 1)) {
+            if self.r.contain_nimber((nimbers[position]<<1) | (position as Nimber & 1)) {
                 self.r_positions.push(position);
             }
         }
     }
 
-    pub fn update_d(&mut self, stats: &NimberStats, nimbers: &[u16], d: u16, solver_stats: &mut impl SolverEvent) {
+    pub fn update_d(&mut self, stats: &NimberStats, nimbers: &[Nimber], d: Nimber, solver_stats: &mut impl SolverEvent) {
         let mut result = Self::new(d);
-        let last_nimber = (*nimbers.last().unwrap() << 1) | ((nimbers.len()-1) as u16 & 1);
+        let last_nimber = (*nimbers.last().unwrap() << 1) | ((nimbers.len()-1) as Nimber & 1);
         let mut nimbers_sorted = stats.nimbers_from_most_common(d).into_iter();
         while let Some(nimber) = nimbers_sorted.next() {
             if nimber == last_nimber {
@@ -154,7 +160,7 @@ impl RCSplit {
         solver_stats.rebuilding_rc(nimbers.len());
     }
     
-    pub fn rebuild_d(&mut self, stats: &NimberStats, nimbers: &[u16], d: u16, solver_stats: &mut impl SolverEvent) {
+    pub fn rebuild_d(&mut self, stats: &NimberStats, nimbers: &[Nimber], d: Nimber, solver_stats: &mut impl SolverEvent) {
         self.clear();
         self.r[0] = d as u64+1; // adds (0,0) (i.e. 0th bit) if d=0 and (0,1) (i.e. 1st bit) if d=1 to R
         for nimber in stats.nimbers_from_most_common(d) {   // skip (0,d)
@@ -164,7 +170,7 @@ impl RCSplit {
         solver_stats.rebuilding_rc(nimbers.len());
     }
     
-    pub fn should_rebuild_d(&self, recent_nimber: u16, stats: &NimberStats) -> bool {
+    pub fn should_rebuild_d(&self, recent_nimber: Nimber, stats: &NimberStats) -> bool {
         let r_occ = stats.occurences[recent_nimber as usize];
         for c in 0..=stats.max {
             let c_occ = stats.occurences[c as usize];
@@ -177,7 +183,7 @@ impl RCSplit {
         false
     }
 
-    pub fn should_rebuild(&self, recent_nimber: u16, stats: &NimberStats) -> bool {
+    pub fn should_rebuild(&self, recent_nimber: Nimber, stats: &NimberStats) -> bool {
         //self.should_rebuild_d(recent_nimber, stats) //OK as 0 is in r
         let r_occ = stats.occurences[recent_nimber as usize];
         for c in 1..=stats.max {
@@ -207,11 +213,12 @@ impl Display for RCSplit {
         }
         writeln!(f)?;
         write!(f, "R{}:", name)?;
-        for n in 0..=u16::MAX {
+        for n in 0..=self.r.msb_index().unwrap_or(0) {
             if self.r.contain_nimber(n) {
                 if as_pairs { write!(f, " {}.{}", n>>1, n&1)?; } else { write!(f, " {}", n)?; }
             }
         }
+        if self.r_positions.is_empty() { return Ok(()); }
         write!(f, " at {} pos:", self.r_positions.len())?;
         for p in self.r_positions.iter().take(10) {
             write!(f, " {}", p)?;
@@ -0,0 +1,242 @@
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::{Game, Nimber};
+
+/// Magic bytes at the beginning of each nimber sequence file.
+const MAGIC: [u8; 8] = *b"OGSNIM\0\0";
+
+/// Version of the nimber sequence file format that stores 16-bit nimbers.
+const VERSION: u32 = 1;
+
+/// Version of the nimber sequence file format that stores 32-bit nimbers,
+/// used only if some nimber exceeds [`u16::MAX`].
+const WIDE_VERSION: u32 = 2;
+
+/// Number of nimbers read or written at once.
+const CHUNK_LEN: usize = 1 << 16;
+
+/// Calculates checksum of the `nimbers` with fletcher 32 algorithm.
+///
+/// Each nimber is summed as a 16-bit word, followed by the word of its higher bits if it exceeds [`u16::MAX`],
+/// so the checksum of 16-bit nimbers does not depend on the width of [`Nimber`].
+pub fn checksum(nimbers: &[Nimber]) -> u32 {
+    checksum_of(nimbers.iter().copied())
+}
+
+/// Calculates checksum of the `nimbers` (given by iterator) with fletcher 32 algorithm, see [`checksum`].
+pub fn checksum_of(nimbers: impl IntoIterator<Item = Nimber>) -> u32 {
+    let mut checksum = (0u16, 0u16);
+    let mut add = |word: u16| {
+        checksum.0 = checksum.0.wrapping_add(word);
+        checksum.1 = checksum.1.wrapping_add(checksum.0);
+    };
+    for n in nimbers {
+        let n = n as u64;
+        add(n as u16);
+        if n > u16::MAX as u64 { add((n >> 16) as u16); }
+    }
+    ((checksum.1 as u32) << 16) | checksum.0 as u32
+}
+
+/// Returns whether all the `nimbers` fit in 16 bits.
+#[inline] fn are_narrow(nimbers: &[Nimber]) -> bool {
+    nimbers.iter().all(|n| *n as u64 <= u16::MAX as u64)
+}
+
+fn invalid<M: Into<String>>(msg: M) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.into())
+}
+
+/// Reads exactly `buf.len()` bytes from `input`, reporting the end of data as the file being truncated.
+fn read_exact_or_truncated<R: Read>(input: &mut R, buf: &mut [u8]) -> io::Result<()> {
+    input.read_exact(buf).map_err(|e|
+        if e.kind() == io::ErrorKind::UnexpectedEof { invalid("nimber sequence file is truncated") } else { e })
+}
+
+/// Writes the `nimbers` of the first positions of the `game` to `output`.
+///
+/// The nimbers are preceded by the header, which consists of:
+/// magic bytes, format version, the game code, the number of nimbers and their checksum.
+/// All numbers are written in little-endian byte order.
+/// The nimbers are 16-bit (version 1 of the format), or 32-bit (version 2) if some of them exceeds [`u16::MAX`].
+pub fn write_nimbers<W: Write>(output: &mut W, game: &Game, nimbers: &[Nimber]) -> io::Result<()> {
+    let code = game.to_string();
+    let narrow = are_narrow(nimbers);
+    output.write_all(&MAGIC)?;
+    output.write_all(&(if narrow { VERSION } else { WIDE_VERSION }).to_le_bytes())?;
+    output.write_all(&(code.len() as u32).to_le_bytes())?;
+    output.write_all(code.as_bytes())?;
+    output.write_all(&(nimbers.len() as u64).to_le_bytes())?;
+    output.write_all(&checksum(nimbers).to_le_bytes())?;
+    let mut bytes = Vec::with_capacity(4 * CHUNK_LEN);
+    for chunk in nimbers.chunks(CHUNK_LEN) {
+        bytes.clear();
+        for n in chunk {
+            let n = *n as u64;
+            if narrow {
+                bytes.extend_from_slice(&(n as u16).to_le_bytes());
+            } else {
+                bytes.extend_from_slice(&(n as u32).to_le_bytes());
+            }
+        }
+        output.write_all(&bytes)?;
+    }
+    Ok(())
+}
+
+/// Reads the game and the nimbers of its first positions, written by [`write_nimbers`], from `input`.
+/// Returns error if the data are not a nimber sequence, are truncated, their checksum is incorrect,
+/// or they include nimbers that exceed [`Nimber::MAX`] (i.e. [`u16::MAX`] without the `wide-nimbers` feature).
+///
+/// The memory is allocated as the data are read, so the lengths given in the header cannot cause allocating
+/// more than the input actually contains.
+pub fn read_nimbers<R: Read>(input: &mut R) -> io::Result<(Game, Vec<Nimber>)> {
+    let mut magic = [0u8; MAGIC.len()];
+    input.read_exact(&mut magic)?;
+    if magic != MAGIC { return Err(invalid("the file is not a nimber sequence")); }
+    let mut u32_bytes = [0u8; 4];
+    input.read_exact(&mut u32_bytes)?;
+    let version = u32::from_le_bytes(u32_bytes);
+    let nimber_bytes = match version {
+        VERSION => 2,
+        WIDE_VERSION => 4,
+        _ => return Err(invalid(format!("unsupported version {} of nimber sequence file (expected {} or {})", version, VERSION, WIDE_VERSION)))
+    };
+    input.read_exact(&mut u32_bytes)?;
+    let code_len = u32::from_le_bytes(u32_bytes) as u64;
+    let mut code = Vec::new();
+    input.by_ref().take(code_len).read_to_end(&mut code)?;
+    if (code.len() as u64) < code_len { return Err(invalid("nimber sequence file is truncated")); }
+    let game = std::str::from_utf8(&code).ok().and_then(|code| Game::from_str(code).ok())
+        .ok_or_else(|| invalid("nimber sequence file contains incorrect game code"))?;
+    let mut u64_bytes = [0u8; 8];
+    read_exact_or_truncated(input, &mut u64_bytes)?;
+    let len = usize::try_from(u64::from_le_bytes(u64_bytes))
+        .map_err(|_| invalid("nimber sequence file declares too many nimbers"))?;
+    read_exact_or_truncated(input, &mut u32_bytes)?;
+    let expected_checksum = u32::from_le_bytes(u32_bytes);
+    let mut nimbers = Vec::with_capacity(len.min(CHUNK_LEN));
+    let mut bytes = vec![0u8; nimber_bytes * CHUNK_LEN];
+    while nimbers.len() < len {
+        let bytes = &mut bytes[..nimber_bytes * (len - nimbers.len()).min(CHUNK_LEN)];
+        read_exact_or_truncated(input, bytes)?;
+        for b in bytes.chunks_exact(nimber_bytes) {
+            let n = if nimber_bytes == 2 { u16::from_le_bytes([b[0], b[1]]) as u64 } else { u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as u64 };
+            if n > Nimber::MAX as u64 {
+                return Err(invalid(format!("nimber sequence file contains nimber {} that exceeds {}, the largest nimber supported without the wide-nimbers feature", n, Nimber::MAX)));
+            }
+            nimbers.push(n as Nimber);
+        }
+    }
+    if checksum(&nimbers) != expected_checksum {
+        return Err(invalid("nimber sequence file is corrupted (checksum mismatch)"));
+    }
+    Ok((game, nimbers))
+}
+
+/// Writes the `nimbers` of the first positions of the `game` to the file with the given `path`, see [`write_nimbers`].
+///
+/// The nimbers are first written to a temporary file (`path` with `.tmp` appended) which then replaces the file at `path`,
+/// so an existing file is never left partially overwritten.
+pub fn save_nimbers<P: AsRef<Path>>(path: P, game: &Game, nimbers: &[Nimber]) -> io::Result<()> {
+    let path = path.as_ref();
+    let mut tmp_path = path.as_os_str().to_owned();
+    tmp_path.push(".tmp");
+    let result = (|| {
+        let mut file = BufWriter::new(File::create(&tmp_path)?);
+        write_nimbers(&mut file, game, nimbers)?;
+        file.into_inner().map_err(io::IntoInnerError::into_error)?.sync_all()?;
+        fs::rename(&tmp_path, path)
+    })();
+    if result.is_err() { let _ = fs::remove_file(&tmp_path); }
+    result
+}
+
+/// Reads the game and the nimbers of its first positions from the file with the given `path`, see [`read_nimbers`].
+pub fn load_nimbers<P: AsRef<Path>>(path: P) -> io::Result<(Game, Vec<Nimber>)> {
+    read_nimbers(&mut BufReader::new(File::open(path)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{NaiveSolver, RCSolver, RC2Solver, Solver};
+
+    fn first_nimbers<S: Solver<Stats = ()>>(game: &Game, n: usize) -> Vec<Nimber> {
+        S::new(game.clone()).take(n).collect()
+    }
+
+    fn check_resume<S: Solver<Stats = ()>>(game: &str) {
+        let game = Game::from_str(game).unwrap();
+        let all = first_nimbers::<S>(&game, 600);
+        let mut file = Vec::new();
+        write_nimbers(&mut file, &game, &all[..250]).unwrap();
+        let mut solver = S::read_nimbers_stats(&mut &file[..], ()).unwrap();
+        assert_eq!(solver.nimbers(), &all[..250]);
+        for _ in 250..600 { solver.next(); }
+        assert_eq!(solver.nimbers(), all, "{}", game.to_string());
+    }
+
+    #[test]
+    fn test_resume() {
+        for game in ["0.07", "0.137", "4.F", "0.3+e0,2+o1"] {
+            check_resume::<NaiveSolver>(game);
+            check_resume::<RCSolver>(game);
+            check_resume::<RCSolver<false>>(game);
+            check_resume::<RC2Solver>(game);
+            check_resume::<RC2Solver<false>>(game);
+        }
+    }
+
+    #[test]
+    fn test_corrupted() {
+        let game = Game::from_str("0.07").unwrap();
+        let mut file = Vec::new();
+        write_nimbers(&mut file, &game, &[0, 0, 1, 1, 2]).unwrap();
+        let (read_game, nimbers) = read_nimbers(&mut &file[..]).unwrap();
+        assert_eq!(read_game.to_string(), "0.07");
+        assert_eq!(nimbers, [0, 0, 1, 1, 2]);
+        *file.last_mut().unwrap() ^= 1;
+        assert_eq!(read_nimbers(&mut &file[..]).err().unwrap().kind(), io::ErrorKind::InvalidData);
+        assert_eq!(read_nimbers(&mut &file[..file.len()-1]).err().unwrap().kind(), io::ErrorKind::InvalidData);
+        assert!(read_nimbers(&mut &b"something else"[..]).is_err());
+    }
+
+    #[test]
+    fn test_wide_nimbers() {
+        // version 2 file with the nimbers 0, 1 and 65536, whose checksum sums the words 0, 1, 0, 1
+        let mut file = Vec::new();
+        file.extend_from_slice(&MAGIC);
+        file.extend_from_slice(&WIDE_VERSION.to_le_bytes());
+        file.extend_from_slice(&4u32.to_le_bytes());
+        file.extend_from_slice(b"0.07");
+        file.extend_from_slice(&3u64.to_le_bytes());
+        file.extend_from_slice(&((4u32 << 16) | 2).to_le_bytes());
+        for n in [0u32, 1, 1 << 16] { file.extend_from_slice(&n.to_le_bytes()); }
+        #[cfg(not(feature = "wide-nimbers"))]
+        assert_eq!(read_nimbers(&mut &file[..]).err().unwrap().kind(), io::ErrorKind::InvalidData);
+        #[cfg(feature = "wide-nimbers")] {
+            let (game, nimbers) = read_nimbers(&mut &file[..]).unwrap();
+            assert_eq!(nimbers, [0, 1, 1 << 16]);
+            let mut written = Vec::new();
+            write_nimbers(&mut written, &game, &nimbers).unwrap();
+            assert_eq!(written, file);
+        }
+    }
+
+    #[test]
+    fn test_huge_lengths() {
+        let game = Game::from_str("0.07").unwrap();
+        let mut file = Vec::new();
+        write_nimbers(&mut file, &game, &[0, 0, 1, 1, 2]).unwrap();
+        let mut huge_code = file.clone();
+        huge_code[12..16].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert_eq!(read_nimbers(&mut &huge_code[..]).err().unwrap().kind(), io::ErrorKind::InvalidData);
+        let mut huge_len = file;
+        huge_len[20..28].copy_from_slice(&(u64::MAX >> 8).to_le_bytes());
+        assert_eq!(read_nimbers(&mut &huge_len[..]).err().unwrap().kind(), io::ErrorKind::InvalidData);
+    }
+}
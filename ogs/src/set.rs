@@ -1,13 +1,29 @@
+/// Nimber of a position.
+///
+/// Nimbers are 16-bit by default. The `wide-nimbers` feature makes them 32-bit,
+/// which allows to solve the games whose nimbers exceed [`u16::MAX`], at the cost of twice as much memory per nimber.
+#[cfg(not(feature = "wide-nimbers"))] pub type Nimber = u16;
+
+/// Nimber of a position.
+///
+/// Nimbers are 32-bit, as the `wide-nimbers` feature is enabled.
+#[cfg(feature = "wide-nimbers")] pub type Nimber = u32;
+
 /// Set of nimbers.
 /// 
 /// Implemented by `u64` slices.
 pub trait BitSet {
     /// Returns the smallest nimber not included in the `self` set.
-    fn mex(&self) -> u16;
+    fn mex(&self) -> Nimber;
+
+    /// Returns the smallest nimber not included in the `self` set, or `None` if it exceeds [`Nimber::MAX`].
+    fn checked_mex(&self) -> Option<Nimber>;
 
-    unsafe fn set_nimber_unchecked(&mut self, nimber: u16);
-    fn add_nimber(&mut self, nimber: u16);
-    fn contain_nimber(&self, nimber: u16) -> bool;
+    unsafe fn set_nimber_unchecked(&mut self, nimber: Nimber);
+    fn add_nimber(&mut self, nimber: Nimber);
+
+    /// Returns whether the `nimber` is included in the `self` set, which is `false` for the nimbers beyond the set.
+    fn contain_nimber(&self, nimber: Nimber) -> bool;
 
     unsafe fn set_bit_unchecked(&mut self, bit_nr: usize);
     fn set_bit(&mut self, bit_nr: usize);
@@ -15,38 +31,67 @@ pub trait BitSet {
     fn try_get_bit(&self, bit_nr: usize) -> Option<bool>;
 
     /// Returns the index of the most significant bit set.
-    fn msb_index(&self) -> Option<u16>;
+    fn msb_index(&self) -> Option<Nimber>;
+}
+
+/// Returns the number of words of the option set sufficient for a position whose options have nimbers
+/// derived from the nimbers up to `max_nimber`.
+///
+/// The nimbers and their xors are smaller than the smallest power of two greater than `max_nimber`,
+/// so the mex of such options does not exceed this power, even if the set is full.
+#[inline] pub(crate) fn option_words(max_nimber: Nimber) -> usize {
+    ((max_nimber as usize + 1).next_power_of_two() / 64).max(1)
+}
+
+/// Clears the option set `options` and resizes it to [`option_words`] words for the given `max_nimber`.
+/// Returns the set, ready for calculating the next nimber.
+#[inline] pub(crate) fn cleared_options(options: &mut Vec<u64>, max_nimber: Nimber) -> &mut [u64] {
+    options.clear();
+    options.resize(option_words(max_nimber), 0);
+    options
 }
 
 /// Implemented by `Vec<u64>`.
 pub trait SetConstructor {
     /// Returns set to which nimbers from `0` to `max_nimber` can be inserted.
-    fn with_max_nimber(max_nimber: u16) -> Self;
+    fn with_max_nimber(max_nimber: Nimber) -> Self;
+
+    /// Adds the `nimber` to the set, growing the set if needed.
+    fn grow_and_add_nimber(&mut self, nimber: Nimber);
 }
 
 impl BitSet for [u64] {
-    fn mex(&self) -> u16 { 
+    fn mex(&self) -> Nimber { 
         let mut result = 0;
         for v in self {
             if *v == u64::MAX {
                 result += 64;
             } else {
-                return result + v.trailing_ones() as u16;
+                return result + v.trailing_ones() as Nimber;
             }
         }
         result
     }
 
-    #[inline] unsafe fn set_nimber_unchecked(&mut self, nimber: u16) {
+    fn checked_mex(&self) -> Option<Nimber> {
+        let mut result: Nimber = 0;
+        for v in self {
+            if *v != u64::MAX { return result.checked_add(v.trailing_ones() as Nimber); }
+            result = result.checked_add(64)?;
+        }
+        Some(result)
+    }
+
+    #[inline] unsafe fn set_nimber_unchecked(&mut self, nimber: Nimber) {
         *self.get_unchecked_mut((nimber/64) as usize) = 1u64 << (nimber % 64) as u64;
     }
     
-    #[inline] fn add_nimber(&mut self, nimber: u16) {
+    #[inline] fn add_nimber(&mut self, nimber: Nimber) {
         self[(nimber/64) as usize] |= 1u64 << (nimber % 64) as u64;
     }
     
-    #[inline] fn contain_nimber(&self, nimber: u16) -> bool {
-        self[(nimber/64) as usize] & (1u64 << (nimber % 64)) != 0
+    #[inline] fn contain_nimber(&self, nimber: Nimber) -> bool {
+        self.get((nimber/64) as usize).is_some_and(|v| v & (1u64 << (nimber % 64)) != 0)
     }
 
     unsafe fn set_bit_unchecked(&mut self, bit_nr: usize) {
@@ -65,18 +110,24 @@ impl BitSet for [u64] {
         Some(self.get(bit_nr/64)? & (1u64 << (bit_nr % 64) as u64) != 0)
     }
     
-    fn msb_index(&self) -> Option<u16> {
+    fn msb_index(&self) -> Option<Nimber> {
         for (i, v) in self.iter().copied().enumerate().rev() {
-            if v != 0 { return Some(64*(i as u16+1) - v.leading_zeros() as u16-1); }
+            if v != 0 { return Some(64*(i as Nimber+1) - v.leading_zeros() as Nimber-1); }
         }
         None
     }
 }
 
 impl SetConstructor for Vec<u64> {
-    fn with_max_nimber(max_nimber: u16) -> Self {
+    fn with_max_nimber(max_nimber: Nimber) -> Self {
         vec![0; max_nimber as usize / 64 + 1]
     }
+
+    fn grow_and_add_nimber(&mut self, nimber: Nimber) {
+        let word = (nimber/64) as usize;
+        if self.len() <= word { self.resize(word + 1, 0); }
+        self[word] |= 1u64 << (nimber % 64) as u64;
+    }
 }
 
 #[cfg(test)]
@@ -89,6 +140,23 @@ pub(crate) mod tests {
         assert_eq!(vec![0b1011u64].mex(), 2);
         assert_eq!(vec![u64::MAX].mex(), 64);
         assert_eq!(vec![u64::MAX, 0b1011u64].mex(), 66);
+        assert_eq!(vec![u64::MAX, 0b1011u64].checked_mex(), Some(66));
+        assert_eq!(vec![u64::MAX; 1023].checked_mex(), Some(65472));
+        let mut full = [u64::MAX; 1<<(16-6)];
+        #[cfg(not(feature = "wide-nimbers"))] assert_eq!(full.checked_mex(), None);
+        #[cfg(feature = "wide-nimbers")] assert_eq!(full.checked_mex(), Some(1<<16));
+        full[1023] = u64::MAX >> 1;
+        assert_eq!(full.checked_mex(), Some(0xFFFF));
+    }
+
+    #[test]
+    fn test_option_words() {
+        assert_eq!(option_words(0), 1);
+        assert_eq!(option_words(63), 1);
+        assert_eq!(option_words(64), 2);
+        assert_eq!(option_words(127), 2);
+        assert_eq!(option_words(128), 4);
+        assert_eq!(option_words(0xFFFF), 1<<(16-6));
     }
 
     #[test]
@@ -96,6 +164,10 @@ pub(crate) mod tests {
         let mut s: Vec<u64> = Vec::with_max_nimber(64);     // insert_nimber needs mut
         for i in 0..=64 { s.add_nimber(i); }
         assert_eq!(s.mex(), 65);
+        assert!(!s.contain_nimber(1000));
+        s.grow_and_add_nimber(1000);
+        assert!(s.contain_nimber(1000));
+        assert_eq!(s.len(), 1000/64 + 1);
     }
 
     #[test]
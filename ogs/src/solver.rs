@@ -1,16 +1,29 @@
-use crate::{Game, SolverEvent};
+use crate::{Game, Nimber, SolverEvent};
+use crate::sequence::{read_nimbers, write_nimbers};
 
-pub trait Solver: Iterator<Item=u16> {
+/// Solver of an octal game, which iterates over the nimbers of the consecutive positions.
+///
+/// The iteration ends (`next` returns `None`) at the first position whose nimber exceeds [`Solver::MAX_NIMBER`].
+/// Nimbers are 16-bit by default, so the games whose nimbers exceed [`u16::MAX`]
+/// require the `wide-nimbers` feature, which makes them 32-bit (see [`Nimber`]).
+pub trait Solver: Iterator<Item=Nimber> {
     type Stats: SolverEvent;
 
+    /// The largest nimber the solver can calculate.
+    const MAX_NIMBER: Nimber = Nimber::MAX;
+
     fn stats(&self) -> &Self::Stats;
-    fn nimbers(&self) -> &[u16];
+    fn nimbers(&self) -> &[Nimber];
     fn game(&self) -> &Game;
     fn capacity(&self) -> usize;
 
     fn with_stats(game: Game, stats: Self::Stats) -> Self;
     fn with_capacity_stats(game: Game, capacity: usize, stats: Self::Stats) -> Self;
 
+    /// Returns the solver of the `game` that continues calculation after the given `nimbers` of its first positions.
+    /// Panics if some of the `nimbers` exceeds [`Solver::MAX_NIMBER`].
+    fn with_nimbers_stats(game: Game, nimbers: Vec<Nimber>, stats: Self::Stats) -> Self;
+
     #[inline] fn new(game: Game) -> Self where Self: Sized, Self::Stats: Default {
         Self::with_stats(game, Default::default())
     }
@@ -19,6 +32,24 @@ pub trait Solver: Iterator<Item=u16> {
         Self::with_capacity_stats(game, capacity, Default::default())
     }
 
+    #[inline] fn with_nimbers(game: Game, nimbers: Vec<Nimber>) -> Self where Self: Sized, Self::Stats: Default {
+        Self::with_nimbers_stats(game, nimbers, Default::default())
+    }
+
+    /// Returns the solver that continues calculation after the game and the nimbers read from `input` (see [`crate::read_nimbers`]).
+    fn read_nimbers_stats<R: std::io::Read>(input: &mut R, stats: Self::Stats) -> std::io::Result<Self> where Self: Sized {
+        let (game, nimbers) = read_nimbers(input)?;
+        Ok(Self::with_nimbers_stats(game, nimbers, stats))
+    }
+
+    /// Writes the game and the nimbers calculated so far to `output` (see [`crate::write_nimbers`]).
+    fn write_nimbers<W: std::io::Write>(&self, output: &mut W) -> std::io::Result<()> {
+        write_nimbers(output, self.game(), self.nimbers())
+    }
+
+    /// Sets the number of threads used to calculate each nimber. Ignored by the solvers that always use one thread.
+    #[inline] fn set_threads(&mut self, _threads: usize) {}
+
     fn print_nimber_stat_to(&self, f: &mut dyn std::io::Write) -> std::io::Result<()>;
 
     fn print_nimber_stat(&self) -> std::io::Result<()> {
@@ -29,4 +60,9 @@ pub trait Solver: Iterator<Item=u16> {
     fn period(&self) -> Option<(usize, usize)> {
         self.game().period(self.nimbers())
     }
+
+    /// Try to calculates (pre-period, period, saltus) of the arithmetically periodic game using the nimbers calculated so far.
+    fn arithmetic_period(&self) -> Option<(usize, usize, Nimber)> {
+        self.game().arithmetic_period(self.nimbers())
+    }
 }
\ No newline at end of file
@@ -1,8 +1,11 @@
 use std::fmt::Display;
 
+use crate::Nimber;
+
 pub trait SolverEvent {
     #[inline] fn take_option(&mut self) {}
     #[inline] fn break_option(&mut self) {}
+    #[inline] fn break_options(&mut self, count: usize) { for _ in 0..count { self.break_option() } }
     #[inline] fn rebuilding_rc(&mut self, _nimbers_len: usize) {}
 }
 
@@ -19,6 +22,7 @@ pub struct SolverIterations {
 impl SolverEvent for SolverIterations {
     #[inline] fn take_option(&mut self) { self.taking += 1; }
     #[inline] fn break_option(&mut self) { self.breaking += 1; }
+    #[inline] fn break_options(&mut self, count: usize) { self.breaking += count; }
     #[inline] fn rebuilding_rc(&mut self, rebuilding_r_positions: usize) {
          self.rebuilding_rc += 1;
          self.rebuilding_r_positions += rebuilding_r_positions
@@ -35,33 +39,44 @@ impl Display for SolverIterations {
     }
 }
 
+#[derive(Default)]
 pub struct NimberStats {
-    pub occurences: [u32; 1<<16],
-    pub max: u16
-}
-
-impl Default for NimberStats {
-    fn default() -> Self { Self { occurences: [0; 1<<16], max: 0 } }
+    /// `occurences[nimber]` is the number of occurrences of the `nimber`, grown as larger nimbers are counted.
+    pub occurences: Vec<u32>,
+    pub max: Nimber
 }
 
 impl NimberStats {
-    pub fn count(&mut self, nimber: u16) {
+    /// Returns statistics of the given `nimbers`.
+    pub fn of(nimbers: impl IntoIterator<Item = Nimber>) -> Self {
+        let mut result = Self::default();
+        for n in nimbers { result.count(n) }
+        result
+    }
+
+    pub fn count(&mut self, nimber: Nimber) {
+        if self.occurences.len() <= nimber as usize { self.occurences.resize(nimber as usize + 1, 0); }
         self.occurences[nimber as usize] += 1;
         if nimber > self.max { self.max = nimber; }
     }
 
+    /// Returns the number of occurrences of the `nimber`.
+    #[inline] pub fn occurrences(&self, nimber: Nimber) -> u32 {
+        self.occurences.get(nimber as usize).copied().unwrap_or(0)
+    }
+
     /// Returns sorted vector of nimbers, from the most to the less commmon, skip chosen nimber
-    pub fn nimbers_from_most_common(&self, to_skip: u16) -> Vec<u16> {
+    pub fn nimbers_from_most_common(&self, to_skip: Nimber) -> Vec<Nimber> {
         let mut result = Vec::with_capacity(self.max as usize);
         if to_skip == 0 {
             for nimber in 1..=self.max {
-                if self.occurences[nimber as usize] != 0 {
+                if self.occurrences(nimber) != 0 {
                     result.push(nimber);
                 }
             }
         } else {
             for nimber in 0..=self.max {
-                if nimber != to_skip && self.occurences[nimber as usize] != 0 {
+                if nimber != to_skip && self.occurrences(nimber) != 0 {
                     result.push(nimber);
                 }
             }
@@ -76,7 +91,7 @@ impl Display for NimberStats {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         if f.sign_plus() {  // pairs:
             for nimber in (0..=self.max).step_by(2) {
-                let occ = (self.occurences[nimber as usize], self.occurences[nimber as usize + 1]);
+                let occ = (self.occurrences(nimber), self.occurrences(nimber + 1));
                 if occ.0 != 0 || occ.1 != 0 {
                     if nimber != 0 { write!(f, "\t")?; }
                     write!(f, "{:>2}:{}+{}", nimber>>1, occ.0, occ.1)?;
@@ -84,7 +99,7 @@ impl Display for NimberStats {
             }
         } else {
             for nimber in 0..=self.max {
-                let occ = self.occurences[nimber as usize];
+                let occ = self.occurrences(nimber);
                 if occ != 0 {
                     if nimber != 0 { write!(f, "\t")?; }
                     write!(f, "{:>2}:{}", nimber, occ)?;
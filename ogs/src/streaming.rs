@@ -0,0 +1,196 @@
+use crate::{BitSet, Game, Nimber, SolverEvent};
+
+/// Base of the polynomial hash of the nimbers in the window.
+const HASH_BASE: u64 = 0x100000001B3;
+
+/// The last nimbers calculated, along with their polynomial hash.
+#[derive(Clone)]
+struct Window {
+    /// The nimber of position `i` is at index `i % nimbers.len()`.
+    nimbers: Vec<Nimber>,
+    /// Number of nimbers calculated so far (the positions before 0 are considered to have nimber 0).
+    len: usize,
+    /// Polynomial hash (with base [`HASH_BASE`]) of the nimbers in the window, the latest one with the lowest power.
+    hash: u64
+}
+
+impl Window {
+    #[inline] fn new(size: usize) -> Self {
+        Self { nimbers: vec![0; size], len: 0, hash: 0 }
+    }
+
+    /// Returns the nimber of the given `position`, which must be included in the window.
+    #[inline] fn get(&self, position: usize) -> Nimber {
+        self.nimbers[position % self.nimbers.len()]
+    }
+
+    /// Appends `nimber` to the window, dropping the oldest one; `base_pow` is [`HASH_BASE`] to the power of the window size.
+    #[inline] fn push(&mut self, nimber: Nimber, base_pow: u64) {
+        let size = self.nimbers.len();
+        let slot = &mut self.nimbers[self.len % size];
+        self.hash = self.hash.wrapping_mul(HASH_BASE).wrapping_add(nimber as u64).wrapping_sub((*slot as u64).wrapping_mul(base_pow));
+        *slot = nimber;
+        self.len += 1;
+    }
+
+    /// Returns whether `self` and `other` (of the same size) include the same sequence of nimbers.
+    fn same_nimbers(&self, other: &Self) -> bool {
+        let size = self.nimbers.len();
+        self.hash == other.hash && (0..size).all(|k| self.nimbers[(self.len + k) % size] == other.nimbers[(other.len + k) % size])
+    }
+}
+
+/// Calculates nimbers of the games whose moves leave at most one heap (like subtraction games),
+/// keeping only the last nimbers, as many as the largest number of tokens a move can take.
+///
+/// Since the nimber of each heap depends only on the nimbers in the window,
+/// the memory used does not depend on the number of positions considered,
+/// and the nimbers are periodic as soon as the window repeats (see [`StreamingSolver::find_period`]).
+/// This allows to find the nimbers of arbitrarily large heaps (see [`StreamingSolver::periodic_position`]).
+pub struct StreamingSolver<S = ()> {
+    game: Game,
+    /// The largest number of tokens a move can take.
+    max_take: usize,
+    window: Window,
+    /// [`HASH_BASE`] to the power of the window size.
+    base_pow: u64,
+    /// Option set reused by each calculation.
+    /// It never gets full, as the nimbers of the options and their number do not exceed `max_take + 1`.
+    option_nimbers: Vec<u64>,
+    pub stats: S
+}
+
+impl<S: SolverEvent> StreamingSolver<S> {
+    /// Returns the solver of the `game`, or `None` if some move of the `game` can leave more than one heap or its code is infinite.
+    pub fn with_stats(game: Game, stats: S) -> Option<Self> {
+        if game.max_heaps() > 1 || !game.is_code_finite() { return None; }
+        let max_take = game.rules().len() - 1;
+        let size = max_take.max(1);
+        Some(Self { game, max_take, window: Window::new(size), base_pow: HASH_BASE.wrapping_pow(size as u32), option_nimbers: vec![0; (max_take + 1) / 64 + 1], stats })
+    }
+
+    /// Returns the solver of the `game`, or `None` if some move of the `game` can leave more than one heap or its code is infinite.
+    #[inline] pub fn new(game: Game) -> Option<Self> where S: Default {
+        Self::with_stats(game, Default::default())
+    }
+
+    #[inline] pub fn game(&self) -> &Game { &self.game }
+
+    /// Returns the number of nimbers calculated so far.
+    #[inline] pub fn positions(&self) -> usize { self.window.len }
+
+    /// Returns the number of the last nimbers kept.
+    #[inline] pub fn window_size(&self) -> usize { self.window.nimbers.len() }
+
+    /// Returns the nimber of the heap of size `n`, calculating the nimbers up to `n` if needed,
+    /// or `None` if the nimber has been already dropped from the window or some nimber up to `n` exceeds [`Nimber::MAX`].
+    pub fn nimber(&mut self, n: usize) -> Option<Nimber> {
+        if n + self.window_size() < self.positions() { return None; }
+        while self.positions() <= n { self.next()?; }
+        Some(self.window.get(n))
+    }
+
+    /// Continues the calculation until the period of the nimbers is found, but not beyond the first `limit` nimbers.
+    /// Returns (pre-period, period) or `None` if the `limit` is reached (or some nimber exceeds [`Nimber::MAX`]).
+    ///
+    /// The period is detected (by the Brent's algorithm) as the repetition of the window,
+    /// which proves it as the further nimbers depend only on the window.
+    /// Then the pre-period is calculated by comparing the nimbers of the positions that are the period apart.
+    pub fn find_period(&mut self, limit: usize) -> Option<(usize, usize)> {
+        // from this length on, the window determines all further nimbers
+        let determined = self.max_take + 1;
+        while self.positions() < determined {
+            if self.positions() >= limit { return None; }
+            self.next()?;
+        }
+        let mut tortoise = self.window.clone();
+        let (mut power, mut period) = (1, 1);
+        loop {
+            if self.positions() >= limit { return None; }
+            self.next()?;
+            if tortoise.same_nimbers(&self.window) { break; }
+            if period == power {
+                tortoise = self.window.clone();
+                power *= 2;
+                period = 0;
+            }
+            period += 1;
+        }
+        // the nimbers repeat (with the period) from the beginning of the window repeated
+        let repeated_from = tortoise.len - tortoise.nimbers.len();
+        let mut nimbers = StreamingSolver::<()>::new(self.game.clone()).unwrap();
+        let mut shifted = StreamingSolver::<()>::new(self.game.clone()).unwrap();
+        for _ in 0..period { shifted.next(); }
+        let mut preperiod = 0;
+        for position in 0..repeated_from {
+            if nimbers.next() != shifted.next() { preperiod = position + 1; }
+        }
+        Some((preperiod, period))
+    }
+
+    /// Returns the position with the same nimber as the heap of size `n`, which is smaller than `preperiod + period`.
+    #[inline] pub fn periodic_position(n: usize, preperiod: usize, period: usize) -> usize {
+        if n < preperiod { n } else { preperiod + (n - preperiod) % period }
+    }
+}
+
+impl<S: SolverEvent> Iterator for StreamingSolver<S> {
+    type Item = Nimber;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let n = self.positions();
+        let option_nimbers = &mut self.option_nimbers;
+        option_nimbers.fill(0);
+        if self.game.can_take_all(n) { option_nimbers.add_nimber(0) }
+        for t in &self.game.taking {
+            let t = *t;
+            if t >= n { break }
+            option_nimbers.add_nimber(self.window.get(n - t));
+            self.stats.take_option();
+        }
+        let result = option_nimbers.checked_mex()?;
+        self.window.push(result, self.base_pow);
+        Some(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+    use crate::{NaiveSolver, Solver};
+
+    fn naive_nimbers(game: &str, n: usize) -> Vec<Nimber> {
+        NaiveSolver::<()>::new(Game::from_str(game).unwrap()).take(n).collect()
+    }
+
+    #[test]
+    fn test_streaming_nimbers() {
+        for game in ["0.3033", "0.1323", "0.33", "0.0313", "0.", "0.[3]0[3]02", "S(2,5,7,11)"] {
+            let naive = naive_nimbers(game, 500);
+            let solver = StreamingSolver::<()>::new(Game::from_str(game).unwrap()).unwrap();
+            assert_eq!(solver.take(500).collect::<Vec<_>>(), naive, "{game}");
+        }
+        assert!(StreamingSolver::<()>::new(Game::from_str("0.07").unwrap()).is_none());
+        assert!(StreamingSolver::<()>::new(Game::from_str("0.(3)").unwrap()).is_none());
+    }
+
+    #[test]
+    fn test_streaming_period() {
+        for game in ["0.3033", "0.1323", "0.0313", "0.", "0.2", "S(2,5,7,11)", "0.30000000000000003"] {
+            let mut solver = StreamingSolver::<()>::new(Game::from_str(game).unwrap()).unwrap();
+            let (preperiod, period) = solver.find_period(100_000).unwrap();
+            let naive = naive_nimbers(game, preperiod + 3 * period + 50);
+            assert!(naive[preperiod..].iter().zip(&naive[preperiod+period..]).all(|(a, b)| a == b), "{game}");
+            assert!(preperiod == 0 || naive[preperiod-1] != naive[preperiod-1+period], "{game}");
+            assert!((1..period).all(|p| naive[preperiod..].iter().zip(&naive[preperiod+p..]).any(|(a, b)| a != b)), "{game}");
+            assert_eq!(solver.nimber(solver.positions() - 1), Some(naive[solver.positions() - 1]));
+        }
+        let mut solver = StreamingSolver::<()>::new(Game::from_str("S(1,3,4)").unwrap()).unwrap();
+        assert_eq!(solver.find_period(3), None);
+        assert_eq!(solver.find_period(100), Some((0, 7)));
+        assert_eq!(StreamingSolver::<()>::periodic_position(1_000_000_000_000, 0, 7), 1);
+        assert_eq!(solver.nimber(0), None);
+        assert_eq!(StreamingSolver::<()>::new(Game::from_str("S(1,3,4)").unwrap()).unwrap().nimber(1), Some(1));
+    }
+}
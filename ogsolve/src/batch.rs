@@ -0,0 +1,154 @@
+use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use ogs::{checksum, Game, Nimber, RCSolver, Solver};
+
+/// Returns all octal games 0.d1d2...dk with 1 <= k <= `max_digits` digits and last digit dk different from 0.
+pub fn all_octal_games(max_digits: u8) -> Vec<Game> {
+    let mut result = Vec::new();
+    let mut code = Vec::with_capacity(max_digits as usize);
+    for len in 1..=max_digits as usize {
+        code.clear();
+        code.resize(len, b'0');
+        'codes: loop {
+            if code[len-1] != b'0' {
+                let mut ascii = b"0.".to_vec();
+                ascii.extend_from_slice(&code);
+                result.push(Game::from_ascii(&ascii).unwrap());
+            }
+            for d in code.iter_mut().rev() {   // next code
+                if *d == b'7' { *d = b'0' } else { *d += 1; continue 'codes; }
+            }
+            break;
+        }
+    }
+    result
+}
+
+/// Returns the games read from the file with the given `path`, which lists one game code per line.
+/// Empty lines and lines beginning with `#` are ignored.
+pub fn games_from_file(path: &Path) -> Result<Vec<Game>, String> {
+    let file = std::fs::File::open(path).map_err(|e| format!("cannot open {}: {}", path.display(), e))?;
+    let mut result = Vec::new();
+    for (line_nr, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.map_err(|e| format!("cannot read {}: {}", path.display(), e))?;
+        let code = line.trim();
+        if code.is_empty() || code.starts_with('#') { continue; }
+        result.push(Game::from_str(code).map_err(|e| format!("{}:{}: {}", path.display(), line_nr+1, e))?);
+    }
+    Ok(result)
+}
+
+/// Result of solving a single game in the batch mode.
+struct GameResult {
+    /// Number of nimbers known, smaller than the bound if some nimber exceeds the largest supported one.
+    positions: usize,
+    /// Number of nimbers calculated, smaller than the bound if the period has been found earlier.
+    calculated: usize,
+    /// (pre-period, period, saltus) found, saltus is 0 for pure periods.
+    period: Option<(usize, usize, Nimber)>,
+    /// Maximum nimber of the positions up to the bound.
+    max_nimber: Nimber,
+    /// Checksum of the nimbers of the positions up to the bound.
+    checksum: u32,
+    /// Hash of the nimbers of the positions up to the bound, used to group the games with the same nimbers.
+    hash: u64
+}
+
+/// Solves the `game` up to the `last_position`, stopping earlier if the periodicity theorem proves the (arithmetic) period.
+/// In such a case, the remaining nimbers are obtained from the period.
+/// Both the calculation and the extension by the period stop at the first nimber that exceeds [`Nimber::MAX`].
+fn solve(game: Game, last_position: usize) -> GameResult {
+    let mut solver = RCSolver::<true>::with_capacity(game, last_position+1);
+    let mut period = None;
+    let mut next_period_check = 1;
+    while solver.nimbers().len() <= last_position {
+        if solver.next().is_none() { break; }
+        let len = solver.nimbers().len();
+        if len >= next_period_check {
+            next_period_check = len + len/16 + 1;
+            period = solver.period().map(|(preperiod, period)| (preperiod, period, 0))
+                .or_else(|| solver.arithmetic_period());
+            if period.is_some() { break; }
+        }
+    }
+    let calculated = solver.nimbers().len();
+    let mut nimbers = solver.nimbers().to_vec();
+    if let Some((_, period, saltus)) = period {
+        for i in calculated..=last_position {
+            let Some(nimber) = nimbers[i-period].checked_add(saltus) else { break };
+            nimbers.push(nimber);
+        }
+    }
+    let mut hasher = DefaultHasher::new();
+    nimbers.hash(&mut hasher);
+    GameResult { positions: nimbers.len(), calculated, period, max_nimber: *nimbers.iter().max().unwrap(), checksum: checksum(&nimbers), hash: hasher.finish() }
+}
+
+const BATCH_HEADER: &str = "game, positions, calculated, period, preperiod, saltus, max_nimber, settled, checksum, same_as";
+
+/// Solves all the `games` up to the `last_position`, using `threads` threads,
+/// and writes the results to the CSV file `output`.
+pub fn run(games: Vec<Game>, last_position: usize, threads: usize, output: &mut dyn Write) -> std::io::Result<()> {
+    let next = AtomicUsize::new(0);
+    let results: Mutex<Vec<Option<GameResult>>> = Mutex::new((0..games.len()).map(|_| None).collect());
+    std::thread::scope(|s| {
+        for _ in 0..threads.max(1) {
+            s.spawn(|| loop {
+                let i = next.fetch_add(1, Ordering::Relaxed);
+                if i >= games.len() { break; }
+                let result = solve(games[i].clone(), last_position);
+                results.lock().unwrap()[i] = Some(result);
+            });
+        }
+    });
+    let results = results.into_inner().unwrap();
+    writeln!(output, "{}", BATCH_HEADER)?;
+    let mut first_with_hash = HashMap::new();
+    let (mut settled, mut groups) = (0, 0);
+    for (game, result) in games.iter().zip(results) {
+        let result = result.unwrap();
+        let code = game.to_string();
+        let same_as = first_with_hash.entry((result.hash, result.checksum)).or_insert_with(|| { groups += 1; code.clone() });
+        let same_as = if *same_as == code { "" } else { same_as.as_str() };
+        let (period, preperiod, saltus) = if let Some((preperiod, period, saltus)) = result.period {
+            settled += 1;
+            (period.to_string(), preperiod.to_string(), saltus.to_string())
+        } else {
+            ("".to_owned(), "".to_owned(), "".to_owned())
+        };
+        writeln!(output, "{}, {}, {}, {}, {}, {}, {}, {}, {:X}, {}",
+            code, result.positions, result.calculated, period, preperiod, saltus, result.max_nimber,
+            result.period.is_some(), result.checksum, same_as)?;
+    }
+    println!("Solved {} games up to position {}: {} settled, {} different nimber sequences", games.len(), last_position, settled, groups);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_all_octal_games() {
+        let games: Vec<_> = all_octal_games(2).iter().map(|g| g.to_string()).collect();
+        assert_eq!(games.len(), 7 + 7*8);
+        assert_eq!(&games[..2], ["0.1", "0.2"]);
+        assert_eq!(&games[7..9], ["0.01", "0.02"]);
+        assert_eq!(games.last().unwrap(), "0.77");
+    }
+
+    #[test]
+    fn test_solve() {
+        let r = solve(Game::from_str("0.07").unwrap(), 1000);
+        assert_eq!(r.period, Some((53, 34, 0)));
+        assert!(r.calculated < 1000);
+        let full: Vec<Nimber> = RCSolver::<true>::new(Game::from_str("0.07").unwrap()).take(1001).collect();
+        assert_eq!(r.checksum, checksum(&full));
+    }
+}
@@ -1,7 +1,12 @@
-use std::{fmt::Display, fs::File, io::Write, time::Instant};
+use std::{fmt::Display, fs::File, io::Write, path::PathBuf, time::Instant};
 
-use clap::{Parser, ValueEnum, ArgAction};
-use ogs::{Game, NaiveSolver, RC2Solver, RCSolver, Solver, SolverIterations};
+mod batch;
+mod verify;
+
+use clap::{Parser, Subcommand, ValueEnum, ArgAction};
+use verify::{Verification, Verifier};
+use serde_json::{json, Value};
+use ogs::{checksum, checksum_of, load_nimbers, position_nimber, winning_moves, save_nimbers, Game, MisereSolver, NaiveSolver, Nimber, PackedRCSolver, RC2Solver, RCSolver, Solver, SolverIterations, StreamingSolver};
 
 #[derive(ValueEnum, Clone, Copy, Debug)]
 pub enum Method {
@@ -16,7 +21,15 @@ pub enum Method {
     /// RC2 with static moments of rebuilding the R/C split
     RC2S,
     /// Predict the number of iterations of naive methods without calculating nimbers
-    PredictNaive
+    PredictNaive,
+    /// RC keeping the nimbers compressed, for games whose moves leave at most two heaps:
+    /// takes less memory (e.g. 6-7 bits per nimber for 0.4 and 10-11 for 0.6), but is several times slower
+    Packed,
+    /// Streaming, for games whose moves leave at most one heap: keeps only the last nimbers and finds the period,
+    /// so -n can be arbitrarily large (like 1000000000000)
+    Stream,
+    /// Misère Grundy values calculated by searching sums of heaps (selected by --misere switch)
+    Misere
 }
 
 impl Display for Method {
@@ -27,15 +40,29 @@ impl Display for Method {
             Method::RCS => write!(f, "rcs"),
             Method::RC2 => write!(f, "rc2"),
             Method::RC2S => write!(f, "rc2s"),
+            Method::Misere => write!(f, "misere"),
+            Method::Packed => write!(f, "packed"),
+            Method::Stream => write!(f, "stream"),
         }
     }
 }
 
+#[derive(Subcommand)]
+pub enum Command {
+    /// Calculate the nimber of the position consisting of heaps of the given sizes and list all winning moves
+    Analyse {
+        /// Sizes of the heaps
+        #[arg(required = true)]
+        heaps: Vec<usize>
+    }
+}
+
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
 struct Conf {
-    /// Game to solve
-    game: Game,
+    /// Game to solve, given by its octal or hexadecimal code (e.g. 0.137, 4.F), optionally followed by restricted splits (e.g. 0.+u0 or grundy for Grundy's game)
+    #[arg(required_unless_present_any = ["all", "list"])]
+    game: Option<Game>,
 
     /// Method(s) of calculating nimbers
     #[arg(short='m', ignore_case = true, default_value="naive", action=ArgAction::Append, value_delimiter=',')]
@@ -56,16 +83,64 @@ struct Conf {
     /// Save the benchmark results to a file with the given name (ogsolve_benchmark.csv by default)
     #[arg(short='b', long="benchmark", num_args=0..=1, default_missing_value="ogsolve_benchmark.csv", value_name="FILE_NAME")]
     pub benchmark_filename: Option<String>,
-}
 
-/// Calculates checksum with fletcher 32 algorithm.
-fn checksum(nimbers: &[u16]) -> u32 {
-    let mut checksum = (0u16, 0u16);
-    for n in nimbers {
-        checksum.0 = checksum.0.wrapping_add(*n);
-        checksum.1 = checksum.1.wrapping_add(checksum.0);
-    }
-    ((checksum.1 as u32) << 16) | checksum.0 as u32
+    /// Calculate misère Grundy values (instead of nimbers) of the positions;
+    /// the method(s) given are ignored and the solver searches sums of heaps, so it is practical only for small positions
+    #[arg(long, default_value_t = false)]
+    pub misere: bool,
+
+    /// Number of misère Grundy values in the genus of each position printed (with --misere and -p switches)
+    #[arg(long, default_value_t = 3)]
+    pub genus_terms: u16,
+
+    /// Stop calculating nimbers as soon as the periodicity theorem proves the (arithmetic) period (-n gives the last position considered)
+    #[arg(long, default_value_t = false)]
+    pub until_period: bool,
+
+    /// Verify or refute the claim that the nimbers are periodic with the given pre-period and period
+    /// (-n gives the last position considered if the periodicity theorem cannot prove the claim)
+    #[arg(long, num_args=2, value_names=["PREPERIOD", "PERIOD"])]
+    pub claim: Option<Vec<usize>>,
+
+    /// Save the calculated nimbers (along with the game code and checksum) to the given file
+    #[arg(long, value_name="FILE_NAME")]
+    pub save: Option<PathBuf>,
+
+    /// Resume the calculation from the nimbers saved (by --save) in the given file
+    #[arg(long, value_name="FILE_NAME")]
+    pub resume: Option<PathBuf>,
+
+    /// Batch mode: solve (up to -n, by the RC method) all octal games 0.d1d2...dk with up to the given number k of digits
+    #[arg(long, value_name="DIGITS", conflicts_with="list")]
+    pub all: Option<u8>,
+
+    /// Batch mode: solve (up to -n, by the RC method) all games listed (one code per line) in the given file
+    #[arg(long, value_name="FILE_NAME")]
+    pub list: Option<PathBuf>,
+
+    /// Number of threads used by the naive and RC methods to calculate each nimber, or in the batch mode to solve many games (number of cores by default in the batch mode, 1 otherwise)
+    #[arg(short='t', long)]
+    pub threads: Option<usize>,
+
+    /// Verify the nimbers calculated by each method with the given verifier (naive by default), reporting the first divergence found;
+    /// the calculation time reported includes the verification (ignored with --misere)
+    #[arg(long, value_enum, num_args=0..=1, default_missing_value="naive", value_name="VERIFIER")]
+    pub verify: Option<Verifier>,
+
+    /// Number of random positions checked by the spot verifier (in addition to the last position)
+    #[arg(long, default_value_t = 1000, value_name="COUNT")]
+    pub spot_checks: usize,
+
+    /// Print the results (of all methods) as a single JSON document instead of the text
+    #[arg(long, default_value_t = false, conflicts_with_all = ["all", "list"])]
+    pub json: bool,
+
+    /// CSV file to which the batch mode writes the results
+    #[arg(long, default_value="ogsolve_batch.csv", value_name="FILE_NAME")]
+    pub csv: PathBuf,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
 }
 
 
@@ -79,34 +154,263 @@ fn csv_file(file_name: &str, header: &str) -> File {
 }
 
 //const BENCHMARK_FILENAME: &'static str = "ogsolve_benchmark";
+/// Reports that the nimber of the `position` exceeds `max_nimber`, the largest nimber supported by the `method`, and exits.
+fn exit_nimber_too_large(method: Method, position: usize, max_nimber: Nimber) -> ! {
+    eprintln!("The nimber of position {position} exceeds {max_nimber}, the largest nimber supported by the {method} method");
+    #[cfg(not(feature = "wide-nimbers"))]
+    eprintln!("Larger nimbers are supported by ogsolve built with the wide-nimbers feature (cargo install ogsolve --features wide-nimbers)");
+    std::process::exit(1)
+}
+
+/// Reports that the heap of size `position` exceeds the largest heap supported by the misère solver, and exits.
+fn exit_heap_too_large(position: usize) -> ! {
+    eprintln!("The heap of size {position} exceeds {}, the largest heap supported by the misère solver", MisereSolver::<()>::MAX_HEAP);
+    std::process::exit(1)
+}
+
 const BENCHMARK_HEADER: &'static str = "game, positions, method, checksum, period, preperiod, take_iter, break_iter, rc_effort, rc_rebuilds, time_micros, zeros_count";
 
 impl Conf {
+    /// Returns the game to solve (which is given if the batch mode is not used).
+    #[inline] fn game(&self) -> &Game {
+        self.game.as_ref().unwrap()
+    }
+
+    /// Runs the batch mode if it is selected. Returns `false` otherwise.
+    fn run_batch(&self) -> bool {
+        let games = match (self.all, &self.list) {
+            (Some(digits), _) => batch::all_octal_games(digits),
+            (None, Some(path)) => batch::games_from_file(path).unwrap_or_else(|e| {
+                eprintln!("Cannot read the list of games: {}", e);
+                std::process::exit(1)
+            }),
+            (None, None) => return false
+        };
+        let threads = self.threads.unwrap_or_else(|| std::thread::available_parallelism().map_or(1, |t| t.get()));
+        let mut file = std::io::BufWriter::new(File::create(&self.csv).unwrap_or_else(|e| {
+            eprintln!("Cannot create {}: {}", self.csv.display(), e);
+            std::process::exit(1)
+        }));
+        batch::run(games, self.position, threads, &mut file).and_then(|_| file.flush()).unwrap();
+        true
+    }
+
     fn predicted_naive_stats(&self) -> SolverIterations {
-        SolverIterations{ taking: self.game.taking_iters(self.position), breaking: self.game.breaking_naive_iters(self.position), ..Default::default() }
+        SolverIterations{ taking: self.game().taking_iters(self.position), breaking: self.game().breaking_naive_iters(self.position), ..Default::default() }
+    }
+
+    /// Returns the claimed (pre-period, period) given by the user.
+    fn claim(&self) -> Option<(usize, usize)> {
+        self.claim.as_ref().map(|c| (c[0], c[1]))
+    }
+
+    /// Returns the number of the first nimbers needed to prove the periodicity with given `preperiod` and `period`.
+    fn period_proof_len(&self, preperiod: usize, period: usize) -> Option<usize> {
+        if self.misere { None } else { self.game().period_proof_len(preperiod, period) }
+    }
+
+    /// Returns the number of the first nimbers needed to prove the arithmetic periodicity with given `preperiod` and `period`.
+    fn arithmetic_period_proof_len(&self, preperiod: usize, period: usize) -> Option<usize> {
+        if self.misere { None } else { self.game().arithmetic_period_proof_len(preperiod, period) }
+    }
+
+    /// Prints the proof that the nimbers are periodic (if `saltus` is 0) or arithmetically periodic.
+    fn print_period_proof(&self, preperiod: usize, period: usize, saltus: Nimber, proof_len: usize) {
+        let plus_saltus = if saltus == 0 { String::new() } else { format!(" + {saltus}") };
+        self.say(format_args!(" proof: nimber(i+{period}) = nimber(i){plus_saltus} for all {preperiod} <= i < {}, which by the periodicity theorem requires the first {proof_len} nimbers to be known",
+            proof_len - period));
+    }
+
+    /// Returns the nimbers loaded from the file given by --resume (reserving space for `capacity` nimbers),
+    /// or exits the process if they cannot be loaded or are calculated for another game.
+    fn resumed_nimbers(&self, capacity: usize) -> Option<Vec<Nimber>> {
+        let path = self.resume.as_ref()?;
+        match load_nimbers(path) {
+            Ok((game, mut nimbers)) if game.to_string() == self.game().to_string() => {
+                nimbers.reserve(capacity.saturating_sub(nimbers.len()));
+                Some(nimbers)
+            },
+            Ok((game, _)) => {
+                eprintln!("Cannot resume from {}: it contains nimbers of {} instead of {}", path.display(), game.to_string(), self.game().to_string());
+                std::process::exit(1)
+            },
+            Err(e) => {
+                eprintln!("Cannot resume from {}: {}", path.display(), e);
+                std::process::exit(1)
+            }
+        }
+    }
+
+    /// Prints (unless the JSON output is selected) the line given in the format of [`println`].
+    fn say(&self, line: std::fmt::Arguments) {
+        if !self.json { println!("{line}") }
     }
 
-    fn run<S: Solver<Stats = SolverIterations>>(&self, method: Method) /*where S::Stats: Default+Display*/ {
-        println!("Solving {} with {}:", self.game.to_string(), method);
-        let mut solver = S::with_capacity(self.game.clone(), self.position+1);
-        if self.print_nimbers { print!(" nimbers:") }
+    /// Calculates (by the RC method, or reads from the file given by --resume) the nimbers of the heaps
+    /// and prints the nimber of the position consisting of the `heaps` and all winning moves.
+    fn run_analyse(&self, heaps: &[usize]) {
+        let len = heaps.iter().max().unwrap() + 1;
+        let mut solver = match self.resumed_nimbers(len) {
+            Some(nimbers) => RCSolver::<true>::with_nimbers(self.game().clone(), nimbers),
+            None => RCSolver::<true>::with_capacity(self.game().clone(), len)
+        };
+        if let Some(threads) = self.threads { solver.set_threads(threads); }
+        while solver.nimbers().len() < len {
+            if solver.next().is_none() { exit_nimber_too_large(Method::RC, solver.nimbers().len(), RCSolver::<true>::MAX_NIMBER); }
+        }
+        let nimbers = solver.nimbers();
+        let nimber = position_nimber(nimbers, heaps).unwrap();
+        let moves = winning_moves(self.game(), nimbers, heaps).unwrap();
+        let position = heaps.iter().map(|h| h.to_string()).collect::<Vec<_>>().join(" ");
+        self.say(format_args!("Position {position} of {}: nimber {nimber}, {}", self.game().to_string(), if nimber == 0 { "losing" } else { "winning" }));
+        if !moves.is_empty() { self.say(format_args!("Winning moves:")); }
+        for m in &moves {
+            let leaving = if m.leaves.is_empty() { "nothing".to_owned() } else { m.leaves.iter().map(|h| h.to_string()).collect::<Vec<_>>().join(" + ") };
+            self.say(format_args!(" from heap {} (#{}): take {} leaving {leaving}", heaps[m.heap], m.heap+1, m.take));
+        }
+        if self.json {
+            let moves: Vec<_> = moves.iter().map(|m|
+                json!({"heap": m.heap, "size": heaps[m.heap], "take": m.take, "leaves": m.leaves})).collect();
+            println!("{}", json!({"game": self.game().to_string(), "heaps": heaps,
+                "nimber": nimber, "winning": nimber != 0, "winning_moves": moves}));
+        }
+    }
+
+    /// Solves the game with the given `method` and returns the results to include in the JSON output.
+    fn run<S: Solver<Stats = SolverIterations>>(&self, method: Method) -> Value /*where S::Stats: Default+Display*/ {
+        self.say(format_args!("Solving {} with {}:", self.game().to_string(), method));
+        let mut json = json!({"method": method.to_string()});
+        let claim = self.claim();
+        let claim_proof_len = claim.and_then(|(preperiod, period)| self.period_proof_len(preperiod, period));
+        let limit = claim_proof_len.unwrap_or(self.position+1);
+        let mut solver = match self.resumed_nimbers(limit) {
+            Some(nimbers) => {
+                if let Some(position) = nimbers.iter().position(|n| *n > S::MAX_NIMBER) {
+                    exit_nimber_too_large(method, position, S::MAX_NIMBER)
+                }
+                self.say(format_args!(" resumed from {} nimbers", nimbers.len()));
+                json["resumed_from"] = json!(nimbers.len());
+                S::with_nimbers(self.game().clone(), nimbers)
+            },
+            None => S::with_capacity(self.game().clone(), limit)
+        };
+        if let Some(threads) = self.threads { solver.set_threads(threads); }
+        let mut verification = if self.misere { None } else {
+            self.verify.map(|verifier| Verification::new(verifier, self.game(), self.threads, self.spot_checks))
+        };
+        let mut divergence = None;
+        let print_nimbers = self.print_nimbers && !self.json;
+        if print_nimbers { print!(" {}:", if self.misere { "misère Grundy values" } else { "nimbers" }) }
         let start_moment = Instant::now();
-        let mut zeros = 0;
-        for n in solver.by_ref().take(self.position+1) {
-            if self.print_nimbers { print!(" {}", n) }
+        let mut zeros = solver.nimbers().iter().filter(|n| **n == 0).count();
+        let mut period = None;
+        let mut arithmetic_period = None;
+        let mut refuted_at = None;
+        let mut next_period_check = 1;
+        while solver.nimbers().len() < limit {
+            let Some(n) = solver.next() else {
+                if self.misere { exit_heap_too_large(solver.nimbers().len()) }
+                exit_nimber_too_large(method, solver.nimbers().len(), S::MAX_NIMBER)
+            };
+            if print_nimbers { print!(" {}", n) }
             if n == 0 { zeros += 1 }
+            if let Some(ref mut verification) = verification {
+                divergence = verification.check_next(solver.nimbers());
+                if divergence.is_some() { break; }
+            }
+            let len = solver.nimbers().len();
+            if let Some((preperiod, p)) = claim {
+                if len > preperiod + p && n != solver.nimbers()[len-1-p] {
+                    refuted_at = Some(len-1);
+                    break;
+                }
+            }
+            if self.until_period && len >= next_period_check {
+                next_period_check = len + len/16 + 1;
+                period = solver.period();
+                if period.is_some() { break; }
+                arithmetic_period = solver.arithmetic_period();
+                if arithmetic_period.is_some() { break; }
+            }
         }
         let time = start_moment.elapsed();
-        if self.print_nimbers { println!() }
-        let period = solver.period();
+        if print_nimbers { println!() }
+        if self.print_nimbers && self.json {
+            json[if self.misere { "misere_grundy_values" } else { "nimbers" }] = json!(solver.nimbers());
+        }
+        if let Some(ref mut verification) = verification {
+            if divergence.is_none() { divergence = verification.check_final(self.game(), solver.nimbers()); }
+            let verifier = self.verify.unwrap();
+            let mut verification_json = json!({"verifier": verifier.to_string(),
+                "passed": divergence.is_none(), "checked": verification.checked()});
+            if let Some(ref divergence) = divergence {
+                self.say(format_args!(" verification by {verifier} method failed: {divergence}"));
+                verification_json["divergence"] = json!({"position": divergence.position, "found": divergence.found,
+                    "expected": divergence.expected, "options": divergence.options});
+            } else {
+                self.say(format_args!(" verification by {verifier} method passed: {} positions checked", verification.checked()));
+            }
+            json["verification"] = verification_json;
+        }
+        if period.is_none() && arithmetic_period.is_none() {
+            period = solver.period();
+            if period.is_none() { arithmetic_period = solver.arithmetic_period(); }
+        }
         if let Some((preperiod, period)) = period {
-            println!(" found period of length {period} and pre-period {preperiod}")
+            self.say(format_args!(" found period of length {period} and pre-period {preperiod}"));
+            let proof_len = self.period_proof_len(preperiod, period);
+            if let Some(proof_len) = proof_len {
+                self.print_period_proof(preperiod, period, 0, proof_len);
+            }
+            json["period"] = Self::period_json(preperiod, period, 0, proof_len);
+        } else if let Some((preperiod, period, saltus)) = arithmetic_period {
+            self.say(format_args!(" found arithmetic period of length {period}, pre-period {preperiod} and saltus {saltus}"));
+            let proof_len = self.arithmetic_period_proof_len(preperiod, period);
+            if let Some(proof_len) = proof_len {
+                self.print_period_proof(preperiod, period, saltus, proof_len);
+            }
+            json["period"] = Self::period_json(preperiod, period, saltus, proof_len);
+        } else {
+            if self.until_period {
+                self.say(format_args!(" period not found up to position {}", solver.nimbers().len()-1));
+            }
+            json["period"] = Value::Null;
+        }
+        if let Some((preperiod, p)) = claim {
+            let nimbers = solver.nimbers();
+            let mut claim_json = json!({"preperiod": preperiod, "period": p});
+            if let Some(i) = refuted_at {
+                self.say(format_args!(" claim refuted: nimber({i}) = {} differs from nimber({}) = {}", nimbers[i], i-p, nimbers[i-p]));
+                claim_json["result"] = json!("refuted");
+                claim_json["refuted_at"] = json!(i);
+            } else if let Some(proof_len) = claim_proof_len {
+                self.say(format_args!(" claim proven"));
+                self.print_period_proof(preperiod, p, 0, proof_len);
+                let not_shortest = preperiod > 0 && preperiod+p <= nimbers.len() && nimbers[preperiod-1] == nimbers[preperiod-1+p];
+                if not_shortest {
+                    self.say(format_args!(" note: the claimed pre-period is not the shortest"));
+                }
+                claim_json["result"] = json!("proven");
+                claim_json["proof_len"] = json!(proof_len);
+                claim_json["shortest_preperiod"] = json!(!not_shortest);
+            } else {
+                self.say(format_args!(" claim agrees with the nimbers up to position {}, but the periodicity theorem cannot prove it for this game", nimbers.len()-1));
+                claim_json["result"] = json!("unproven");
+            }
+            json["claim"] = claim_json;
+        }
+        if let Some(ref path) = self.save {
+            if let Err(e) = save_nimbers(path, solver.game(), solver.nimbers()) {
+                eprintln!("Cannot save nimbers to {}: {}", path.display(), e);
+            }
         }
         let checksum = checksum(solver.nimbers());
-        println!(" nimber of {}: {}  losing positions: {:.2}%  checksum: {:X}", self.position, solver.nimbers().last().unwrap(), 100.0 * zeros as f64 / solver.nimbers().len() as f64, checksum);
+        let position = solver.nimbers().len()-1;
+        let losing = 100.0 * zeros as f64 / solver.nimbers().len() as f64;
+        self.say(format_args!(" {} of {}: {}  losing positions: {:.2}%  checksum: {:X}", if self.misere { "misère Grundy value" } else { "nimber" }, position, solver.nimbers().last().unwrap(), losing, checksum));
         let stats = solver.stats();
-        println!(" iterations:  {stats}\n calculation time: {time:#.2?}");
-        if self.print_stats { solver.print_nimber_stat().unwrap(); }
+        self.say(format_args!(" iterations:  {stats}\n calculation time: {time:#.2?}"));
+        if self.print_stats && !self.json { solver.print_nimber_stat().unwrap(); }
         if let Some(ref filename) = self.benchmark_filename {
             let (p, pp) = if let Some((preperiod, period)) = period {
                 (period.to_string(), preperiod.to_string())
@@ -114,31 +418,154 @@ impl Conf {
                 ("".to_owned(), "".to_owned())
             };
             writeln!(csv_file(&filename, BENCHMARK_HEADER), "{}, {}, {}, {:X}, {}, {}, {}, {}, {}, {}, {}, {}",
-                solver.game().to_string(), self.position, method, checksum, p, pp,
+                solver.game().to_string(), position, method, checksum, p, pp,
                 stats.taking, stats.breaking, stats.rebuilding_r_positions, stats.rebuilding_rc, time.as_micros(), zeros).unwrap();
         }
-    }    
+        json["position"] = json!(position);
+        json[if self.misere { "misere_grundy_value" } else { "nimber" }] = json!(solver.nimbers().last().unwrap());
+        json["max_nimber"] = json!(solver.nimbers().iter().max().unwrap());
+        json["losing_positions_percent"] = json!(losing);
+        json["checksum"] = json!(format!("{checksum:X}"));
+        json["iterations"] = Self::iterations_json(stats);
+        json["time_secs"] = json!(time.as_secs_f64());
+        json
+    }
+
+    /// Returns JSON description of the (arithmetic if `saltus` is not 0) period.
+    fn period_json(preperiod: usize, period: usize, saltus: Nimber, proof_len: Option<usize>) -> Value {
+        json!({"preperiod": preperiod, "period": period, "saltus": saltus, "proof_len": proof_len})
+    }
+
+    /// Returns JSON description of the solver iterations.
+    fn iterations_json(stats: &SolverIterations) -> Value {
+        json!({"taking": stats.taking, "breaking": stats.breaking, "total": stats.taking + stats.breaking,
+            "rc_effort": stats.rebuilding_r_positions, "rc_rebuilds": stats.rebuilding_rc})
+    }
+
+    /// Solves the game by the streaming solver, which finds the period (searching up to -n)
+    /// and then the nimber of the position -n, and returns the results to include in the JSON output.
+    fn run_stream(&self) -> Value {
+        self.say(format_args!("Solving {} with {}:", self.game().to_string(), Method::Stream));
+        let Some(mut solver) = StreamingSolver::<SolverIterations>::new(self.game().clone()) else {
+            eprintln!("The stream method supports only games with finite codes, whose moves leave at most one heap");
+            std::process::exit(1)
+        };
+        let mut json = json!({"method": Method::Stream.to_string(), "window": solver.window_size()});
+        let start_moment = Instant::now();
+        let period = solver.find_period(self.position + 1);
+        let proof_len = solver.positions();
+        let nimber = match period {
+            Some((preperiod, period)) => {
+                let position = StreamingSolver::<()>::periodic_position(self.position, preperiod, period);
+                solver.nimber(position).or_else(|| StreamingSolver::<()>::new(self.game().clone()).unwrap().nimber(position))
+            },
+            None => solver.nimber(self.position)
+        };
+        let Some(nimber) = nimber else { exit_nimber_too_large(Method::Stream, solver.positions(), Nimber::MAX) };
+        let time = start_moment.elapsed();
+        if let Some((preperiod, period)) = period {
+            self.say(format_args!(" found period of length {period} and pre-period {preperiod}"));
+            self.say(format_args!(" proof: the last {} nimbers repeat within the first {proof_len} nimbers, and determine all further nimbers", solver.window_size()));
+            json["period"] = Self::period_json(preperiod, period, 0, Some(proof_len));
+        } else {
+            self.say(format_args!(" period not found up to position {}", self.position));
+            json["period"] = Value::Null;
+        }
+        self.say(format_args!(" nimber of {}: {nimber}", self.position));
+        let stats = &solver.stats;
+        self.say(format_args!(" iterations:  {stats}\n calculation time: {time:#.2?}"));
+        json["position"] = json!(self.position);
+        json["nimber"] = json!(nimber);
+        json["iterations"] = Self::iterations_json(stats);
+        json["time_secs"] = json!(time.as_secs_f64());
+        json
+    }
+
+    /// Solves the game up to the position -n by the RC solver that keeps the nimbers compressed,
+    /// and returns the results to include in the JSON output.
+    fn run_packed(&self) -> Value {
+        self.say(format_args!("Solving {} with {}:", self.game().to_string(), Method::Packed));
+        let Some(mut solver) = PackedRCSolver::<SolverIterations>::new(self.game().clone()) else {
+            eprintln!("The packed method supports only games whose moves leave at most two heaps, without restricted breaking moves");
+            std::process::exit(1)
+        };
+        let start_moment = Instant::now();
+        let zeros = solver.by_ref().take(self.position + 1).filter(|n| *n == 0).count();
+        let time = start_moment.elapsed();
+        let nimbers = solver.nimbers();
+        if nimbers.len() <= self.position { exit_nimber_too_large(Method::Packed, nimbers.len(), Nimber::MAX); }
+        let (nimber, checksum) = (nimbers.get(self.position), checksum_of(nimbers.iter()));
+        let losing = 100.0 * zeros as f64 / nimbers.len() as f64;
+        let (bytes, rare) = (nimbers.size_bytes(), nimbers.rare());
+        self.say(format_args!(" nimber of {}: {nimber}  losing positions: {losing:.2}%  checksum: {checksum:X}", self.position));
+        self.say(format_args!(" memory: {bytes} bytes ({:.2} bits per nimber), {rare} rare nimbers stored explicitly", 8.0 * bytes as f64 / nimbers.len() as f64));
+        let stats = &solver.stats;
+        self.say(format_args!(" iterations:  {stats}\n calculation time: {time:#.2?}"));
+        if self.print_stats && !self.json { solver.print_nimber_stat_to(&mut std::io::stdout()).unwrap(); }
+        json!({"method": Method::Packed.to_string(),
+            "position": self.position, "nimber": nimber,
+            "losing_positions_percent": losing,
+            "checksum": format!("{checksum:X}"),
+            "memory_bytes": bytes, "rare_nimbers": rare,
+            "iterations": Self::iterations_json(stats),
+            "time_secs": time.as_secs_f64()})
+    }
+
+    fn run_misere(&self) -> Value {
+        let mut json = self.run::<MisereSolver<SolverIterations>>(Method::Misere);
+        if self.print_nimbers {
+            let mut solver = MisereSolver::<()>::new(self.game().clone());
+            let genera: Vec<String> = (0..=self.position).map(|n| solver.genus(n as u16, self.genus_terms).to_string()).collect();
+            if self.json {
+                json["genera"] = json!(genera);
+            } else {
+                println!(" genera: {}", genera.join(" "));
+            }
+        }
+        json
+    }
 }
 
 fn main() {
     let conf: Conf = Conf::parse();
+    if conf.run_batch() { return; }
+    if let Some(Command::Analyse { ref heaps }) = conf.command {
+        conf.run_analyse(heaps);
+        return;
+    }
+    let mut json = json!({"game": conf.game().to_string(), "position": conf.position});
+    if conf.misere {
+        json["misere"] = json!(true);
+        json["results"] = json!([conf.run_misere()]);
+        if conf.json { println!("{json}"); }
+        return;
+    }
     let naive_iters = conf.predicted_naive_stats();
-    println!("Predicted number of naive iterations to solve {}:\n {}", conf.game.to_string(), naive_iters);
+    conf.say(format_args!("Predicted number of naive iterations to solve {}:\n {}", conf.game().to_string(), naive_iters));
+    json["predicted_naive_iterations"] = Conf::iterations_json(&naive_iters);
+    let mut results = Vec::new();
     for method in conf.method.iter().copied() {
         match method {
-            Method::Naive => conf.run::<NaiveSolver<SolverIterations>>(method),
-            Method::RC => conf.run::<RCSolver<true, SolverIterations>>(method),
-            Method::RCS => conf.run::<RCSolver<false, SolverIterations>>(method),
-            Method::RC2 => conf.run::<RC2Solver<true, SolverIterations>>(method),
-            Method::RC2S => conf.run::<RC2Solver<false, SolverIterations>>(method),
+            Method::Naive => results.push(conf.run::<NaiveSolver<SolverIterations>>(method)),
+            Method::RC => results.push(conf.run::<RCSolver<true, SolverIterations>>(method)),
+            Method::RCS => results.push(conf.run::<RCSolver<false, SolverIterations>>(method)),
+            Method::RC2 => results.push(conf.run::<RC2Solver<true, SolverIterations>>(method)),
+            Method::RC2S => results.push(conf.run::<RC2Solver<false, SolverIterations>>(method)),
+            Method::Misere => results.push(conf.run_misere()),
+            Method::Packed => results.push(conf.run_packed()),
+            Method::Stream => results.push(conf.run_stream()),
             Method::PredictNaive => {
                 if let Some(ref filename) = conf.benchmark_filename {
                     writeln!(csv_file(&filename, BENCHMARK_HEADER), "{}, {}, {}, {}, {}, {}, {}, {}, {}, {}, {}, {}",
-                        conf.game.to_string(), conf.position, method, "", "", "",
+                        conf.game().to_string(), conf.position, method, "", "", "",
                         naive_iters.taking, naive_iters.breaking, naive_iters.rebuilding_r_positions, naive_iters.rebuilding_rc,
                         "", "").unwrap();
                 }
             },
         }
     }
-}
\ No newline at end of file
+    if conf.json {
+        json["results"] = json!(results);
+        println!("{json}");
+    }
+}
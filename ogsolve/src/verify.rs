@@ -0,0 +1,157 @@
+use std::fmt::Display;
+use std::hash::{BuildHasher, RandomState};
+
+use clap::ValueEnum;
+use ogs::{Game, NaiveSolver, Nimber, Solver};
+
+/// Method of verifying the nimbers calculated.
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum Verifier {
+    /// Calculate all nimbers independently by the naive method, alongside the verified method
+    Naive,
+    /// Check the nimbers of randomly selected positions (and the last one) by enumerating their options
+    Spot
+}
+
+impl Display for Verifier {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            Verifier::Naive => write!(f, "naive"),
+            Verifier::Spot => write!(f, "spot"),
+        }
+    }
+}
+
+/// The first position (among the checked ones) whose nimber is incorrect.
+pub struct Divergence {
+    /// The position.
+    pub position: usize,
+    /// Nimber of the position calculated by the verified method.
+    pub found: Nimber,
+    /// Nimber of the position calculated by the verifier.
+    pub expected: Nimber,
+    /// Nimbers of the options of the position, whose mex is `expected`.
+    pub options: Vec<Nimber>
+}
+
+impl Display for Divergence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "nimber({}) = {} differs from {}, the mex of the option nimbers:", self.position, self.found, self.expected)?;
+        for o in &self.options { write!(f, " {o}")?; }
+        Ok(())
+    }
+}
+
+/// Returns the divergence at the `position` if its nimber differs from the mex of its options,
+/// calculated from `nimbers` of the smaller positions.
+fn check_position(game: &Game, nimbers: &[Nimber], position: usize) -> Option<Divergence> {
+    let options = game.option_nimbers(&nimbers[..position]);
+    let expected = options.iter().enumerate().find(|(i, o)| *i as Nimber != **o).map_or(options.len(), |(i, _)| i) as Nimber;
+    (expected != nimbers[position]).then(|| Divergence { position, found: nimbers[position], expected, options })
+}
+
+/// Verifies the nimbers calculated by a solver.
+pub enum Verification {
+    /// Reference naive solver, which calculates nimbers alongside the verified solver.
+    Naive(Box<NaiveSolver>),
+    /// Number of random positions to check at the end of calculation, and the number of positions checked.
+    Spot { checks: usize, checked: usize }
+}
+
+impl Verification {
+    /// Returns the verification by the given `verifier`.
+    /// The naive one uses given number of `threads`, the spot one checks `spot_checks` random positions.
+    pub fn new(verifier: Verifier, game: &Game, threads: Option<usize>, spot_checks: usize) -> Self {
+        match verifier {
+            Verifier::Naive => Self::Naive(Box::new(NaiveSolver::new(game.clone()).with_threads(threads.unwrap_or(1)))),
+            Verifier::Spot => Self::Spot { checks: spot_checks, checked: 0 }
+        }
+    }
+
+    /// Checks the `nimbers` calculated so far (not checked yet) if the verifier follows the calculation.
+    pub fn check_next(&mut self, nimbers: &[Nimber]) -> Option<Divergence> {
+        let Self::Naive(reference) = self else { return None; };
+        while reference.nimbers().len() < nimbers.len() {
+            let position = reference.nimbers().len();
+            let expected = reference.next().unwrap();
+            if expected != nimbers[position] {
+                return Some(Divergence { position, found: nimbers[position], expected, options: reference.game().option_nimbers(&nimbers[..position]) });
+            }
+        }
+        None
+    }
+
+    /// Checks the `nimbers` of the `game` at the end of calculation.
+    pub fn check_final(&mut self, game: &Game, nimbers: &[Nimber]) -> Option<Divergence> {
+        match self {
+            Self::Naive(_) => self.check_next(nimbers),
+            Self::Spot { checks, checked } => {
+                let positions = random_positions(nimbers.len(), *checks, RandomState::new().hash_one(nimbers.len()));
+                *checked = positions.len();
+                spot_check(game, nimbers, positions)
+            }
+        }
+    }
+
+    /// Returns the number of positions checked by the verifier.
+    pub fn checked(&self) -> usize {
+        match self {
+            Self::Naive(reference) => reference.nimbers().len(),
+            Self::Spot { checked, .. } => *checked
+        }
+    }
+}
+
+/// Returns (in increasing order, without repetitions) the last of `len` positions and up to `count` others,
+/// selected pseudo-randomly with the given `seed`.
+fn random_positions(len: usize, count: usize, mut seed: u64) -> Vec<usize> {
+    if len == 0 { return Vec::new(); }
+    if count + 1 >= len { return (0..len).collect(); }
+    let mut result: Vec<usize> = (0..count).map(|_| {
+        // splitmix64
+        seed = seed.wrapping_add(0x9E3779B97F4A7C15);
+        let mut z = seed;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+        ((z ^ (z >> 31)) % len as u64) as usize
+    }).collect();
+    result.push(len - 1);
+    result.sort_unstable();
+    result.dedup();
+    result
+}
+
+/// Returns the first divergence among the `positions` of the `nimbers` of the `game`.
+fn spot_check(game: &Game, nimbers: &[Nimber], positions: Vec<usize>) -> Option<Divergence> {
+    positions.into_iter().find_map(|position| check_position(game, nimbers, position))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_random_positions() {
+        let positions = random_positions(1000, 20, 7);
+        assert!(positions.len() <= 21);
+        assert_eq!(positions.last(), Some(&999));
+        assert!(positions.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(random_positions(5, 20, 7), [0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_verification() {
+        let game = Game::from_str("0.07").unwrap();
+        let mut nimbers: Vec<Nimber> = NaiveSolver::<()>::new(game.clone()).take(300).collect();
+        assert!(Verification::new(Verifier::Naive, &game, None, 0).check_final(&game, &nimbers).is_none());
+        assert!(Verification::new(Verifier::Spot, &game, None, 1000).check_final(&game, &nimbers).is_none());
+        nimbers[100] ^= 1;
+        nimbers[200] ^= 1;
+        let divergence = Verification::new(Verifier::Naive, &game, None, 0).check_final(&game, &nimbers).unwrap();
+        assert_eq!((divergence.position, divergence.found ^ 1), (100, divergence.expected));
+        let divergence = Verification::new(Verifier::Spot, &game, None, 1000).check_final(&game, &nimbers).unwrap();
+        assert_eq!(divergence.position, 100);
+        assert!(!divergence.options.contains(&divergence.expected));
+    }
+}